@@ -1,12 +1,169 @@
 use base64::{engine::general_purpose::STANDARD, Engine};
+use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
 use tauri::{AppHandle, Emitter, Manager};
 use walkdir::WalkDir;
 
+// Error returned by commands, serialized as `{ kind, message }` so the UI can tell
+// a missing file from a permission problem without matching on message text
+#[derive(Debug, Serialize, Clone, thiserror::Error)]
+#[serde(tag = "kind", content = "message", rename_all = "snake_case")]
+pub enum AzimuthError {
+    #[error("{0}")]
+    Io(String),
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    AlreadyExists(String),
+    #[error("{0}")]
+    PermissionDenied(String),
+    #[error("{0}")]
+    InvalidPath(String),
+    #[error("{0}")]
+    InvalidInput(String),
+    #[error("{0}")]
+    Serde(String),
+    #[error("{0}")]
+    Network(String),
+    #[error("{0}")]
+    Other(String),
+}
+
+impl AzimuthError {
+    // Keeps an io error's category, with `context` ahead of its message
+    fn io_context(e: impl Into<std::io::Error>, context: impl std::fmt::Display) -> Self {
+        let e = e.into();
+        std::io::Error::new(e.kind(), format!("{}: {}", context, e)).into()
+    }
+}
+
+impl From<std::io::Error> for AzimuthError {
+    fn from(e: std::io::Error) -> Self {
+        match e.kind() {
+            std::io::ErrorKind::NotFound => AzimuthError::NotFound(e.to_string()),
+            std::io::ErrorKind::AlreadyExists => AzimuthError::AlreadyExists(e.to_string()),
+            std::io::ErrorKind::PermissionDenied => AzimuthError::PermissionDenied(e.to_string()),
+            _ => AzimuthError::Io(e.to_string()),
+        }
+    }
+}
+
+impl From<serde_json::Error> for AzimuthError {
+    fn from(e: serde_json::Error) -> Self {
+        AzimuthError::Serde(e.to_string())
+    }
+}
+
+impl From<serde_yaml::Error> for AzimuthError {
+    fn from(e: serde_yaml::Error) -> Self {
+        AzimuthError::Serde(e.to_string())
+    }
+}
+
+impl From<reqwest::Error> for AzimuthError {
+    fn from(e: reqwest::Error) -> Self {
+        AzimuthError::Network(e.to_string())
+    }
+}
+
+impl<E, R> From<aws_sdk_s3::error::SdkError<E, R>> for AzimuthError
+where
+    aws_sdk_s3::error::SdkError<E, R>: std::fmt::Display,
+{
+    fn from(e: aws_sdk_s3::error::SdkError<E, R>) -> Self {
+        AzimuthError::Network(e.to_string())
+    }
+}
+
+impl From<zip::result::ZipError> for AzimuthError {
+    fn from(e: zip::result::ZipError) -> Self {
+        match e {
+            zip::result::ZipError::Io(e) => e.into(),
+            e => AzimuthError::InvalidInput(e.to_string()),
+        }
+    }
+}
+
+impl From<git2::Error> for AzimuthError {
+    fn from(e: git2::Error) -> Self {
+        match (e.code(), e.class()) {
+            (git2::ErrorCode::NotFound, _) => AzimuthError::NotFound(e.to_string()),
+            (git2::ErrorCode::Exists, _) => AzimuthError::AlreadyExists(e.to_string()),
+            (_, git2::ErrorClass::Net | git2::ErrorClass::Http | git2::ErrorClass::Ssh) => AzimuthError::Network(e.to_string()),
+            _ => AzimuthError::Other(e.to_string()),
+        }
+    }
+}
+
+// ssh2 maps SFTP status codes (no such file, permission denied) to io kinds
+impl From<ssh2::Error> for AzimuthError {
+    fn from(e: ssh2::Error) -> Self {
+        std::io::Error::from(e).into()
+    }
+}
+
+impl From<notify::Error> for AzimuthError {
+    fn from(e: notify::Error) -> Self {
+        match e.kind {
+            notify::ErrorKind::Io(e) => e.into(),
+            kind => AzimuthError::Other(notify::Error::new(kind).to_string()),
+        }
+    }
+}
+
+impl From<image::ImageError> for AzimuthError {
+    fn from(e: image::ImageError) -> Self {
+        match e {
+            image::ImageError::IoError(e) => e.into(),
+            e => AzimuthError::InvalidInput(e.to_string()),
+        }
+    }
+}
+
+// Only user-supplied search patterns can fail to compile
+impl From<regex::Error> for AzimuthError {
+    fn from(e: regex::Error) -> Self {
+        AzimuthError::InvalidInput(e.to_string())
+    }
+}
+
+impl<T> From<std::sync::PoisonError<T>> for AzimuthError {
+    fn from(e: std::sync::PoisonError<T>) -> Self {
+        AzimuthError::Other(e.to_string())
+    }
+}
+
+impl From<tokio::task::JoinError> for AzimuthError {
+    fn from(e: tokio::task::JoinError) -> Self {
+        AzimuthError::Other(e.to_string())
+    }
+}
+
+// Bare messages, as from `ok_or("...")?`, carry no category
+impl From<String> for AzimuthError {
+    fn from(message: String) -> Self {
+        AzimuthError::Other(message)
+    }
+}
+
+impl From<&str> for AzimuthError {
+    fn from(message: &str) -> Self {
+        AzimuthError::Other(message.to_string())
+    }
+}
+
+impl From<AzimuthError> for String {
+    fn from(e: AzimuthError) -> Self {
+        e.to_string()
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Note {
     pub id: String,
@@ -15,6 +172,90 @@ pub struct Note {
     pub folder: String,
     pub created_at: String,
     pub updated_at: String,
+    // Parsed from a leading YAML block, which `content` leaves out
+    #[serde(default)]
+    pub frontmatter: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum NoteContent {
+    Text { content: String },
+    Binary { size: u64, hex_preview: String },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchMode {
+    #[default]
+    Substring,
+    Regex,
+    WholeWord,
+    // Terms combined with AND (the default between terms), OR and NOT
+    Boolean,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TagMatch {
+    // Note must carry every requested tag
+    #[default]
+    All,
+    // Note must carry at least one requested tag
+    Any,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct SearchOptions {
+    pub mode: SearchMode,
+    // Notebook folder to search instead of the whole vault
+    pub scope_path: Option<String>,
+    // Only scan files with these extensions ("md" or ".md"); all text files when unset
+    pub extensions: Option<Vec<String>>,
+    // Only search notes tagged with these, combined according to `tag_match`
+    pub tags: Vec<String>,
+    pub tag_match: TagMatch,
+    // Match against the note's readable text, ignoring link targets, URLs, HTML and fences
+    pub plain_text_only: bool,
+    // Page of the sorted results to return; all of them when unset
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+    // Also include notes whose file name fuzzily matches the query
+    pub fuzzy: bool,
+    // Substring and whole-word searches ignore case unless set. Regex patterns are
+    // used as written; add (?i) to ignore case.
+    pub case_sensitive: bool,
+    // Only scan files last modified within this range (RFC 3339, inclusive)
+    pub modified_after: Option<String>,
+    pub modified_before: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReplaceChange {
+    pub path: String,
+    pub occurrences: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReplaceSummary {
+    pub files_changed: usize,
+    pub occurrences: usize,
+    pub dry_run: bool,
+    pub changes: Vec<ReplaceChange>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SearchHistoryEntry {
+    pub query: String,
+    pub searched_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SearchPage {
+    pub results: Vec<SearchResult>,
+    // Number of matching notes before `limit` and `offset` were applied
+    pub total_matches: usize,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -24,7 +265,26 @@ pub struct SearchResult {
     pub notebook_path: String,
     pub notebook_name: String,
     pub snippet: String,
+    // Context around each of the first few matches, overlapping windows merged
+    pub snippets: Vec<String>,
     pub match_count: usize,
+    // 1-based line of the first content match (1 when only the file name matched)
+    pub line_number: usize,
+    // Fuzzy file name score, only set for fuzzy searches that matched the name
+    pub fuzzy_score: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExternalLink {
+    pub url: String,
+    pub notes: Vec<String>,
+}
+
+// A `[[wikilink]]` from a note, with the note it resolves to when one exists
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OutgoingLink {
+    pub target: String,
+    pub path: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -41,6 +301,13 @@ pub struct SyncConfig {
     pub enabled: bool,
     pub credentials: serde_json::Value,
     pub last_sync: Option<String>,
+    // Delete files on one side when they were removed from the other since the last sync
+    #[serde(default)]
+    pub propagate_deletions: bool,
+    // Approximate cap on each upload and download, in bytes per second. iCloud Drive
+    // syncs are local copies and aren't capped.
+    #[serde(default)]
+    pub max_bytes_per_sec: Option<u64>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -67,8 +334,36 @@ pub struct AppSettings {
     pub notebook_styles: HashMap<String, NotebookStyle>,
     #[serde(default)]
     pub pinned_folders: Vec<String>,
+    // Notes kept at the top of their notebook's list, unlike favorites which are bookmarks
+    #[serde(default)]
+    pub pinned_notes: Vec<String>,
     #[serde(default = "default_auto_save")]
     pub auto_save: bool,
+    #[serde(default)]
+    pub note_reminders: HashMap<String, String>,
+    // Keyed by the note's stable `azimuth_id` rather than its path
+    #[serde(default)]
+    pub note_metadata: HashMap<String, HashMap<String, serde_json::Value>>,
+    // Archived notebooks, relative to the notes folder (e.g. "Archive/Projects/Launch")
+    #[serde(default)]
+    pub archived: Vec<String>,
+    // Leave archived notebooks out of search, the notebook list and sync
+    #[serde(default = "default_exclude_archived")]
+    pub exclude_archived: bool,
+    #[serde(default = "default_ignored_dirs")]
+    pub ignored_dirs: Vec<String>,
+    #[serde(default = "default_max_notebooks")]
+    pub max_notebooks: usize,
+    #[serde(default = "default_max_entries_to_scan")]
+    pub max_entries_to_scan: usize,
+    #[serde(default = "default_trash_retention_days")]
+    pub trash_retention_days: u32,
+    // Largest attachment save_attachment will write; no limit when unset
+    #[serde(default)]
+    pub max_attachment_bytes: Option<u64>,
+    // "light", "dark" or "system"
+    #[serde(default = "default_theme")]
+    pub theme: String,
 }
 
 fn default_ui_font_family() -> String {
@@ -87,6 +382,30 @@ fn default_auto_save() -> bool {
     true
 }
 
+fn default_theme() -> String {
+    "system".to_string()
+}
+
+fn default_exclude_archived() -> bool {
+    true
+}
+
+fn default_ignored_dirs() -> Vec<String> {
+    IGNORED_DIRS.iter().map(|dir| dir.to_string()).collect()
+}
+
+fn default_max_notebooks() -> usize {
+    MAX_NOTEBOOKS
+}
+
+fn default_max_entries_to_scan() -> usize {
+    MAX_ENTRIES_TO_SCAN
+}
+
+fn default_trash_retention_days() -> u32 {
+    30
+}
+
 impl Default for AppSettings {
     fn default() -> Self {
         Self {
@@ -101,9 +420,59 @@ impl Default for AppSettings {
             tags: HashMap::new(),
             notebook_styles: HashMap::new(),
             pinned_folders: Vec::new(),
+            pinned_notes: Vec::new(),
             auto_save: true,
+            note_reminders: HashMap::new(),
+            note_metadata: HashMap::new(),
+            archived: Vec::new(),
+            exclude_archived: true,
+            ignored_dirs: default_ignored_dirs(),
+            max_notebooks: MAX_NOTEBOOKS,
+            max_entries_to_scan: MAX_ENTRIES_TO_SCAN,
+            trash_retention_days: default_trash_retention_days(),
+            max_attachment_bytes: None,
+            theme: default_theme(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScanSettings {
+    pub ignored_dirs: Vec<String>,
+    pub max_notebooks: usize,
+    pub max_entries_to_scan: usize,
+}
+
+impl ScanSettings {
+    fn from_settings(settings: &AppSettings) -> Self {
+        Self {
+            ignored_dirs: settings.ignored_dirs.clone(),
+            max_notebooks: settings.max_notebooks,
+            max_entries_to_scan: settings.max_entries_to_scan,
         }
     }
+
+    fn is_ignored(&self, name: &str) -> bool {
+        self.ignored_dirs.iter().any(|dir| dir == name)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FavoriteToggle {
+    pub is_favorite: bool,
+    pub favorites: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PinToggle {
+    pub is_pinned: bool,
+    pub pinned_notes: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DueReminder {
+    pub note_path: String,
+    pub due: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -115,6 +484,27 @@ pub struct SyncStatus {
     pub conflicts: Vec<SyncConflict>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PlannedChange {
+    pub path: String,
+    pub change: String, // "new", "modified", "deleted"
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SyncPreview {
+    pub remote: Vec<PlannedChange>, // what the sync would change remotely
+    pub local: Vec<PlannedChange>,  // and in the vault
+    pub conflicts: Vec<String>,     // changed on both sides since the last sync
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SyncPreflight {
+    pub status: String, // "up_to_date", "local_ahead", "remote_ahead", "diverged"
+    pub local_changes: usize,
+    pub remote_changes: usize,
+    pub last_sync: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SyncConflict {
     pub file_path: String,
@@ -130,25 +520,84 @@ pub struct ConflictResolution {
     pub resolution: String, // "keep_local", "keep_remote", "keep_both"
 }
 
-fn get_file_hash(path: &PathBuf) -> Result<String, String> {
-    let content = fs::read(path).map_err(|e| e.to_string())?;
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BackupImportResult {
+    pub files_imported: usize,
+    pub files_skipped: usize,
+    pub settings_merged: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AttachmentMove {
+    pub note: String,
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AttachmentRepairReport {
+    pub moved: Vec<AttachmentMove>,
+    pub notes_updated: usize,
+    pub missing: Vec<String>,
+    pub skipped: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TypographyOptions {
+    pub straight_quotes: bool,
+    pub normalize_dashes: bool,
+    pub replace_nbsp: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DuplicateTitleGroup {
+    pub title: String,
+    pub notes: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TitleChange {
+    pub note: String,
+    pub old_title: String,
+    pub new_title: String,
+}
+
+// Same shape as JavaScript's toISOString(), so timestamps from either side sort together
+fn system_time_to_rfc3339(time: std::time::SystemTime) -> String {
+    chrono::DateTime::<chrono::Utc>::from(time).to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+// Hashes in fixed-size chunks so large attachments aren't read into memory whole
+fn get_file_hash(path: &PathBuf) -> Result<String, AzimuthError> {
+    use std::io::Read;
+
+    let mut file = fs::File::open(path)?;
     let mut hasher = Sha256::new();
-    hasher.update(&content);
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = match file.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        hasher.update(&buffer[..read]);
+    }
     Ok(hex::encode(hasher.finalize()))
 }
 
-fn get_app_config_path() -> Result<PathBuf, String> {
+fn get_app_config_path() -> Result<PathBuf, AzimuthError> {
     let config_dir = dirs::config_dir()
         .ok_or("Could not find config directory")?
         .join("azimuth");
     if !config_dir.exists() {
-        fs::create_dir_all(&config_dir).map_err(|e| e.to_string())?;
+        fs::create_dir_all(&config_dir)?;
     }
     Ok(config_dir.join("config.json"))
 }
 
 #[tauri::command]
-fn get_notes_dir() -> Result<String, String> {
+fn get_notes_dir() -> Result<String, AzimuthError> {
     // Check for custom path in app config first
     if let Ok(config_path) = get_app_config_path() {
         if config_path.exists() {
@@ -157,7 +606,7 @@ fn get_notes_dir() -> Result<String, String> {
                     if let Some(custom_path) = config.get("notes_dir").and_then(|v| v.as_str()) {
                         let path = PathBuf::from(custom_path);
                         if !path.exists() {
-                            fs::create_dir_all(&path).map_err(|e| e.to_string())?;
+                            fs::create_dir_all(&path)?;
                         }
                         return Ok(path.to_string_lossy().to_string());
                     }
@@ -167,21 +616,21 @@ fn get_notes_dir() -> Result<String, String> {
     }
     
     // Default fallback to ~/Azimuth
-    let home = dirs::home_dir().ok_or("Could not find home directory")?;
+    let home = dirs::home_dir().ok_or(AzimuthError::NotFound("Could not find home directory".to_string()))?;
     let notes_dir = home.join("Azimuth");
     if !notes_dir.exists() {
-        fs::create_dir_all(&notes_dir).map_err(|e| e.to_string())?;
+        fs::create_dir_all(&notes_dir)?;
     }
     Ok(notes_dir.to_string_lossy().to_string())
 }
 
 #[tauri::command]
-fn set_notes_dir(path: String) -> Result<(), String> {
+fn set_notes_dir(path: String) -> Result<(), AzimuthError> {
     let config_path = get_app_config_path()?;
     
     // Read existing config or create new one
     let mut config: serde_json::Value = if config_path.exists() {
-        let content = fs::read_to_string(&config_path).map_err(|e| e.to_string())?;
+        let content = fs::read_to_string(&config_path)?;
         serde_json::from_str(&content).unwrap_or(serde_json::json!({}))
     } else {
         serde_json::json!({})
@@ -191,70 +640,162 @@ fn set_notes_dir(path: String) -> Result<(), String> {
     config["notes_dir"] = serde_json::Value::String(path);
     
     // Write back
-    let json = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
-    fs::write(&config_path, json).map_err(|e| e.to_string())?;
+    let json = serde_json::to_string_pretty(&config)?;
+    fs::write(&config_path, json)?;
     Ok(())
 }
 
 // App Settings
 #[tauri::command]
-fn load_settings(base_path: String) -> Result<AppSettings, String> {
+fn load_settings(base_path: String) -> Result<AppSettings, AzimuthError> {
     let settings_path = PathBuf::from(&base_path).join(".azimuth_settings.json");
     if !settings_path.exists() {
         return Ok(AppSettings::default());
     }
-    let content = fs::read_to_string(&settings_path).map_err(|e| e.to_string())?;
-    serde_json::from_str(&content).map_err(|e| e.to_string())
+    let content = fs::read_to_string(&settings_path)?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+fn write_settings(base_path: &str, settings: &AppSettings) -> Result<(), AzimuthError> {
+    let settings_path = PathBuf::from(base_path).join(".azimuth_settings.json");
+    let json = serde_json::to_string_pretty(settings)?;
+    fs::write(&settings_path, json)?;
+    Ok(())
+}
+
+// The window theme for a `theme` setting; None follows the system
+fn window_theme(theme: &str) -> Option<tauri::Theme> {
+    match theme {
+        "light" => Some(tauri::Theme::Light),
+        "dark" => Some(tauri::Theme::Dark),
+        _ => None,
+    }
 }
 
+// Emits `theme-changed` with the new theme when the save changes it
 #[tauri::command]
-fn save_settings(base_path: String, settings: AppSettings) -> Result<(), String> {
-    let settings_path = PathBuf::from(&base_path).join(".azimuth_settings.json");
-    let json = serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?;
-    fs::write(&settings_path, json).map_err(|e| e.to_string())?;
+fn save_settings(app: AppHandle, base_path: String, settings: AppSettings) -> Result<(), AzimuthError> {
+    let previous_theme = {
+        let _guard = SETTINGS_LOCK.lock()?;
+        let previous_theme = load_settings(base_path.clone()).map(|previous| previous.theme).ok();
+        write_settings(&base_path, &settings)?;
+        previous_theme
+    };
+    if previous_theme.as_ref() != Some(&settings.theme) {
+        if let Some(window) = app.get_webview_window("main") {
+            let _ = window.set_theme(window_theme(&settings.theme));
+        }
+        let _ = app.emit("theme-changed", settings.theme);
+    }
     Ok(())
 }
 
+// Serializes read-modify-write cycles on the settings file so concurrent
+// commands can't drop each other's changes
+static SETTINGS_LOCK: Mutex<()> = Mutex::new(());
+
+fn update_settings<T>(base_path: &str, update: impl FnOnce(&mut AppSettings) -> T) -> Result<T, AzimuthError> {
+    let _guard = SETTINGS_LOCK.lock()?;
+    let mut settings = load_settings(base_path.to_string())?;
+    let result = update(&mut settings);
+    write_settings(base_path, &settings)?;
+    Ok(result)
+}
+
+// Auto-save
+#[tauri::command]
+fn set_auto_save(base_path: String, enabled: bool) -> Result<bool, AzimuthError> {
+    update_settings(&base_path, |settings| {
+        settings.auto_save = enabled;
+        settings.auto_save
+    })
+}
+
+#[tauri::command]
+fn get_auto_save(base_path: String) -> Result<bool, AzimuthError> {
+    let settings = load_settings(base_path)?;
+    Ok(settings.auto_save)
+}
+
 // Favorites
 #[tauri::command]
-fn toggle_favorite(base_path: String, note_path: String) -> Result<AppSettings, String> {
-    let mut settings = load_settings(base_path.clone())?;
-    if settings.favorites.contains(&note_path) {
-        settings.favorites.retain(|p| p != &note_path);
-    } else {
-        settings.favorites.push(note_path);
-    }
-    save_settings(base_path, settings.clone())?;
-    Ok(settings)
+fn toggle_favorite(base_path: String, note_path: String) -> Result<AppSettings, AzimuthError> {
+    update_settings(&base_path, |settings| {
+        if settings.favorites.contains(&note_path) {
+            settings.favorites.retain(|p| p != &note_path);
+        } else {
+            settings.favorites.push(note_path);
+        }
+        settings.clone()
+    })
+}
+
+#[tauri::command]
+fn toggle_favorite_v2(base_path: String, note_path: String) -> Result<FavoriteToggle, AzimuthError> {
+    update_settings(&base_path, |settings| {
+        let is_favorite = !settings.favorites.contains(&note_path);
+        if is_favorite {
+            settings.favorites.push(note_path);
+        } else {
+            settings.favorites.retain(|p| p != &note_path);
+        }
+        FavoriteToggle {
+            is_favorite,
+            favorites: settings.favorites.clone(),
+        }
+    })
 }
 
 #[tauri::command]
-fn get_favorites(base_path: String) -> Result<Vec<String>, String> {
+fn get_favorites(base_path: String) -> Result<Vec<String>, AzimuthError> {
     let settings = load_settings(base_path)?;
     Ok(settings.favorites)
 }
 
+// Pinned notes
+#[tauri::command]
+fn toggle_pinned_note(base_path: String, note_path: String) -> Result<PinToggle, AzimuthError> {
+    update_settings(&base_path, |settings| {
+        let is_pinned = !settings.pinned_notes.contains(&note_path);
+        if is_pinned {
+            settings.pinned_notes.push(note_path);
+        } else {
+            settings.pinned_notes.retain(|p| p != &note_path);
+        }
+        PinToggle {
+            is_pinned,
+            pinned_notes: settings.pinned_notes.clone(),
+        }
+    })
+}
+
+#[tauri::command]
+fn get_pinned_notes(base_path: String) -> Result<Vec<String>, AzimuthError> {
+    let settings = load_settings(base_path)?;
+    Ok(settings.pinned_notes)
+}
+
 // Tags
 #[tauri::command]
-fn set_note_tags(base_path: String, note_path: String, tags: Vec<String>) -> Result<AppSettings, String> {
-    let mut settings = load_settings(base_path.clone())?;
-    if tags.is_empty() {
-        settings.tags.remove(&note_path);
-    } else {
-        settings.tags.insert(note_path, tags);
-    }
-    save_settings(base_path, settings.clone())?;
-    Ok(settings)
+fn set_note_tags(base_path: String, note_path: String, tags: Vec<String>) -> Result<AppSettings, AzimuthError> {
+    update_settings(&base_path, |settings| {
+        if tags.is_empty() {
+            settings.tags.remove(&note_path);
+        } else {
+            settings.tags.insert(note_path, tags);
+        }
+        settings.clone()
+    })
 }
 
 #[tauri::command]
-fn get_note_tags(base_path: String, note_path: String) -> Result<Vec<String>, String> {
+fn get_note_tags(base_path: String, note_path: String) -> Result<Vec<String>, AzimuthError> {
     let settings = load_settings(base_path)?;
     Ok(settings.tags.get(&note_path).cloned().unwrap_or_default())
 }
 
 #[tauri::command]
-fn get_all_tags(base_path: String) -> Result<Vec<String>, String> {
+fn get_all_tags(base_path: String) -> Result<Vec<String>, AzimuthError> {
     let settings = load_settings(base_path)?;
     let mut all_tags: Vec<String> = settings.tags.values().flatten().cloned().collect();
     all_tags.sort();
@@ -262,986 +803,8577 @@ fn get_all_tags(base_path: String) -> Result<Vec<String>, String> {
     Ok(all_tags)
 }
 
-#[tauri::command]
-fn get_notes_by_tag(base_path: String, tag: String) -> Result<Vec<String>, String> {
-    let settings = load_settings(base_path)?;
-    let notes: Vec<String> = settings.tags
+fn tag_matches(tag: &str, query: &str) -> bool {
+    tag == query || tag.strip_prefix(query).is_some_and(|rest| rest.starts_with('/'))
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TagNode {
+    // The last segment of the tag, e.g. "frontend" for "project/azimuth/frontend"
+    pub name: String,
+    pub tag: String,
+    // Notes tagged with this tag or any nested under it
+    pub count: usize,
+    pub children: Vec<TagNode>,
+}
+
+fn tag_tree_children(prefix: Option<&str>, counts: &BTreeMap<String, usize>) -> Vec<TagNode> {
+    counts
         .iter()
-        .filter(|(_, tags)| tags.contains(&tag))
-        .map(|(path, _)| path.clone())
-        .collect();
-    Ok(notes)
+        .filter(|(tag, _)| match prefix {
+            Some(prefix) => tag.strip_prefix(prefix).and_then(|rest| rest.strip_prefix('/')).is_some_and(|rest| !rest.contains('/')),
+            None => !tag.contains('/'),
+        })
+        .map(|(tag, count)| TagNode {
+            name: tag.rsplit('/').next().unwrap_or(tag).to_string(),
+            tag: tag.clone(),
+            count: *count,
+            children: tag_tree_children(Some(tag), counts),
+        })
+        .collect()
 }
 
-// Global Search
+// Tags nested on `/`, alphabetical at each level. Parents that are never used on
+// their own still appear, with the count of their descendants.
 #[tauri::command]
-fn search_notes(base_path: String, query: String) -> Result<Vec<SearchResult>, String> {
-    if query.trim().is_empty() {
-        return Ok(Vec::new());
-    }
-    
-    let query_lower = query.to_lowercase();
-    let mut results = Vec::new();
-    
-    for entry in WalkDir::new(&base_path)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.path().is_file())
-    {
-        let path = entry.path();
-        let extension = path.extension()
-            .map(|e| e.to_string_lossy().to_lowercase())
-            .unwrap_or_default();
-        
-        if !is_text_extension(&extension) {
-            continue;
-        }
-        
-        if let Ok(content) = fs::read_to_string(path) {
-            let content_lower = content.to_lowercase();
-            let file_name = path.file_name().unwrap().to_string_lossy().to_string();
-            let file_name_lower = file_name.to_lowercase();
-            
-            // Count matches in content and filename
-            let content_matches = content_lower.matches(&query_lower).count();
-            let name_matches = if file_name_lower.contains(&query_lower) { 1 } else { 0 };
-            let total_matches = content_matches + name_matches;
-            
-            if total_matches > 0 {
-                // Get snippet around first match
-                let snippet = if let Some(pos) = content_lower.find(&query_lower) {
-                    let start = pos.saturating_sub(50);
-                    let end = (pos + query.len() + 50).min(content.len());
-                    let mut s = content[start..end].to_string();
-                    if start > 0 { s = format!("...{}", s); }
-                    if end < content.len() { s = format!("{}...", s); }
-                    s.replace('\n', " ")
-                } else {
-                    content.chars().take(100).collect::<String>()
-                };
-                
-                // Get notebook info
-                let parent = path.parent().unwrap();
-                let notebook_name = parent.file_name()
-                    .map(|n| n.to_string_lossy().to_string())
-                    .unwrap_or_default();
-                
-                results.push(SearchResult {
-                    note_id: file_name,
-                    note_title: path.file_stem().unwrap().to_string_lossy().to_string(),
-                    notebook_path: parent.to_string_lossy().to_string(),
-                    notebook_name,
-                    snippet,
-                    match_count: total_matches,
-                });
+fn get_tag_tree(base_path: String) -> Result<Vec<TagNode>, AzimuthError> {
+    let settings = load_settings(base_path)?;
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for tags in settings.tags.values() {
+        let mut prefixes: HashSet<&str> = HashSet::new();
+        for tag in tags {
+            let tag = tag.trim_matches('/');
+            for (i, _) in tag.match_indices('/') {
+                prefixes.insert(&tag[..i]);
+            }
+            if !tag.is_empty() {
+                prefixes.insert(tag);
             }
         }
+        for prefix in prefixes {
+            *counts.entry(prefix.to_string()).or_default() += 1;
+        }
     }
-    
-    // Sort by match count descending
-    results.sort_by(|a, b| b.match_count.cmp(&a.match_count));
-    Ok(results)
+    Ok(tag_tree_children(None, &counts))
 }
 
-// Directories to skip when scanning for notebooks
-const IGNORED_DIRS: &[&str] = &[
-    ".", "..", ".git", ".svn", ".hg", "node_modules", "target", "build", "dist",
-    ".Trash", ".Spotlight-V100", ".fseventsd", "Library", "Applications",
-    ".cache", ".npm", ".cargo", ".rustup", ".local", ".config",
-    "__pycache__", ".venv", "venv", ".tox", ".pytest_cache",
-    ".DS_Store", "Thumbs.db",
-];
+// Each tag with the number of notes using it, most used first (ties alphabetical)
+#[tauri::command]
+fn get_tag_counts(base_path: String) -> Result<Vec<(String, usize)>, AzimuthError> {
+    let settings = load_settings(base_path)?;
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for tags in settings.tags.values() {
+        let unique: HashSet<&String> = tags.iter().collect();
+        for tag in unique {
+            *counts.entry(tag.clone()).or_default() += 1;
+        }
+    }
+    let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+    counts.sort_by(|(a_tag, a_count), (b_tag, b_count)| b_count.cmp(a_count).then_with(|| a_tag.cmp(b_tag)));
+    Ok(counts)
+}
 
-#[derive(Clone, Serialize)]
-struct LoadComplete {
-    notebooks: Vec<Notebook>,
+const DEFAULT_TAG_SUGGESTIONS: usize = 10;
+
+// Existing tags for a tag input: those starting with `prefix` (or with a nested
+// segment that does) first, then fuzzy matches, each most used first. With no
+// prefix, the most used tags.
+#[tauri::command]
+fn suggest_tags(base_path: String, prefix: String, limit: Option<usize>) -> Result<Vec<(String, usize)>, AzimuthError> {
+    let prefix = prefix.trim().trim_start_matches('#').to_lowercase();
+    let limit = limit.unwrap_or(DEFAULT_TAG_SUGGESTIONS);
+    let counts = get_tag_counts(base_path)?;
+    if prefix.is_empty() {
+        return Ok(counts.into_iter().take(limit).collect());
+    }
+
+    let starts_with_prefix = |tag: &str| {
+        let tag = tag.to_lowercase();
+        tag.starts_with(&prefix) || tag.match_indices('/').any(|(i, _)| tag[i + 1..].starts_with(&prefix))
+    };
+    let matcher = SkimMatcherV2::default();
+    let (starts, rest): (Vec<_>, Vec<_>) = counts.into_iter().partition(|(tag, _)| starts_with_prefix(tag));
+    let fuzzy = rest.into_iter().filter(|(tag, _)| matcher.fuzzy_match(tag, &prefix).is_some());
+    Ok(starts.into_iter().chain(fuzzy).take(limit).collect())
 }
 
-const MAX_NOTEBOOKS: usize = 50;
-const MAX_ENTRIES_TO_SCAN: usize = 200;
+// Renames a tag on every note that has it, dropping it where the note already has
+// `new_tag`. Returns the number of notes changed.
+#[tauri::command]
+fn rename_tag(base_path: String, old_tag: String, new_tag: String) -> Result<usize, AzimuthError> {
+    let new_tag = new_tag.trim().trim_start_matches('#').to_string();
+    if new_tag.is_empty() {
+        return Err(AzimuthError::InvalidInput("Tag name cannot be empty".to_string()));
+    }
+    if new_tag == old_tag {
+        return Ok(0);
+    }
+    update_settings(&base_path, |settings| retag_notes(settings, &[old_tag], &new_tag))
+}
 
-// Trigger async notebook loading - results come via events
+// Folds several tags into one on every note that has any of them, e.g. "to-do"
+// into "todo". Returns the number of notes changed.
 #[tauri::command]
-fn list_notebooks_async(app: AppHandle, base_path: String) {
-    std::thread::spawn(move || {
-        let path = PathBuf::from(&base_path);
-        if !path.exists() {
-            let _ = fs::create_dir_all(&path);
+fn merge_tags(base_path: String, sources: Vec<String>, target: String) -> Result<usize, AzimuthError> {
+    let target = target.trim().trim_start_matches('#').to_string();
+    if target.is_empty() {
+        return Err(AzimuthError::InvalidInput("Tag name cannot be empty".to_string()));
+    }
+    let sources: Vec<String> = sources.into_iter().filter(|source| *source != target).collect();
+    update_settings(&base_path, |settings| retag_notes(settings, &sources, &target))
+}
+
+// Replaces `sources` with `target` in each note's tags, where the first source was
+fn retag_notes(settings: &mut AppSettings, sources: &[String], target: &str) -> usize {
+    let mut changed = 0;
+    for tags in settings.tags.values_mut() {
+        let Some(position) = tags.iter().position(|tag| sources.contains(tag)) else { continue };
+        if !tags.iter().any(|tag| tag == target) {
+            tags.insert(position, target.to_string());
         }
-        
-        let mut notebooks = Vec::new();
-        let mut scanned = 0;
-        
-        if let Ok(read_dir) = std::fs::read_dir(&path) {
-            for entry in read_dir.filter_map(|e| e.ok()) {
-                scanned += 1;
-                
-                if scanned > MAX_ENTRIES_TO_SCAN {
-                    break;
-                }
-                
-                let name = entry.file_name().to_string_lossy().to_string();
-                
-                if name.starts_with('.') || IGNORED_DIRS.contains(&name.as_str()) {
-                    continue;
-                }
-                
-                let is_dir = entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false);
-                if !is_dir {
-                    continue;
-                }
-                
-                notebooks.push(Notebook {
-                    id: entry.path().to_string_lossy().to_string(),
-                    name: name.clone(),
-                    path: entry.path().to_string_lossy().to_string(),
-                    children: vec![Notebook {
-                        id: String::new(),
-                        name: String::new(),
-                        path: String::new(),
-                        children: vec![],
-                    }],
-                });
-                
-                if notebooks.len() >= MAX_NOTEBOOKS {
-                    break;
-                }
+        tags.retain(|tag| !sources.contains(tag));
+        changed += 1;
+    }
+    changed
+}
+
+// Removes a tag from every note, dropping notes left without tags as set_note_tags
+// does. Returns the number of notes changed.
+#[tauri::command]
+fn delete_tag(base_path: String, tag: String) -> Result<usize, AzimuthError> {
+    update_settings(&base_path, |settings| {
+        let mut removed = 0;
+        for tags in settings.tags.values_mut() {
+            let before = tags.len();
+            tags.retain(|t| *t != tag);
+            if tags.len() != before {
+                removed += 1;
             }
         }
-        
-        notebooks.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
-        let _ = app.emit("load-complete", LoadComplete { notebooks });
-    });
+        settings.tags.retain(|_, tags| !tags.is_empty());
+        removed
+    })
 }
 
-// Synchronous version for lazy-loading children (small directories)
+// Notes tagged with `tag` or anything nested under it ("project" matches "project/azimuth")
 #[tauri::command]
-fn list_notebooks(base_path: String) -> Result<Vec<Notebook>, String> {
-    let path = PathBuf::from(&base_path);
-    if !path.exists() {
-        fs::create_dir_all(&path).map_err(|e| e.to_string())?;
-    }
-    list_notebooks_simple(&path)
+fn get_notes_by_tag(base_path: String, tag: String) -> Result<Vec<String>, AzimuthError> {
+    let settings = load_settings(base_path)?;
+    let notes: Vec<String> = settings.tags
+        .iter()
+        .filter(|(_, tags)| tags.iter().any(|t| tag_matches(t, &tag)))
+        .map(|(path, _)| path.clone())
+        .collect();
+    Ok(notes)
 }
 
-// Simple version for import_folder (no progress needed)
-fn list_notebooks_simple(path: &PathBuf) -> Result<Vec<Notebook>, String> {
-    let mut notebooks = Vec::new();
-    
-    let entries = match fs::read_dir(path) {
-        Ok(e) => e,
-        Err(_) => return Ok(Vec::new()),
-    };
-    
-    for entry in entries.filter_map(|e| e.ok()).take(MAX_NOTEBOOKS) {
-        let is_dir = match entry.metadata() {
-            Ok(m) => m.is_dir(),
-            Err(_) => continue,
-        };
-        
-        if !is_dir {
-            continue;
+// Inline `#tag`s: a `#` at the start of a word followed by letters, digits, `_`, `-` or
+// `/` (for nested tags). Headings, code, URLs and all-digit tokens like `#1` don't count.
+fn extract_tags(content: &str) -> Vec<String> {
+    let url_re = regex::Regex::new(r"\]\([^)\n]*\)|[a-zA-Z][a-zA-Z0-9+.-]*://\S+").expect("valid url pattern");
+    let tag_re = regex::Regex::new(r"(?:^|[^\w#&/])#([\w][\w/-]*)").expect("valid tag pattern");
+    let text = url_re.replace_all(&strip_code(content), " ").to_string();
+    let mut tags: Vec<String> = Vec::new();
+    for caps in tag_re.captures_iter(&text) {
+        let tag = caps[1].trim_end_matches(['/', '-']).to_string();
+        if !tag.is_empty() && !tag.chars().all(|c| c.is_ascii_digit()) && !tags.contains(&tag) {
+            tags.push(tag);
         }
-        
-        let name = entry.file_name().to_string_lossy().to_string();
-        
-        if name.starts_with('.') || IGNORED_DIRS.contains(&name.as_str()) {
-            continue;
-        }
-        
-        notebooks.push(Notebook {
-            id: entry.path().to_string_lossy().to_string(),
-            name: name.clone(),
-            path: entry.path().to_string_lossy().to_string(),
-            children: vec![Notebook {
-                id: String::new(),
-                name: String::new(),
-                path: String::new(),
-                children: vec![],
-            }],
-        });
     }
-    
-    notebooks.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
-    Ok(notebooks)
+    tags
 }
 
+// Adds the note's inline #tags to its tags, returning the result. Tags are never
+// removed here, so ones set by hand or dropped from the text stay until removed.
 #[tauri::command]
-fn create_notebook(base_path: String, name: String) -> Result<Notebook, String> {
-    let path = PathBuf::from(&base_path).join(&name);
-    fs::create_dir_all(&path).map_err(|e| e.to_string())?;
-    Ok(Notebook {
-        id: path.to_string_lossy().to_string(),
-        name: name.clone(),
-        path: path.to_string_lossy().to_string(),
-        children: Vec::new(),
-    })
+fn sync_inline_tags(base_path: String, note_path: String) -> Result<Vec<String>, AzimuthError> {
+    let content = fs::read_to_string(&note_path)?;
+    let tags = extract_tags(&content);
+    merge_note_tags(&base_path, HashMap::from([(note_path.clone(), tags)]))?;
+    get_note_tags(base_path, note_path)
 }
 
+// Stable note ids
+// Metadata keyed by file path is lost when a note is renamed, so notes can carry
+// an `azimuth_id` in their frontmatter that survives renames and moves
 #[tauri::command]
-fn list_notes(notebook_path: String) -> Result<Vec<Note>, String> {
-    let path = PathBuf::from(&notebook_path);
-    if !path.exists() {
-        return Ok(Vec::new());
+fn get_or_assign_note_id(notebook_path: String, note_id: String) -> Result<String, AzimuthError> {
+    let path = safe_join(Path::new(&notebook_path), &note_id)?;
+    let extension = path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if !is_markdown_extension(&extension) {
+        return Err(AzimuthError::InvalidInput(format!("Stable ids are only supported for Markdown notes: {}", note_id)));
     }
-    
-    let mut notes = Vec::new();
-    for entry in fs::read_dir(&path).map_err(|e| e.to_string())? {
-        let entry = entry.map_err(|e| e.to_string())?;
-        let file_path = entry.path();
-        if file_path.is_file() {
-            let metadata = fs::metadata(&file_path).map_err(|e| e.to_string())?;
-            let file_name = file_path.file_name().unwrap().to_string_lossy().to_string();
-            let extension = file_path.extension()
-                .map(|e| e.to_string_lossy().to_lowercase())
-                .unwrap_or_default();
-            
-            let content = if is_text_extension(&extension) {
-                fs::read_to_string(&file_path).unwrap_or_else(|_| {
-                    let asset_url = format!("asset://localhost/{}", file_path.to_string_lossy().replace(" ", "%20"));
-                    format!("[📎 {}]({})", file_name, asset_url)
-                })
-            } else if is_image_extension(&extension) {
-                let asset_url = format!("asset://localhost/{}", file_path.to_string_lossy().replace(" ", "%20"));
-                format!("![{}]({})", file_name, asset_url)
-            } else if is_video_extension(&extension) {
-                let asset_url = format!("asset://localhost/{}", file_path.to_string_lossy().replace(" ", "%20"));
-                format!("<video controls width=\"100%\" style=\"max-height: 80vh;\">\n  <source src=\"{}\" type=\"video/{}\">\n  Your browser does not support the video tag.\n</video>", asset_url, get_video_mime(&extension))
-            } else if is_audio_extension(&extension) {
-                let asset_url = format!("asset://localhost/{}", file_path.to_string_lossy().replace(" ", "%20"));
-                format!("<audio controls style=\"width: 100%;\">\n  <source src=\"{}\" type=\"audio/{}\">\n  Your browser does not support the audio tag.\n</audio>", asset_url, get_audio_mime(&extension))
-            } else if extension == "pdf" {
-                let asset_url = format!("asset://localhost/{}", file_path.to_string_lossy().replace(" ", "%20"));
-                format!("<iframe src=\"{}\" style=\"width: 100%; height: 60vh; border: none;\"></iframe>", asset_url)
-            } else {
-                let asset_url = format!("asset://localhost/{}", file_path.to_string_lossy().replace(" ", "%20"));
-                format!("[📎 {}]({})", file_name, asset_url)
-            };
-            
-            notes.push(Note {
-                id: file_name.clone(),
-                title: file_name.clone(),
-                content,
-                folder: notebook_path.clone(),
-                created_at: format!("{:?}", metadata.created().unwrap_or(std::time::SystemTime::now())),
-                updated_at: format!("{:?}", metadata.modified().unwrap_or(std::time::SystemTime::now())),
-            });
+
+    let content = fs::read_to_string(&path)?;
+    let (mut fields, body) = match split_frontmatter(&content) {
+        Some(split) => split,
+        None if content.starts_with("---") => {
+            return Err(AzimuthError::Other(format!("Could not parse frontmatter in {}", note_id)));
         }
+        None => (serde_yaml::Mapping::new(), content.as_str()),
+    };
+
+    let key = serde_yaml::Value::String("azimuth_id".to_string());
+    if let Some(id) = fields.get(&key).and_then(|v| v.as_str()) {
+        return Ok(id.to_string());
     }
-    Ok(notes)
+
+    let id = uuid::Uuid::new_v4().to_string();
+    fields.insert(key, serde_yaml::Value::String(id.clone()));
+    fs::write(&path, join_frontmatter(&fields, body)?)?;
+    Ok(id)
 }
 
-fn is_image_extension(ext: &str) -> bool {
-    matches!(ext, "png" | "jpg" | "jpeg" | "gif" | "webp" | "svg" | "bmp" | "ico" | "tiff" | "tif")
+#[tauri::command]
+fn set_note_metadata(
+    base_path: String,
+    stable_id: String,
+    key: String,
+    value: serde_json::Value,
+) -> Result<HashMap<String, serde_json::Value>, AzimuthError> {
+    update_settings(&base_path, |settings| {
+        let metadata = settings.note_metadata.entry(stable_id.clone()).or_default();
+        if value.is_null() {
+            metadata.remove(&key);
+        } else {
+            metadata.insert(key, value);
+        }
+        let result = metadata.clone();
+        if result.is_empty() {
+            settings.note_metadata.remove(&stable_id);
+        }
+        result
+    })
 }
 
-fn is_video_extension(ext: &str) -> bool {
-    matches!(ext, "mp4" | "webm" | "mov" | "avi" | "mkv" | "m4v" | "ogv" | "3gp" | "wmv")
+#[tauri::command]
+fn get_note_metadata(base_path: String, stable_id: String) -> Result<HashMap<String, serde_json::Value>, AzimuthError> {
+    let settings = load_settings(base_path)?;
+    Ok(settings.note_metadata.get(&stable_id).cloned().unwrap_or_default())
 }
 
-fn is_audio_extension(ext: &str) -> bool {
-    matches!(ext, "mp3" | "wav" | "ogg" | "flac" | "aac" | "m4a" | "wma" | "opus")
+// Reminders
+#[tauri::command]
+fn set_reminder(base_path: String, note_path: String, due: String) -> Result<HashMap<String, String>, AzimuthError> {
+    chrono::DateTime::parse_from_rfc3339(&due).map_err(|e| AzimuthError::InvalidInput(format!("Invalid reminder time '{}': {}", due, e)))?;
+    update_settings(&base_path, |settings| {
+        settings.note_reminders.insert(note_path, due);
+        settings.note_reminders.clone()
+    })
 }
 
-fn get_video_mime(ext: &str) -> &str {
-    match ext {
-        "mp4" | "m4v" => "mp4",
-        "webm" => "webm",
-        "mov" => "quicktime",
-        "avi" => "x-msvideo",
-        "mkv" => "x-matroska",
-        "ogv" => "ogg",
-        "3gp" => "3gpp",
-        "wmv" => "x-ms-wmv",
-        _ => "mp4"
-    }
+#[tauri::command]
+fn clear_reminder(base_path: String, note_path: String) -> Result<HashMap<String, String>, AzimuthError> {
+    update_settings(&base_path, |settings| {
+        settings.note_reminders.remove(&note_path);
+        settings.note_reminders.clone()
+    })
 }
 
-fn get_audio_mime(ext: &str) -> &str {
-    match ext {
-        "mp3" => "mpeg",
-        "wav" => "wav",
-        "ogg" | "opus" => "ogg",
-        "flac" => "flac",
-        "aac" | "m4a" => "aac",
-        "wma" => "x-ms-wma",
-        "webm" => "webm",
-        _ => "mpeg"
+#[tauri::command]
+fn get_due_reminders(base_path: String, now: String) -> Result<Vec<DueReminder>, AzimuthError> {
+    let now = chrono::DateTime::parse_from_rfc3339(&now).map_err(|e| AzimuthError::InvalidInput(format!("Invalid time '{}': {}", now, e)))?;
+    let settings = load_settings(base_path)?;
+
+    let mut due: Vec<(chrono::DateTime<chrono::FixedOffset>, DueReminder)> = settings
+        .note_reminders
+        .into_iter()
+        .filter_map(|(note_path, due_at)| {
+            let time = chrono::DateTime::parse_from_rfc3339(&due_at).ok()?;
+            (time <= now).then_some((time, DueReminder { note_path, due: due_at }))
+        })
+        .collect();
+    due.sort_by_key(|(time, _)| *time);
+    Ok(due.into_iter().map(|(_, reminder)| reminder).collect())
+}
+
+const REMINDER_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+// Background loop started from setup: emits `reminder-due` once for each reminder
+// in the current notes dir as it comes due
+fn watch_reminders(app: AppHandle) {
+    let mut notified: HashSet<(String, String)> = HashSet::new();
+    loop {
+        if let Ok(base_path) = get_notes_dir() {
+            let now = chrono::Utc::now().to_rfc3339();
+            if let Ok(due) = get_due_reminders(base_path, now) {
+                for reminder in due {
+                    if notified.insert((reminder.note_path.clone(), reminder.due.clone())) {
+                        let _ = app.emit("reminder-due", reminder);
+                    }
+                }
+            }
+        }
+        std::thread::sleep(REMINDER_POLL_INTERVAL);
     }
 }
 
-fn is_text_extension(ext: &str) -> bool {
-    matches!(ext, 
-        "md" | "markdown" | "mdown" | "mkd" |
-        "txt" | "text" | "log" |
-        "json" | "yaml" | "yml" | "toml" | "ini" | "cfg" | "conf" | "config" |
-        "rs" | "py" | "js" | "ts" | "jsx" | "tsx" | "java" | "c" | "cpp" | "h" | "hpp" |
-        "go" | "rb" | "php" | "swift" | "kt" | "scala" | "cs" | "fs" | "vb" |
-        "lua" | "pl" | "pm" | "r" | "m" | "mm" | "sql" | "sh" | "bash" | "zsh" |
-        "fish" | "ps1" | "psm1" | "bat" | "cmd" |
-        "html" | "htm" | "css" | "scss" | "sass" | "less" | "xml" | "xsl" | "xslt" |
-        "svg" | "vue" | "svelte" |
-        "csv" | "tsv" |
-        "pem" | "crt" | "cer" | "key" | "pub" |
-        "rst" | "adoc" | "asciidoc" | "org" | "tex" | "latex" |
-        "env" | "gitignore" | "dockerignore" | "editorconfig" | "prettierrc" |
-        "eslintrc" | "babelrc" | "nvmrc" | "npmrc" | "yarnrc" |
-        "makefile" | "cmake" | "gradle" | "properties" |
-        ""
-    )
+// Files changed outside the app (by sync or another editor)
+const WATCH_DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(300);
+
+// Managed Tauri state holding the notes folder watcher. Dropping the watcher also
+// ends its debounce thread.
+#[derive(Default)]
+pub struct NoteWatcher {
+    watcher: Mutex<Option<notify::RecommendedWatcher>>,
 }
 
-#[tauri::command]
-fn save_note(notebook_path: String, note_id: String, content: String) -> Result<(), String> {
-    let path = PathBuf::from(&notebook_path).join(&note_id);
-    fs::write(&path, &content).map_err(|e| e.to_string())?;
-    Ok(())
+// Hidden files and folders under the watched folder are the app's own bookkeeping
+// (settings, history, trash, temp files from write_atomic) or another tool's
+fn is_watched_path(root: &Path, path: &Path) -> bool {
+    path.strip_prefix(root)
+        .map(|relative| !relative.components().any(|c| c.as_os_str().to_string_lossy().starts_with('.')))
+        .unwrap_or(false)
 }
 
-#[tauri::command]
-fn delete_note(notebook_path: String, note_id: String) -> Result<(), String> {
-    let note_path = PathBuf::from(&notebook_path).join(&note_id);
-    if note_path.exists() {
-        fs::remove_file(&note_path).map_err(|e| e.to_string())?;
-    }
-    
-    let stem = PathBuf::from(&note_id)
-        .file_stem()
-        .map(|s| s.to_string_lossy().to_string())
-        .unwrap_or(note_id.clone());
-    let attachments_path = PathBuf::from(&notebook_path).join(&stem);
-    if attachments_path.exists() && attachments_path.is_dir() {
-        fs::remove_dir_all(&attachments_path).map_err(|e| e.to_string())?;
+// Emits `file-created`, `file-changed` or `file-deleted` with the path once a path's
+// events have been quiet for WATCH_DEBOUNCE. Deletion is judged by whether the file
+// is still there, so a save that replaces the file reads as a change.
+fn debounce_file_events(app: AppHandle, root: PathBuf, events: std::sync::mpsc::Receiver<notify::Result<notify::Event>>) {
+    let mut pending: HashMap<PathBuf, bool> = HashMap::new();
+    loop {
+        match events.recv_timeout(WATCH_DEBOUNCE) {
+            Ok(Ok(event)) => {
+                let created = matches!(event.kind, notify::EventKind::Create(_));
+                for path in event.paths.into_iter().filter(|path| is_watched_path(&root, path)) {
+                    *pending.entry(path).or_default() |= created;
+                }
+            }
+            Ok(Err(_)) => {}
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
+                for (path, created) in pending.drain() {
+                    let event = match (path.exists(), created) {
+                        (false, _) => "file-deleted",
+                        (true, true) => "file-created",
+                        (true, false) => "file-changed",
+                    };
+                    let _ = app.emit(event, path.to_string_lossy().to_string());
+                }
+            }
+            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => return,
+        }
     }
-    Ok(())
 }
 
+// Watches `path` (recursively) in place of whatever was watched before
 #[tauri::command]
-fn rename_note(notebook_path: String, old_id: String, new_id: String) -> Result<(), String> {
-    let old_path = PathBuf::from(&notebook_path).join(&old_id);
-    let new_path = PathBuf::from(&notebook_path).join(&new_id);
-    
-    if !old_path.exists() {
-        return Err(format!("File does not exist: {}", old_id));
-    }
-    
-    if new_path.exists() {
-        return Err(format!("A file with that name already exists: {}", new_id));
+fn start_watching(app: AppHandle, path: String, state: tauri::State<'_, NoteWatcher>) -> Result<(), AzimuthError> {
+    use notify::Watcher;
+    let root = PathBuf::from(&path);
+    if !root.is_dir() {
+        return Err(AzimuthError::NotFound(format!("Folder does not exist: {}", path)));
     }
-    
-    fs::rename(&old_path, &new_path).map_err(|e| e.to_string())?;
+    let (sender, events) = std::sync::mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    watcher.watch(&root, notify::RecursiveMode::Recursive)?;
+    std::thread::spawn(move || debounce_file_events(app, root, events));
+    *state.watcher.lock()? = Some(watcher);
     Ok(())
 }
 
 #[tauri::command]
-fn move_note(source_folder: String, target_folder: String, note_id: String) -> Result<(), String> {
-    let source_path = PathBuf::from(&source_folder).join(&note_id);
-    let target_path = PathBuf::from(&target_folder).join(&note_id);
-    
-    if !source_path.exists() {
-        return Err(format!("File does not exist: {}", note_id));
-    }
-    
-    if target_path.exists() {
-        return Err(format!("A file with that name already exists in the target folder: {}", note_id));
-    }
-    
-    // Ensure target folder exists
-    fs::create_dir_all(&target_folder).map_err(|e| e.to_string())?;
-    
-    fs::rename(&source_path, &target_path).map_err(|e| e.to_string())?;
+fn stop_watching(state: tauri::State<'_, NoteWatcher>) -> Result<(), AzimuthError> {
+    *state.watcher.lock()? = None;
     Ok(())
 }
 
-#[tauri::command]
-fn read_note(notebook_path: String, note_id: String) -> Result<String, String> {
-    let path = PathBuf::from(&notebook_path).join(&note_id);
-    fs::read_to_string(&path).map_err(|e| e.to_string())
+// Global Search
+enum QueryMatcher {
+    // Case-insensitive substring, holding the lowercased query
+    Substring(String),
+    Pattern(regex::Regex),
+    Boolean(BooleanQuery),
 }
 
-#[tauri::command]
-fn read_file_binary(file_path: String) -> Result<Vec<u8>, String> {
-    let path = PathBuf::from(&file_path);
-    fs::read(&path).map_err(|e| e.to_string())
+// `rust AND async OR tokio NOT java`: every group must have at least one term
+// present and no excluded term may appear. AND binds looser than OR, so that
+// example is rust AND (async OR tokio) AND NOT java.
+struct BooleanQuery {
+    required: Vec<Vec<QueryMatcher>>,
+    excluded: Vec<QueryMatcher>,
 }
 
-#[tauri::command]
-fn save_attachment(notebook_path: String, _note_id: String, file_name: String, data: String) -> Result<String, String> {
-    // Save attachment directly in the notebook folder (adjacent to notes)
-    let notebook_dir = PathBuf::from(&notebook_path);
-    
-    let file_path = notebook_dir.join(&file_name);
-    let decoded = STANDARD.decode(&data).map_err(|e| e.to_string())?;
-    fs::write(&file_path, decoded).map_err(|e| e.to_string())?;
-    
-    let asset_url = format!("asset://localhost/{}", file_path.to_string_lossy().replace(" ", "%20"));
-    Ok(asset_url)
+impl BooleanQuery {
+    fn parse(query: &str, case_sensitive: bool) -> Result<Self, AzimuthError> {
+        let term = |word: &str| QueryMatcher::new(word, SearchMode::Substring, case_sensitive);
+        let mut required: Vec<Vec<QueryMatcher>> = Vec::new();
+        let mut excluded = Vec::new();
+        let mut pending: Option<&str> = None;
+
+        for word in query.split_whitespace() {
+            match (word, pending) {
+                ("AND" | "OR" | "NOT", Some(op)) => {
+                    return Err(AzimuthError::InvalidInput(format!("Expected a search term after {}, found {}", op, word)));
+                }
+                ("AND", None) if required.is_empty() && excluded.is_empty() => {
+                    return Err(AzimuthError::InvalidInput("Search query can't start with AND".to_string()));
+                }
+                ("OR", None) if required.is_empty() => {
+                    return Err(AzimuthError::InvalidInput("Search query can't start with OR".to_string()));
+                }
+                ("AND" | "OR" | "NOT", None) => pending = Some(word),
+                (_, Some("NOT")) => {
+                    excluded.push(term(word)?);
+                    pending = None;
+                }
+                (_, Some("OR")) => {
+                    required.last_mut().unwrap().push(term(word)?);
+                    pending = None;
+                }
+                _ => {
+                    required.push(vec![term(word)?]);
+                    pending = None;
+                }
+            }
+        }
+
+        if let Some(op) = pending {
+            return Err(AzimuthError::InvalidInput(format!("Expected a search term after {}", op)));
+        }
+        if required.is_empty() {
+            return Err(AzimuthError::InvalidInput("Search query needs at least one term that isn't excluded".to_string()));
+        }
+        Ok(BooleanQuery { required, excluded })
+    }
+
+    // Hits of every required term, or nothing when the text doesn't satisfy the query
+    fn find(&self, text: &str, limit: usize) -> (usize, Vec<(usize, usize)>) {
+        if self.excluded.iter().any(|term| term.find(text, 0).0 > 0) {
+            return (0, Vec::new());
+        }
+
+        let mut count = 0;
+        let mut spans = Vec::new();
+        for group in &self.required {
+            let mut group_count = 0;
+            for term in group {
+                let (term_count, term_spans) = term.find(text, limit);
+                group_count += term_count;
+                spans.extend(term_spans);
+            }
+            if group_count == 0 {
+                return (0, Vec::new());
+            }
+            count += group_count;
+        }
+        spans.sort();
+        spans.truncate(limit);
+        (count, spans)
+    }
+}
+
+impl QueryMatcher {
+    fn new(query: &str, mode: SearchMode, case_sensitive: bool) -> Result<Self, AzimuthError> {
+        let flags = if case_sensitive { "" } else { "(?i)" };
+        match mode {
+            SearchMode::Substring if !case_sensitive => Ok(QueryMatcher::Substring(query.to_lowercase())),
+            SearchMode::Substring => Ok(QueryMatcher::Pattern(regex::Regex::new(&regex::escape(query))?)),
+            SearchMode::Regex => regex::Regex::new(query)
+                .map(QueryMatcher::Pattern)
+                .map_err(|e| AzimuthError::InvalidInput(format!("Invalid regular expression: {}", e))),
+            SearchMode::WholeWord => Ok(QueryMatcher::Pattern(regex::Regex::new(&format!(r"{}\b{}\b", flags, regex::escape(query)))?)),
+            SearchMode::Boolean => BooleanQuery::parse(query, case_sensitive).map(QueryMatcher::Boolean),
+        }
+    }
+
+    // Replaces every match, returning the new text and the number of replacements.
+    // With `expand`, regex replacements can refer to capture groups ($1, ${name}).
+    fn replace_all(&self, text: &str, replacement: &str, expand: bool) -> (String, usize) {
+        match self {
+            QueryMatcher::Substring(_) => {
+                let (_, spans) = self.find(text, usize::MAX);
+                let mut replaced = String::with_capacity(text.len());
+                let mut last = 0;
+                for &(start, end) in &spans {
+                    replaced.push_str(&text[last..start]);
+                    replaced.push_str(replacement);
+                    last = end;
+                }
+                replaced.push_str(&text[last..]);
+                (replaced, spans.len())
+            }
+            QueryMatcher::Pattern(re) => {
+                let count = re.find_iter(text).count();
+                let replaced = if expand {
+                    re.replace_all(text, replacement)
+                } else {
+                    re.replace_all(text, regex::NoExpand(replacement))
+                };
+                (replaced.into_owned(), count)
+            }
+            // Rejected up front by replace_in_notes
+            QueryMatcher::Boolean(_) => (text.to_string(), 0),
+        }
+    }
+
+    // Number of matches in `text` and the byte spans of the first `limit` of them
+    fn find(&self, text: &str, limit: usize) -> (usize, Vec<(usize, usize)>) {
+        match self {
+            QueryMatcher::Substring(query_lower) => {
+                let text_lower = text.to_lowercase();
+                let count = text_lower.matches(query_lower.as_str()).count();
+                let spans = text_lower
+                    .match_indices(query_lower.as_str())
+                    .take(limit)
+                    .map(|(pos, _)| {
+                        (
+                            lowercase_offset_to_original(text, pos),
+                            lowercase_offset_to_original(text, pos + query_lower.len()),
+                        )
+                    })
+                    .collect();
+                (count, spans)
+            }
+            QueryMatcher::Pattern(re) => {
+                let count = re.find_iter(text).count();
+                let spans = re.find_iter(text).take(limit).map(|m| (m.start(), m.end())).collect();
+                (count, spans)
+            }
+            QueryMatcher::Boolean(query) => query.find(text, limit),
+        }
+    }
+}
+
+// Paths of notes whose tags satisfy the filter, compared case-insensitively
+fn tagged_notes(base_path: &str, tags: &[String], tag_match: TagMatch) -> Result<Vec<PathBuf>, AzimuthError> {
+    let wanted: Vec<String> = tags.iter().map(|tag| tag.to_lowercase()).collect();
+    let settings = load_settings(base_path.to_string())?;
+    let mut notes: Vec<PathBuf> = settings.tags
+        .iter()
+        .filter(|(_, note_tags)| {
+            let has = |tag: &String| note_tags.iter().any(|t| t.to_lowercase() == *tag);
+            match tag_match {
+                TagMatch::All => wanted.iter().all(has),
+                TagMatch::Any => wanted.iter().any(has),
+            }
+        })
+        .map(|(path, _)| PathBuf::from(path))
+        .collect();
+    notes.sort();
+    Ok(notes)
+}
+
+// Directory to walk for a search: the vault root, or the scope folder once it is
+// confirmed to be a directory inside the vault
+fn resolve_search_root(base_path: &str, scope_path: Option<&str>) -> Result<PathBuf, AzimuthError> {
+    let Some(scope) = scope_path.filter(|s| !s.trim().is_empty()) else {
+        return Ok(PathBuf::from(base_path));
+    };
+
+    let scope = Path::new(scope);
+    if scope.components().any(|c| matches!(c, std::path::Component::ParentDir)) {
+        return Err(AzimuthError::InvalidPath(format!("Search scope must not contain '..': {}", scope.display())));
+    }
+    let scope = if scope.is_absolute() { scope.to_path_buf() } else { Path::new(base_path).join(scope) };
+    if !scope.is_dir() {
+        return Err(AzimuthError::InvalidPath(format!("Search scope is not a directory: {}", scope.display())));
+    }
+
+    let root = fs::canonicalize(base_path)?;
+    let canonical_scope = fs::canonicalize(&scope)?;
+    if !canonical_scope.starts_with(&root) {
+        return Err(AzimuthError::InvalidPath(format!("Search scope is outside the notes folder: {}", scope.display())));
+    }
+    Ok(scope)
+}
+
+// A validated search, built up front so bad queries and scopes are reported
+// before any scanning starts
+struct SearchPlan {
+    base_path: String,
+    query: String,
+    options: SearchOptions,
+    matcher: QueryMatcher,
+    search_root: PathBuf,
+    extensions: Option<HashSet<String>>,
+    modified_after: Option<std::time::SystemTime>,
+    modified_before: Option<std::time::SystemTime>,
+    markdown_syntax: regex::Regex,
+}
+
+fn parse_search_time(value: Option<&str>, name: &str) -> Result<Option<std::time::SystemTime>, AzimuthError> {
+    value
+        .map(|value| {
+            chrono::DateTime::parse_from_rfc3339(value)
+                .map(std::time::SystemTime::from)
+                .map_err(|e| AzimuthError::InvalidInput(format!("Invalid {} '{}': {}", name, value, e)))
+        })
+        .transpose()
+}
+
+impl SearchPlan {
+    fn new(base_path: String, query: String, options: SearchOptions) -> Result<Self, AzimuthError> {
+        let matcher = QueryMatcher::new(&query, options.mode, options.case_sensitive)?;
+        let search_root = resolve_search_root(&base_path, options.scope_path.as_deref())?;
+        let extensions: Option<HashSet<String>> = options.extensions.as_ref().map(|list| {
+            list.iter()
+                .map(|ext| ext.trim().trim_start_matches('.').to_lowercase())
+                .collect()
+        });
+        let modified_after = parse_search_time(options.modified_after.as_deref(), "modified_after")?;
+        let modified_before = parse_search_time(options.modified_before.as_deref(), "modified_before")?;
+        let markdown_syntax = regex::Regex::new(MARKDOWN_SYNTAX_PATTERN)?;
+        Ok(SearchPlan {
+            base_path,
+            query,
+            options,
+            matcher,
+            search_root,
+            extensions,
+            modified_after,
+            modified_before,
+            markdown_syntax,
+        })
+    }
+
+    // Files the search covers: the scope (or the tagged notes) minus archived
+    // notebooks, narrowed by extension and modified date
+    fn files(&self) -> Result<Vec<PathBuf>, AzimuthError> {
+        let SearchPlan { base_path, options, search_root, extensions, modified_after, modified_before, .. } = self;
+        let archived = excluded_archive_paths(base_path);
+        let scan = scan_settings(base_path);
+
+        // With a tag filter only the tagged notes need reading, so skip the walk
+        let mut files: Vec<PathBuf> = if options.tags.is_empty() {
+            WalkDir::new(search_root)
+                .into_iter()
+                .filter_entry(|e| {
+                    // Hidden folders hold the trash, snapshots and note history
+                    let name = e.file_name().to_string_lossy();
+                    let ignored = e.depth() > 0 && (name.starts_with('.') || scan.is_ignored(&name));
+                    !ignored && !is_archived(base_path, e.path(), &archived)
+                })
+                .filter_map(|e| e.ok())
+                .filter(|e| e.path().is_file())
+                .filter(|e| !is_search_data_file(e.file_name()))
+                .map(|e| e.into_path())
+                .collect()
+        } else {
+            let mut tagged = tagged_notes(base_path, &options.tags, options.tag_match)?;
+            tagged.retain(|path| {
+                let hidden = path.strip_prefix(search_root).is_ok_and(|relative| {
+                    relative.components().any(|c| c.as_os_str().to_string_lossy().starts_with('.'))
+                });
+                path.starts_with(search_root) && !hidden && path.is_file() && !is_archived(base_path, path, &archived)
+            });
+            tagged
+        };
+
+        files.retain(|path| {
+            let extension = path.extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            if !is_text_extension(&extension) {
+                return false;
+            }
+            if let Some(extensions) = extensions
+                && !extensions.contains(&extension)
+            {
+                return false;
+            }
+            if modified_after.is_some() || modified_before.is_some() {
+                let Ok(modified) = fs::metadata(path).and_then(|m| m.modified()) else { return false };
+                if modified_after.is_some_and(|after| modified < after)
+                    || modified_before.is_some_and(|before| modified > before)
+                {
+                    return false;
+                }
+            }
+            true
+        });
+        Ok(files)
+    }
+
+    // Calls `on_result` for each matching note as soon as it is found
+    fn run(&self, mut on_result: impl FnMut(SearchResult)) -> Result<(), AzimuthError> {
+        let SearchPlan { base_path, query, options, matcher, markdown_syntax, .. } = self;
+        let indexed = indexed_candidates(base_path, query, options.mode);
+        let fuzzy_matcher = options.fuzzy.then(SkimMatcherV2::default);
+
+        for path in &self.files()? {
+            let file_name = path.file_name().unwrap().to_string_lossy().to_string();
+            let name_matches = if matcher.find(&file_name, 0).0 > 0 { 1 } else { 0 };
+            let fuzzy_score = fuzzy_matcher.as_ref().and_then(|fuzzy| {
+                fuzzy.fuzzy_match(&path.file_stem().unwrap().to_string_lossy(), query)
+            });
+
+            // The index only covers content, so a note can still match on its file name
+            if let Some(candidates) = &indexed
+                && !candidates.contains(&index_key(Path::new(base_path), path))
+                && name_matches == 0
+                && fuzzy_score.is_none()
+            {
+                continue;
+            }
+
+            if let Ok(content) = fs::read_to_string(path) {
+                // Count matches in content and filename
+                let (content_matches, spans) = if options.plain_text_only {
+                    let plain = PlainText::new(&content, markdown_syntax);
+                    let (count, spans) = matcher.find(&plain.text, MAX_SNIPPETS);
+                    (count, spans.into_iter().map(|span| plain.span_to_original(span)).collect())
+                } else {
+                    matcher.find(&content, MAX_SNIPPETS)
+                };
+                let total_matches = content_matches + name_matches;
+
+                if total_matches > 0 || fuzzy_score.is_some() {
+                    // Get snippets around the first matches
+                    let snippets = snippets_around(&content, &spans);
+                    // Spans are offsets into `content` even for plain-text searches
+                    let line_number = spans
+                        .first()
+                        .map(|&(start, _)| content[..start].matches('\n').count() + 1)
+                        .unwrap_or(1);
+                    let snippet = snippets
+                        .first()
+                        .cloned()
+                        .unwrap_or_else(|| content.chars().take(100).collect::<String>());
+
+                    // Get notebook info
+                    let parent = path.parent().unwrap();
+                    let notebook_name = parent.file_name()
+                        .map(|n| n.to_string_lossy().to_string())
+                        .unwrap_or_default();
+
+                    on_result(SearchResult {
+                        note_id: file_name,
+                        note_title: path.file_stem().unwrap().to_string_lossy().to_string(),
+                        notebook_path: parent.to_string_lossy().to_string(),
+                        notebook_name,
+                        snippet,
+                        snippets,
+                        match_count: total_matches,
+                        line_number,
+                        fuzzy_score,
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
 }
 
 #[tauri::command]
-fn get_attachment_path(notebook_path: String, note_id: String, file_name: String) -> String {
-    PathBuf::from(&notebook_path)
-        .join(&note_id)
-        .join(&file_name)
-        .to_string_lossy()
-        .to_string()
+fn search_notes(base_path: String, query: String, options: Option<SearchOptions>) -> Result<SearchPage, AzimuthError> {
+    if query.trim().is_empty() {
+        return Ok(SearchPage { results: Vec::new(), total_matches: 0 });
+    }
+
+    let plan = SearchPlan::new(base_path, query, options.unwrap_or_default())?;
+    let mut results = Vec::new();
+    plan.run(|result| results.push(result))?;
+
+    // Sort by relevance descending, then page so the top results come first
+    results.sort_by_key(|result| std::cmp::Reverse(search_relevance(result)));
+    let total_matches = results.len();
+    let results = results
+        .into_iter()
+        .skip(plan.options.offset.unwrap_or(0))
+        .take(plan.options.limit.unwrap_or(usize::MAX))
+        .collect();
+    Ok(SearchPage { results, total_matches })
+}
+
+// Fuzzy name score worth one literal match when ranking, so a strong title match
+// (typically 100+) outranks a handful of passing mentions in the content
+const FUZZY_SCORE_PER_MATCH: i64 = 20;
+
+fn search_relevance(result: &SearchResult) -> i64 {
+    result.match_count as i64 + result.fuzzy_score.unwrap_or(0) / FUZZY_SCORE_PER_MATCH
 }
 
+// Replaces matches of `query` in every file the same search would cover. With
+// `dry_run` nothing is written and the summary shows what would change.
 #[tauri::command]
-fn list_attachments(notebook_path: String, note_id: String) -> Result<Vec<String>, String> {
-    let attachments_dir = PathBuf::from(&notebook_path).join(&note_id);
-    if !attachments_dir.exists() {
-        return Ok(Vec::new());
+fn replace_in_notes(
+    base_path: String,
+    query: String,
+    replacement: String,
+    options: Option<SearchOptions>,
+    dry_run: bool,
+) -> Result<ReplaceSummary, AzimuthError> {
+    if query.is_empty() {
+        return Err(AzimuthError::InvalidInput("Search text must not be empty".to_string()));
     }
-    
-    let mut files = Vec::new();
-    for entry in fs::read_dir(&attachments_dir).map_err(|e| e.to_string())? {
-        let entry = entry.map_err(|e| e.to_string())?;
-        if entry.path().is_file() {
-            files.push(entry.file_name().to_string_lossy().to_string());
+
+    let options = options.unwrap_or_default();
+    if options.mode == SearchMode::Boolean {
+        return Err(AzimuthError::InvalidInput("Boolean queries can't be used for replace".to_string()));
+    }
+    let plan = SearchPlan::new(base_path, query, options)?;
+    let expand = plan.options.mode == SearchMode::Regex;
+    let mut summary = ReplaceSummary { files_changed: 0, occurrences: 0, dry_run, changes: Vec::new() };
+
+    for path in plan.files()? {
+        let Ok(content) = fs::read_to_string(&path) else { continue };
+        let (replaced, occurrences) = plan.matcher.replace_all(&content, &replacement, expand);
+        if occurrences == 0 || replaced == content {
+            continue;
+        }
+
+        if !dry_run {
+            write_atomic(&path, &replaced)?;
+            if let Some(parent) = path.parent() {
+                invalidate_notebook_activity(parent);
+                invalidate_notebook_size(parent);
+            }
         }
+        summary.files_changed += 1;
+        summary.occurrences += occurrences;
+        summary.changes.push(ReplaceChange { path: path.to_string_lossy().to_string(), occurrences });
     }
-    Ok(files)
+    Ok(summary)
+}
+
+// Writes to a temporary sibling first and renames it over the target, so a failed
+// write never leaves a half-written note behind
+fn write_atomic(path: &Path, content: &str) -> Result<(), AzimuthError> {
+    let file_name = path.file_name().ok_or("Could not get file name")?.to_string_lossy();
+    let temp_path = path.with_file_name(format!(".{}.azimuth-tmp", file_name));
+    fs::write(&temp_path, content)?;
+    fs::rename(&temp_path, path).map_err(|e| {
+        let _ = fs::remove_file(&temp_path);
+        e.into()
+    })
+}
+
+#[derive(Clone, Serialize)]
+struct SearchComplete {
+    total: usize,
+    error: Option<String>,
 }
 
+// Streaming search - each match arrives as a `search-result` event in the order
+// found (not sorted, so `limit` and `offset` don't apply), followed by `search-complete`
 #[tauri::command]
-fn import_folder(base_path: String, folder_path: String) -> Result<Notebook, String> {
-    let source = PathBuf::from(&folder_path);
-    if !source.exists() || !source.is_dir() {
-        return Err("Invalid folder path".to_string());
+fn search_notes_async(app: AppHandle, base_path: String, query: String, options: Option<SearchOptions>) -> Result<(), AzimuthError> {
+    if query.trim().is_empty() {
+        let _ = app.emit("search-complete", SearchComplete { total: 0, error: None });
+        return Ok(());
     }
-    
-    let folder_name = source
-        .file_name()
-        .ok_or("Could not get folder name")?
-        .to_string_lossy()
-        .to_string();
-    
-    let dest = PathBuf::from(&base_path).join(&folder_name);
-    
-    if dest.exists() {
-        let children = list_notebooks_simple(&dest)?;
-        return Ok(Notebook {
-            id: dest.to_string_lossy().to_string(),
-            name: folder_name,
-            path: dest.to_string_lossy().to_string(),
-            children,
+
+    let plan = SearchPlan::new(base_path, query, options.unwrap_or_default())?;
+    std::thread::spawn(move || {
+        let mut total = 0;
+        let outcome = plan.run(|result| {
+            total += 1;
+            let _ = app.emit("search-result", result);
         });
-    }
-    
-    fs::create_dir_all(&dest).map_err(|e| e.to_string())?;
-    import_folder_contents(&source, &dest).map_err(|e| e.to_string())?;
-    
-    let children = list_notebooks_simple(&dest)?;
-    Ok(Notebook {
-        id: dest.to_string_lossy().to_string(),
-        name: folder_name,
-        path: dest.to_string_lossy().to_string(),
-        children,
-    })
+        let _ = app.emit("search-complete", SearchComplete { total, error: outcome.err().map(String::from) });
+    });
+    Ok(())
 }
 
-fn import_folder_contents(src: &PathBuf, dst: &PathBuf) -> std::io::Result<()> {
-    for entry in fs::read_dir(src)? {
-        let entry = entry?;
-        let src_path = entry.path();
-        let file_name = entry.file_name().to_string_lossy().to_string();
-        
-        if src_path.is_dir() {
-            let dst_subdir = dst.join(&file_name);
-            copy_dir_recursive(&src_path, &dst_subdir)?;
-        } else if src_path.is_file() {
-            let dst_path = dst.join(&file_name);
-            fs::copy(&src_path, &dst_path)?;
+// Search index
+// Inverted index of lowercased words to the notes containing them, so a search
+// only has to read the notes that can possibly match. Paths are relative to the
+// notes folder and each note's mtime is recorded for incremental updates.
+const SEARCH_INDEX_FILE: &str = ".azimuth_index.json";
+
+// Search's own bookkeeping files, which would otherwise match every query
+fn is_search_data_file(name: &std::ffi::OsStr) -> bool {
+    name == SEARCH_INDEX_FILE || name == SEARCH_HISTORY_FILE
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+struct SearchIndex {
+    files: HashMap<String, u64>,
+    terms: HashMap<String, HashSet<String>>,
+}
+
+fn index_key(base: &Path, path: &Path) -> String {
+    path.strip_prefix(base)
+        .map(|p| p.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/"))
+        .unwrap_or_default()
+}
+
+fn index_terms(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(|term| term.to_lowercase())
+        .collect()
+}
+
+fn modified_nanos(path: &Path) -> u64 {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+// Every file search_notes would scan, with its current mtime
+fn indexable_files(base: &Path) -> HashMap<String, u64> {
+    let scan = scan_settings(&base.to_string_lossy());
+    WalkDir::new(base)
+        .into_iter()
+        .filter_entry(|e| {
+            let name = e.file_name().to_string_lossy();
+            e.depth() == 0 || !(name.starts_with('.') || scan.is_ignored(&name))
+        })
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && !is_search_data_file(e.file_name()))
+        .filter(|e| {
+            let extension = e.path().extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            is_text_extension(&extension)
+        })
+        .map(|e| (index_key(base, e.path()), modified_nanos(e.path())))
+        .collect()
+}
+
+fn load_search_index(base: &Path) -> Option<SearchIndex> {
+    let content = fs::read_to_string(base.join(SEARCH_INDEX_FILE)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+// Re-reads only notes that are new or whose mtime changed, and drops removed ones.
+// Returns the number of notes (re)indexed.
+fn update_search_index(base: &Path, index: &mut SearchIndex) -> usize {
+    let current = indexable_files(base);
+    let stale: HashSet<String> = index.files
+        .iter()
+        .filter(|(key, modified)| current.get(*key) != Some(*modified))
+        .map(|(key, _)| key.clone())
+        .collect();
+    let added: Vec<String> = current
+        .keys()
+        .filter(|key| !index.files.contains_key(*key) || stale.contains(*key))
+        .cloned()
+        .collect();
+
+    if !stale.is_empty() {
+        for notes in index.terms.values_mut() {
+            notes.retain(|key| !stale.contains(key));
         }
+        index.terms.retain(|_, notes| !notes.is_empty());
+        index.files.retain(|key, _| !stale.contains(key));
     }
-    Ok(())
+
+    for key in &added {
+        let content = fs::read_to_string(base.join(key)).unwrap_or_default();
+        for term in index_terms(&content) {
+            index.terms.entry(term).or_default().insert(key.clone());
+        }
+        index.files.insert(key.clone(), current[key]);
+    }
+    added.len()
 }
 
 #[tauri::command]
-fn is_directory(path: String) -> bool {
-    PathBuf::from(&path).is_dir()
+fn rebuild_search_index(base_path: String) -> Result<usize, AzimuthError> {
+    let base = PathBuf::from(&base_path);
+    let mut index = load_search_index(&base).unwrap_or_default();
+    let updated = update_search_index(&base, &mut index);
+    let json = serde_json::to_string(&index)?;
+    fs::write(base.join(SEARCH_INDEX_FILE), json)?;
+    Ok(updated)
 }
 
-#[tauri::command]
-fn move_notebook(source_path: String, target_path: String) -> Result<(), String> {
-    let source = PathBuf::from(&source_path);
-    let target_dir = PathBuf::from(&target_path);
-    
-    if !source.exists() {
-        return Err(format!("Source folder does not exist: {}", source_path));
+// Notes that may contain the query according to the index, or None when the index
+// can't answer: no index, any note changed since it was built, a regex or boolean
+// query, or a query without any word characters. Each word of the query must appear
+// within some indexed word of the note.
+fn indexed_candidates(base_path: &str, query: &str, mode: SearchMode) -> Option<HashSet<String>> {
+    if matches!(mode, SearchMode::Regex | SearchMode::Boolean) {
+        return None;
     }
-    
-    if !source.is_dir() {
-        return Err(format!("Source is not a directory: {}", source_path));
+    let words = index_terms(query);
+    if words.is_empty() {
+        return None;
     }
-    
-    if !target_dir.exists() {
-        return Err(format!("Target folder does not exist: {}", target_path));
+    let base = Path::new(base_path);
+    let index = load_search_index(base)?;
+    if indexable_files(base) != index.files {
+        return None;
     }
-    
-    let folder_name = source
-        .file_name()
-        .ok_or("Could not get folder name")?
-        .to_string_lossy()
-        .to_string();
-    
-    let destination = target_dir.join(&folder_name);
-    
-    // Check if destination already exists
-    if destination.exists() {
-        return Err(format!("A folder named '{}' already exists in the target location", folder_name));
+
+    let mut candidates: Option<HashSet<String>> = None;
+    for word in &words {
+        let notes: HashSet<String> = index.terms
+            .iter()
+            .filter(|(term, _)| match mode {
+                SearchMode::WholeWord => *term == word,
+                _ => term.contains(word.as_str()),
+            })
+            .flat_map(|(_, notes)| notes.iter().cloned())
+            .collect();
+        candidates = Some(match candidates {
+            Some(existing) => existing.intersection(&notes).cloned().collect(),
+            None => notes,
+        });
     }
-    
-    // Check if trying to move into itself
-    if target_dir.starts_with(&source) {
-        return Err("Cannot move a folder into itself".to_string());
+    candidates
+}
+
+// Search history
+const SEARCH_HISTORY_FILE: &str = ".azimuth_search_history.json";
+const MAX_SEARCH_HISTORY: usize = 100;
+
+fn load_search_history(base_path: &str) -> Vec<SearchHistoryEntry> {
+    fs::read_to_string(PathBuf::from(base_path).join(SEARCH_HISTORY_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+// Records a query as the most recent, dropping any earlier copy of it
+#[tauri::command]
+fn save_search_query(base_path: String, query: String) -> Result<(), AzimuthError> {
+    let query = query.trim().to_string();
+    if query.is_empty() {
+        return Ok(());
     }
-    
-    // Perform the move (rename)
-    match fs::rename(&source, &destination) {
-        Ok(_) => Ok(()),
-        Err(e) => {
-            // If rename fails (e.g., cross-device), try copy and delete
-            copy_dir_recursive(&source, &destination)
-                .map_err(|copy_err| format!("Failed to move folder: {} (copy failed: {})", e, copy_err))?;
-            fs::remove_dir_all(&source)
-                .map_err(|del_err| format!("Folder copied but failed to remove original: {}", del_err))?;
-            Ok(())
+
+    let mut history = load_search_history(&base_path);
+    history.retain(|entry| entry.query != query);
+    history.insert(0, SearchHistoryEntry { query, searched_at: chrono::Utc::now().to_rfc3339() });
+    history.truncate(MAX_SEARCH_HISTORY);
+
+    let json = serde_json::to_string_pretty(&history)?;
+    fs::write(PathBuf::from(&base_path).join(SEARCH_HISTORY_FILE), json)?;
+    Ok(())
+}
+
+// Most recent first
+#[tauri::command]
+fn get_search_history(base_path: String, limit: Option<usize>) -> Result<Vec<SearchHistoryEntry>, AzimuthError> {
+    let mut history = load_search_history(&base_path);
+    history.truncate(limit.unwrap_or(MAX_SEARCH_HISTORY));
+    Ok(history)
+}
+
+// Removes fenced code blocks and inline code spans so scanners don't pick up
+// links or tags from code samples
+fn strip_code(content: &str) -> String {
+    let mut stripped = String::with_capacity(content.len());
+    let mut fence: Option<&str> = None;
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        match fence {
+            Some(marker) => {
+                if trimmed.starts_with(marker) {
+                    fence = None;
+                }
+            }
+            None if trimmed.starts_with("```") => fence = Some("```"),
+            None if trimmed.starts_with("~~~") => fence = Some("~~~"),
+            None => {
+                stripped.push_str(line);
+                stripped.push('\n');
+            }
+        }
+    }
+
+    let inline_code = regex::Regex::new(r"`[^`\n]*`").expect("valid inline code pattern");
+    inline_code.replace_all(&stripped, "").to_string()
+}
+
+// Fence lines, links and images (keeping their text), HTML tags and bare URLs
+const MARKDOWN_SYNTAX_PATTERN: &str =
+    r"(?m)^[ \t]*(?:```|~~~).*$|!?\[(?P<text>[^\]\n]*)\]\([^)\n]*\)|<[^>\n]+>|https?://[^\s)>\]]+";
+
+// Note text with Markdown syntax removed. Each kept run of text is copied verbatim,
+// so offsets into `text` map back to the original by segment.
+struct PlainText {
+    text: String,
+    // (offset in text, offset in original) where each kept run starts
+    segments: Vec<(usize, usize)>,
+}
+
+impl PlainText {
+    fn new(content: &str, syntax: &regex::Regex) -> Self {
+        let mut plain = PlainText { text: String::with_capacity(content.len()), segments: Vec::new() };
+        let mut last = 0;
+        for caps in syntax.captures_iter(content) {
+            let whole = caps.get(0).unwrap();
+            plain.keep(content, last, whole.start());
+            if let Some(text) = caps.name("text") {
+                plain.keep(content, text.start(), text.end());
+            }
+            last = whole.end();
+        }
+        plain.keep(content, last, content.len());
+        plain
+    }
+
+    fn keep(&mut self, content: &str, start: usize, end: usize) {
+        if start < end {
+            self.segments.push((self.text.len(), start));
+            self.text.push_str(&content[start..end]);
+        }
+    }
+
+    fn to_original(&self, offset: usize) -> usize {
+        let index = self.segments.partition_point(|&(plain_start, _)| plain_start <= offset);
+        match index.checked_sub(1).map(|i| self.segments[i]) {
+            Some((plain_start, original_start)) => original_start + (offset - plain_start),
+            None => 0,
+        }
+    }
+
+    // Maps a match span, taking the end from its last byte so it doesn't jump
+    // past syntax that was removed right after the match
+    fn span_to_original(&self, (start, end): (usize, usize)) -> (usize, usize) {
+        let original_start = self.to_original(start);
+        let original_end = if end > start { self.to_original(end - 1) + 1 } else { original_start };
+        (original_start, original_end)
+    }
+}
+
+// Text notes in the vault, skipping hidden and ignored folders
+fn vault_text_files(base_path: &str) -> Vec<PathBuf> {
+    let scan = scan_settings(base_path);
+    WalkDir::new(base_path)
+        .into_iter()
+        .filter_entry(|e| {
+            let name = e.file_name().to_string_lossy();
+            e.depth() == 0 || !(name.starts_with('.') || scan.is_ignored(&name))
+        })
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_file())
+        .map(|e| e.into_path())
+        .filter(|path| {
+            let extension = path.extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            is_text_extension(&extension)
+        })
+        .collect()
+}
+
+// External links
+#[tauri::command]
+fn collect_external_links(base_path: String) -> Result<Vec<ExternalLink>, AzimuthError> {
+    let url_re = regex::Regex::new(r#"https?://[^\s<>()\[\]"'`]+"#)?;
+    let mut links: HashMap<String, Vec<String>> = HashMap::new();
+
+    for path in vault_text_files(&base_path) {
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+        let note_path = path.to_string_lossy().to_string();
+        for url in url_re.find_iter(&strip_code(&content)) {
+            // Sentence punctuation directly after a bare URL isn't part of it
+            let url = url.as_str().trim_end_matches(['.', ',', ';', ':', '!', '?']);
+            let notes = links.entry(url.to_string()).or_default();
+            if !notes.contains(&note_path) {
+                notes.push(note_path.clone());
+            }
+        }
+    }
+
+    let mut links: Vec<ExternalLink> = links
+        .into_iter()
+        .map(|(url, notes)| ExternalLink { url, notes })
+        .collect();
+    links.sort_by(|a, b| a.url.cmp(&b.url));
+    Ok(links)
+}
+
+// Wikilinks
+// Targets of `[[Target]]`, `[[Target|alias]]` and `[[Target#heading]]` links with
+// their spans in `content`, leaving out links inside code
+fn find_wikilinks(content: &str) -> Vec<(String, (usize, usize))> {
+    let link_re = regex::Regex::new(r"\[\[([^\[\]|#\n]+)(?:#[^\[\]|\n]*)?(?:\|[^\[\]\n]*)?\]\]").expect("valid wikilink pattern");
+    let code_re = regex::Regex::new(r"`[^`\n]*`").expect("valid inline code pattern");
+    let mut links = Vec::new();
+    let mut fence: Option<&str> = None;
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+        let trimmed = line.trim_start();
+        match fence {
+            Some(marker) => {
+                if trimmed.starts_with(marker) {
+                    fence = None;
+                }
+                continue;
+            }
+            None if trimmed.starts_with("```") => {
+                fence = Some("```");
+                continue;
+            }
+            None if trimmed.starts_with("~~~") => {
+                fence = Some("~~~");
+                continue;
+            }
+            None => {}
+        }
+        let code: Vec<(usize, usize)> = code_re.find_iter(line).map(|m| (m.start(), m.end())).collect();
+        for caps in link_re.captures_iter(line) {
+            let whole = caps.get(0).unwrap();
+            if code.iter().any(|&(start, end)| whole.start() < end && start < whole.end()) {
+                continue;
+            }
+            let target = caps[1].trim().to_string();
+            if !target.is_empty() {
+                links.push((target, (line_start + whole.start(), line_start + whole.end())));
+            }
+        }
+    }
+    links
+}
+
+// What a link target names: its last path segment, without a Markdown extension,
+// compared case-insensitively
+fn wikilink_key(target: &str) -> String {
+    let name = target.trim().rsplit(['/', '\\']).next().unwrap_or_default();
+    let name = match name.rsplit_once('.') {
+        Some((stem, extension)) if is_markdown_extension(&extension.to_lowercase()) => stem,
+        _ => name,
+    };
+    name.to_lowercase()
+}
+
+// Notes whose wikilinks point at `note_title`, with a snippet around each link
+#[tauri::command]
+fn get_backlinks(base_path: String, note_title: String) -> Result<Vec<SearchResult>, AzimuthError> {
+    let key = wikilink_key(&note_title);
+    let mut results = Vec::new();
+    for path in vault_text_files(&base_path) {
+        let Ok(content) = fs::read_to_string(&path) else { continue };
+        let spans: Vec<(usize, usize)> = find_wikilinks(&content)
+            .into_iter()
+            .filter(|(target, _)| wikilink_key(target) == key)
+            .map(|(_, span)| span)
+            .collect();
+        let Some(&(first, _)) = spans.first() else { continue };
+
+        let snippets = snippets_around(&content, &spans[..spans.len().min(MAX_SNIPPETS)]);
+        let parent = path.parent().unwrap_or(Path::new(&base_path));
+        results.push(SearchResult {
+            note_id: path.file_name().unwrap_or_default().to_string_lossy().to_string(),
+            note_title: path.file_stem().unwrap_or_default().to_string_lossy().to_string(),
+            notebook_path: parent.to_string_lossy().to_string(),
+            notebook_name: parent.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+            snippet: snippets.first().cloned().unwrap_or_default(),
+            snippets,
+            match_count: spans.len(),
+            line_number: content[..first].matches('\n').count() + 1,
+            fuzzy_score: None,
+        });
+    }
+    results.sort_by_key(|result| result.note_title.to_lowercase());
+    Ok(results)
+}
+
+// The note's wikilinks in order, each resolved to a note in the vault when one
+// matches: a path relative to the vault root first, then by name, nearest the
+// linking note's notebook first
+#[tauri::command]
+fn get_outgoing_links(notebook_path: String, note_id: String) -> Result<Vec<OutgoingLink>, AzimuthError> {
+    let notebook = PathBuf::from(&notebook_path);
+    let content = fs::read_to_string(safe_join(&notebook, &note_id)?)?;
+    let vault = notebook
+        .ancestors()
+        .find(|dir| dir.join(".azimuth_settings.json").exists())
+        .unwrap_or(&notebook)
+        .to_path_buf();
+
+    let mut by_name: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    for path in vault_text_files(&vault.to_string_lossy()) {
+        let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        by_name.entry(wikilink_key(&name)).or_default().push(path);
+    }
+    // Fewest steps from the notebook wins, then alphabetical
+    let distance = |path: &Path| {
+        let parent = path.parent().unwrap_or(path);
+        let shared = parent.components().zip(notebook.components()).take_while(|(a, b)| a == b).count();
+        parent.components().count() + notebook.components().count() - 2 * shared
+    };
+
+    let mut links: Vec<OutgoingLink> = Vec::new();
+    for (target, _) in find_wikilinks(&content) {
+        if links.iter().any(|link| link.target == target) {
+            continue;
+        }
+        let relative = vault.join(&target);
+        let by_path = [relative.clone(), relative.with_extension("md")]
+            .into_iter()
+            .find(|path| path.is_file() && path.starts_with(&vault));
+        let path = by_path.or_else(|| {
+            let mut candidates = by_name.get(&wikilink_key(&target))?.clone();
+            candidates.sort_by_key(|path| (distance(path), path.clone()));
+            candidates.into_iter().next()
+        });
+        links.push(OutgoingLink {
+            target,
+            path: path.map(|path| path.to_string_lossy().to_string()),
+        });
+    }
+    Ok(links)
+}
+
+const SNIPPET_CONTEXT: usize = 50;
+const MAX_SNIPPETS: usize = 3;
+
+fn floor_char_boundary(s: &str, index: usize) -> usize {
+    let mut index = index.min(s.len());
+    while !s.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+fn ceil_char_boundary(s: &str, index: usize) -> usize {
+    let mut index = index.min(s.len());
+    while !s.is_char_boundary(index) {
+        index += 1;
+    }
+    index
+}
+
+// Lowercasing can change a character's UTF-8 length, so byte offsets found in the
+// lowercased copy have to be mapped back before slicing the original
+fn lowercase_offset_to_original(original: &str, lower_offset: usize) -> usize {
+    let mut lower_len = 0;
+    for (index, c) in original.char_indices() {
+        if lower_len >= lower_offset {
+            return index;
+        }
+        lower_len += c.to_lowercase().map(char::len_utf8).sum::<usize>();
+    }
+    original.len()
+}
+
+// Snippet of roughly SNIPPET_CONTEXT bytes either side of a match, widened to
+// character boundaries so multibyte text can't cause a slicing panic
+fn snippet_window(content: &str, match_start: usize, match_end: usize) -> (usize, usize) {
+    (
+        floor_char_boundary(content, match_start.saturating_sub(SNIPPET_CONTEXT)),
+        ceil_char_boundary(content, match_end + SNIPPET_CONTEXT),
+    )
+}
+
+// One snippet per match span, merging windows that overlap so nearby matches
+// don't show the same text twice
+fn snippets_around(content: &str, spans: &[(usize, usize)]) -> Vec<String> {
+    let mut windows: Vec<(usize, usize)> = Vec::new();
+    for &(match_start, match_end) in spans {
+        let (start, end) = snippet_window(content, match_start, match_end);
+        match windows.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => windows.push((start, end)),
+        }
+    }
+    windows.into_iter().map(|(start, end)| format_snippet(content, start, end)).collect()
+}
+
+fn format_snippet(content: &str, start: usize, end: usize) -> String {
+    let mut s = content[start..end].to_string();
+    if start > 0 { s = format!("...{}", s); }
+    if end < content.len() { s = format!("{}...", s); }
+    s.replace('\n', " ")
+}
+
+// Default directories to skip when scanning for notebooks (see AppSettings::ignored_dirs)
+const IGNORED_DIRS: &[&str] = &[
+    ".", "..", ".git", ".svn", ".hg", "node_modules", "target", "build", "dist",
+    ".Trash", ".Spotlight-V100", ".fseventsd", "Library", "Applications",
+    ".cache", ".npm", ".cargo", ".rustup", ".local", ".config",
+    "__pycache__", ".venv", "venv", ".tox", ".pytest_cache",
+    ".DS_Store", "Thumbs.db",
+];
+
+#[derive(Clone, Serialize)]
+struct LoadComplete {
+    notebooks: Vec<Notebook>,
+}
+
+const MAX_NOTEBOOKS: usize = 50;
+const MAX_ENTRIES_TO_SCAN: usize = 200;
+
+// Scan settings for a folder: its own settings when it is the notes folder,
+// otherwise the configured notes folder's, so nested listings follow the same rules
+fn scan_settings(path: &str) -> ScanSettings {
+    let settings = if PathBuf::from(path).join(".azimuth_settings.json").exists() {
+        load_settings(path.to_string())
+    } else {
+        get_notes_dir().and_then(load_settings)
+    };
+    ScanSettings::from_settings(&settings.unwrap_or_default())
+}
+
+#[tauri::command]
+fn get_scan_settings(base_path: String) -> Result<ScanSettings, AzimuthError> {
+    Ok(ScanSettings::from_settings(&load_settings(base_path)?))
+}
+
+#[tauri::command]
+fn set_scan_settings(base_path: String, scan: ScanSettings) -> Result<ScanSettings, AzimuthError> {
+    if scan.max_notebooks == 0 || scan.max_entries_to_scan == 0 {
+        return Err(AzimuthError::InvalidInput("Scan limits must be greater than zero".to_string()));
+    }
+    update_settings(&base_path, |settings| {
+        settings.ignored_dirs = scan.ignored_dirs.clone();
+        settings.max_notebooks = scan.max_notebooks;
+        settings.max_entries_to_scan = scan.max_entries_to_scan;
+    })?;
+    Ok(scan)
+}
+
+// How long a computed notebook activity time is trusted. Editing an existing note
+// doesn't touch its folder's mtime, so the folder mtime alone can't invalidate it.
+const ACTIVITY_CACHE_TTL: std::time::Duration = std::time::Duration::from_secs(30);
+
+struct ActivityEntry {
+    computed_at: std::time::Instant,
+    dir_modified: Option<std::time::SystemTime>,
+    activity: std::time::SystemTime,
+}
+
+static NOTEBOOK_ACTIVITY: LazyLock<Mutex<HashMap<PathBuf, ActivityEntry>>> = LazyLock::new(Default::default);
+
+// Most recent modification time of the notebook folder or any file directly in it
+fn notebook_activity(path: &Path) -> std::time::SystemTime {
+    let dir_modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+
+    if let Ok(cache) = NOTEBOOK_ACTIVITY.lock()
+        && let Some(entry) = cache.get(path)
+        && entry.dir_modified == dir_modified
+        && entry.computed_at.elapsed() < ACTIVITY_CACHE_TTL
+    {
+        return entry.activity;
+    }
+
+    let mut activity = dir_modified.unwrap_or(std::time::UNIX_EPOCH);
+    if let Ok(entries) = fs::read_dir(path) {
+        for entry in entries.filter_map(|e| e.ok()) {
+            if let Ok(modified) = entry.metadata().and_then(|m| m.modified()) {
+                activity = activity.max(modified);
+            }
+        }
+    }
+
+    if let Ok(mut cache) = NOTEBOOK_ACTIVITY.lock() {
+        cache.insert(path.to_path_buf(), ActivityEntry {
+            computed_at: std::time::Instant::now(),
+            dir_modified,
+            activity,
+        });
+    }
+    activity
+}
+
+fn invalidate_notebook_activity(path: &Path) {
+    if let Ok(mut cache) = NOTEBOOK_ACTIVITY.lock() {
+        cache.remove(path);
+    }
+}
+
+// "name" (default) sorts alphabetically, "recent" puts the most recently touched notebooks first
+fn sort_notebooks(notebooks: &mut [Notebook], sort_mode: &str) {
+    match sort_mode {
+        "recent" => {
+            notebooks.sort_by_cached_key(|nb| {
+                (std::cmp::Reverse(notebook_activity(Path::new(&nb.path))), nb.name.to_lowercase())
+            });
+        }
+        _ => notebooks.sort_by_key(|nb| nb.name.to_lowercase()),
+    }
+}
+
+fn validate_sort_mode(sort_mode: Option<String>) -> Result<String, AzimuthError> {
+    let sort_mode = sort_mode.unwrap_or_else(|| "name".to_string());
+    match sort_mode.as_str() {
+        "name" | "recent" => Ok(sort_mode),
+        _ => Err(AzimuthError::InvalidInput(format!("Invalid sort mode: {}", sort_mode))),
+    }
+}
+
+// Trigger async notebook loading - results come via events
+#[tauri::command]
+fn list_notebooks_async(app: AppHandle, base_path: String, sort_mode: Option<String>) -> Result<(), AzimuthError> {
+    let sort_mode = validate_sort_mode(sort_mode)?;
+    std::thread::spawn(move || {
+        let path = PathBuf::from(&base_path);
+        if !path.exists() {
+            let _ = fs::create_dir_all(&path);
+        }
+        let archived = excluded_archive_paths(&base_path);
+        let scan = scan_settings(&base_path);
+        
+        let mut notebooks = Vec::new();
+        let mut scanned = 0;
+        
+        if let Ok(read_dir) = std::fs::read_dir(&path) {
+            for entry in read_dir.filter_map(|e| e.ok()) {
+                scanned += 1;
+                
+                if scanned > scan.max_entries_to_scan {
+                    break;
+                }
+                
+                let name = entry.file_name().to_string_lossy().to_string();
+                
+                if name.starts_with('.') || scan.is_ignored(&name) {
+                    continue;
+                }
+                
+                let is_dir = entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false);
+                if !is_dir || is_hidden_archive(&base_path, &entry.path(), &archived) {
+                    continue;
+                }
+                
+                notebooks.push(Notebook {
+                    id: entry.path().to_string_lossy().to_string(),
+                    name: name.clone(),
+                    path: entry.path().to_string_lossy().to_string(),
+                    children: vec![Notebook {
+                        id: String::new(),
+                        name: String::new(),
+                        path: String::new(),
+                        children: vec![],
+                    }],
+                });
+                
+                if notebooks.len() >= scan.max_notebooks {
+                    break;
+                }
+            }
+        }
+        
+        sort_notebooks(&mut notebooks, &sort_mode);
+        let _ = app.emit("load-complete", LoadComplete { notebooks });
+    });
+    Ok(())
+}
+
+const NOTEBOOK_TREE_MAX_DEPTH: usize = 8;
+
+// Trigger loading the whole nested notebook tree, down to `max_depth` levels -
+// the result comes via a "notebook-tree-loaded" event
+#[tauri::command]
+fn list_notebook_tree(app: AppHandle, base_path: String, max_depth: Option<usize>, sort_mode: Option<String>) -> Result<(), AzimuthError> {
+    let sort_mode = validate_sort_mode(sort_mode)?;
+    let max_depth = max_depth.unwrap_or(NOTEBOOK_TREE_MAX_DEPTH);
+    std::thread::spawn(move || {
+        let path = PathBuf::from(&base_path);
+        if !path.exists() {
+            let _ = fs::create_dir_all(&path);
+        }
+        let archived = excluded_archive_paths(&base_path);
+        let scan = scan_settings(&base_path);
+        let mut visited = HashSet::new();
+        if let Ok(root) = fs::canonicalize(&path) {
+            visited.insert(root);
+        }
+        let notebooks = notebook_tree(&base_path, &path, max_depth, &scan, &archived, &sort_mode, &mut visited);
+        let _ = app.emit("notebook-tree-loaded", LoadComplete { notebooks });
+    });
+    Ok(())
+}
+
+// Notebooks under `path` with their children filled in. `visited` holds the
+// canonical folders already listed, so a symlink back up the tree isn't followed.
+fn notebook_tree(
+    base_path: &str,
+    path: &Path,
+    depth: usize,
+    scan: &ScanSettings,
+    archived: &[String],
+    sort_mode: &str,
+    visited: &mut HashSet<PathBuf>,
+) -> Vec<Notebook> {
+    let mut notebooks = Vec::new();
+    if depth == 0 {
+        return notebooks;
+    }
+    let Ok(read_dir) = fs::read_dir(path) else {
+        return notebooks;
+    };
+
+    for entry in read_dir.filter_map(|e| e.ok()).take(scan.max_entries_to_scan) {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') || scan.is_ignored(&name) {
+            continue;
+        }
+        let entry_path = entry.path();
+        if !entry_path.is_dir() || is_hidden_archive(base_path, &entry_path, archived) {
+            continue;
+        }
+        let Ok(canonical) = fs::canonicalize(&entry_path) else {
+            continue;
+        };
+        if !visited.insert(canonical) {
+            continue;
+        }
+
+        let children = notebook_tree(base_path, &entry_path, depth - 1, scan, archived, sort_mode, visited);
+        notebooks.push(Notebook {
+            id: entry_path.to_string_lossy().to_string(),
+            name,
+            path: entry_path.to_string_lossy().to_string(),
+            children,
+        });
+        if notebooks.len() >= scan.max_notebooks {
+            break;
+        }
+    }
+
+    sort_notebooks(&mut notebooks, sort_mode);
+    notebooks
+}
+
+// Synchronous version for lazy-loading children (small directories)
+#[tauri::command]
+fn list_notebooks(base_path: String, sort_mode: Option<String>) -> Result<Vec<Notebook>, AzimuthError> {
+    let sort_mode = validate_sort_mode(sort_mode)?;
+    let path = PathBuf::from(&base_path);
+    if !path.exists() {
+        fs::create_dir_all(&path)?;
+    }
+    let mut notebooks = list_notebooks_simple(&path, &scan_settings(&base_path))?;
+    let archived = excluded_archive_paths(&base_path);
+    notebooks.retain(|nb| !is_hidden_archive(&base_path, Path::new(&nb.path), &archived));
+    if sort_mode != "name" {
+        sort_notebooks(&mut notebooks, &sort_mode);
+    }
+    Ok(notebooks)
+}
+
+// Simple version for import_folder (no progress needed)
+fn list_notebooks_simple(path: &PathBuf, scan: &ScanSettings) -> Result<Vec<Notebook>, AzimuthError> {
+    let mut notebooks = Vec::new();
+    
+    let entries = match fs::read_dir(path) {
+        Ok(e) => e,
+        Err(_) => return Ok(Vec::new()),
+    };
+    
+    for entry in entries.filter_map(|e| e.ok()) {
+        if notebooks.len() >= scan.max_notebooks {
+            break;
+        }
+
+        let is_dir = match entry.metadata() {
+            Ok(m) => m.is_dir(),
+            Err(_) => continue,
+        };
+        
+        if !is_dir {
+            continue;
+        }
+        
+        let name = entry.file_name().to_string_lossy().to_string();
+        
+        if name.starts_with('.') || scan.is_ignored(&name) {
+            continue;
+        }
+        
+        notebooks.push(Notebook {
+            id: entry.path().to_string_lossy().to_string(),
+            name: name.clone(),
+            path: entry.path().to_string_lossy().to_string(),
+            children: vec![Notebook {
+                id: String::new(),
+                name: String::new(),
+                path: String::new(),
+                children: vec![],
+            }],
+        });
+    }
+    
+    notebooks.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+    Ok(notebooks)
+}
+
+// Notebook sizes
+const NOTEBOOK_SIZE_CACHE_FILE: &str = "notebook_sizes.json";
+
+// Cached per directory rather than per tree: adding, removing or renaming a child
+// bumps the directory's mtime, so an entry stays valid until then. In-place edits
+// don't, which is why save_note and friends invalidate explicitly.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct DirSizeEntry {
+    modified: Option<std::time::SystemTime>,
+    files_size: u64,
+    subdirs: Vec<String>,
+}
+
+static NOTEBOOK_SIZES: LazyLock<Mutex<HashMap<String, DirSizeEntry>>> = LazyLock::new(|| {
+    let cache = get_notebook_size_cache_path()
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    Mutex::new(cache)
+});
+
+fn get_notebook_size_cache_path() -> Result<PathBuf, AzimuthError> {
+    Ok(get_app_config_path()?.with_file_name(NOTEBOOK_SIZE_CACHE_FILE))
+}
+
+fn save_notebook_size_cache(cache: &HashMap<String, DirSizeEntry>) -> Result<(), AzimuthError> {
+    let content = serde_json::to_string(cache)?;
+    Ok(fs::write(get_notebook_size_cache_path()?, content)?)
+}
+
+// Total size of the files under `path`, only re-reading directories whose mtime changed
+fn cached_dir_size(path: &Path, cache: &mut HashMap<String, DirSizeEntry>, dirty: &mut bool) -> u64 {
+    let key = path.to_string_lossy().to_string();
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+
+    let entry = match cache.get(&key) {
+        Some(entry) if entry.modified.is_some() && entry.modified == modified => entry.clone(),
+        _ => {
+            let mut entry = DirSizeEntry { modified, files_size: 0, subdirs: Vec::new() };
+            if let Ok(entries) = fs::read_dir(path) {
+                for child in entries.filter_map(|e| e.ok()) {
+                    let Ok(file_type) = child.file_type() else { continue };
+                    if file_type.is_dir() {
+                        entry.subdirs.push(child.file_name().to_string_lossy().to_string());
+                    } else if file_type.is_file() {
+                        entry.files_size += child.metadata().map(|m| m.len()).unwrap_or(0);
+                    }
+                }
+            }
+            cache.insert(key, entry.clone());
+            *dirty = true;
+            entry
+        }
+    };
+
+    entry.files_size
+        + entry
+            .subdirs
+            .iter()
+            .map(|name| cached_dir_size(&path.join(name), cache, dirty))
+            .sum::<u64>()
+}
+
+fn invalidate_notebook_size(path: &Path) {
+    if let Ok(mut cache) = NOTEBOOK_SIZES.lock()
+        && cache.remove(path.to_string_lossy().as_ref()).is_some()
+    {
+        let _ = save_notebook_size_cache(&cache);
+    }
+}
+
+#[tauri::command]
+fn get_notebook_size(notebook_path: String) -> Result<u64, AzimuthError> {
+    let path = PathBuf::from(&notebook_path);
+    if !path.is_dir() {
+        return Err(AzimuthError::NotFound(format!("Notebook does not exist: {}", notebook_path)));
+    }
+
+    let mut cache = NOTEBOOK_SIZES.lock()?;
+    let mut dirty = false;
+    let size = cached_dir_size(&path, &mut cache, &mut dirty);
+    if dirty {
+        save_notebook_size_cache(&cache)?;
+    }
+    Ok(size)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NotebookStats {
+    // Text files; everything else counts as an attachment
+    pub notes: usize,
+    pub attachments: usize,
+    pub total_size: u64,
+    pub last_modified: Option<String>,
+    // File counts keyed by lowercase extension ("" for files without one)
+    pub by_extension: HashMap<String, usize>,
+}
+
+// Hidden and ignored folders are skipped. Only the notebook's own files are
+// counted unless `recursive`.
+#[tauri::command]
+fn notebook_stats(notebook_path: String, recursive: Option<bool>) -> Result<NotebookStats, AzimuthError> {
+    let path = PathBuf::from(&notebook_path);
+    if !path.is_dir() {
+        return Err(AzimuthError::NotFound(format!("Notebook does not exist: {}", notebook_path)));
+    }
+    let scan = scan_settings(&notebook_path);
+    let max_depth = if recursive.unwrap_or(false) { usize::MAX } else { 1 };
+
+    let mut stats = NotebookStats {
+        notes: 0,
+        attachments: 0,
+        total_size: 0,
+        last_modified: None,
+        by_extension: HashMap::new(),
+    };
+    let mut last_modified = None;
+    for entry in WalkDir::new(&path)
+        .max_depth(max_depth)
+        .into_iter()
+        .filter_entry(|e| {
+            let name = e.file_name().to_string_lossy();
+            e.depth() == 0 || !(name.starts_with('.') || scan.is_ignored(&name))
+        })
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && e.file_name() != NOTE_ORDER_FILE)
+    {
+        let Ok(metadata) = entry.metadata() else { continue };
+        let extension = entry.path().extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if is_text_extension(&extension) {
+            stats.notes += 1;
+        } else {
+            stats.attachments += 1;
+        }
+        stats.total_size += metadata.len();
+        last_modified = last_modified.max(metadata.modified().ok());
+        *stats.by_extension.entry(extension).or_insert(0) += 1;
+    }
+    stats.last_modified = last_modified.map(system_time_to_rfc3339);
+    Ok(stats)
+}
+
+const MAX_NOTEBOOK_NAME_LEN: usize = 255;
+
+// Device names Windows reserves regardless of extension (e.g. "con.txt")
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL",
+    "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9",
+    "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+#[tauri::command]
+fn validate_notebook_name(name: String) -> Result<(), AzimuthError> {
+    let trimmed = name.trim();
+    if trimmed.is_empty() {
+        return Err(AzimuthError::InvalidPath("Notebook name cannot be empty".to_string()));
+    }
+
+    if trimmed.contains(['/', '\\']) {
+        return Err(AzimuthError::InvalidPath(format!("Notebook name cannot contain path separators: {}", name)));
+    }
+
+    if trimmed == "." || trimmed == ".." {
+        return Err(AzimuthError::InvalidPath(format!("'{}' is not a valid notebook name", name)));
+    }
+
+    let device = trimmed.split('.').next().unwrap_or(trimmed).trim_end().to_uppercase();
+    if RESERVED_NAMES.contains(&device.as_str()) {
+        return Err(AzimuthError::InvalidPath(format!("'{}' is a reserved name on Windows", name)));
+    }
+
+    if trimmed.chars().count() > MAX_NOTEBOOK_NAME_LEN {
+        return Err(AzimuthError::InvalidPath(format!("Notebook name is too long (max {} characters)", MAX_NOTEBOOK_NAME_LEN)));
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+fn create_notebook(base_path: String, name: String) -> Result<Notebook, AzimuthError> {
+    validate_notebook_name(name.clone())?;
+    let path = PathBuf::from(&base_path).join(&name);
+    fs::create_dir_all(&path)?;
+    Ok(Notebook {
+        id: path.to_string_lossy().to_string(),
+        name: name.clone(),
+        path: path.to_string_lossy().to_string(),
+        children: Vec::new(),
+    })
+}
+
+// Sorted by `sort_by` ("name", "created", "modified" or "size"; name by default),
+// ascending unless `ascending` is false. Frontmatter `tags` are merged into the
+// vault's tags when `base_path` is given.
+#[tauri::command]
+fn list_notes(
+    notebook_path: String,
+    base_path: Option<String>,
+    sort_by: Option<String>,
+    ascending: Option<bool>,
+) -> Result<Vec<Note>, AzimuthError> {
+    let mut notes = Vec::new();
+    let mut frontmatter_tags: HashMap<String, Vec<String>> = HashMap::new();
+    for (file_path, metadata) in note_files(&notebook_path, sort_by, ascending)? {
+        let note = note_from_file(&notebook_path, &file_path, &metadata, true);
+        let tags = frontmatter_tags_of(&note);
+        if !tags.is_empty() {
+            frontmatter_tags.insert(format!("{}/{}", notebook_path, note.id), tags);
+        }
+        notes.push(note);
+    }
+
+    if let Some(base_path) = base_path {
+        merge_note_tags(&base_path, frontmatter_tags)?;
+    }
+    Ok(notes)
+}
+
+// Markdown or HTML that shows a non-text file inline when its note is opened
+fn media_embed(file_path: &Path, extension: &str) -> String {
+    let file_name = file_path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let asset_url = format!("asset://localhost/{}", file_path.to_string_lossy().replace(" ", "%20"));
+    if is_image_extension(extension) {
+        format!("![{}]({})", file_name, asset_url)
+    } else if is_video_extension(extension) {
+        format!("<video controls width=\"100%\" style=\"max-height: 80vh;\">\n  <source src=\"{}\" type=\"video/{}\">\n  Your browser does not support the video tag.\n</video>", asset_url, get_video_mime(extension))
+    } else if is_audio_extension(extension) {
+        format!("<audio controls style=\"width: 100%;\">\n  <source src=\"{}\" type=\"audio/{}\">\n  Your browser does not support the audio tag.\n</audio>", asset_url, get_audio_mime(extension))
+    } else if extension == "pdf" {
+        format!("<iframe src=\"{}\" style=\"width: 100%; height: 60vh; border: none;\"></iframe>", asset_url)
+    } else {
+        format!("[📎 {}]({})", file_name, asset_url)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NoteMetadata {
+    pub id: String,
+    pub title: String,
+    pub folder: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub size: u64,
+}
+
+// The notebook's notes without their content, for switching notebooks quickly.
+// Only Markdown files are opened, and only to look for a frontmatter title.
+#[tauri::command]
+fn list_note_metadata(notebook_path: String) -> Result<Vec<NoteMetadata>, AzimuthError> {
+    let files = note_files(&notebook_path, None, None)?;
+    Ok(files
+        .into_iter()
+        .map(|(file_path, metadata)| {
+            let id = file_path.file_name().unwrap_or_default().to_string_lossy().to_string();
+            let is_markdown = file_path.extension()
+                .map(|e| is_markdown_extension(&e.to_string_lossy().to_lowercase()))
+                .unwrap_or(false);
+            let title = is_markdown
+                .then(|| fs::read_to_string(&file_path).ok())
+                .flatten()
+                .and_then(|content| {
+                    let (fields, _) = split_frontmatter(&content)?;
+                    let title = fields.get("title")?.as_str()?.trim().to_string();
+                    (!title.is_empty()).then_some(title)
+                })
+                .unwrap_or_else(|| id.clone());
+            NoteMetadata {
+                id,
+                title,
+                folder: notebook_path.clone(),
+                created_at: system_time_to_rfc3339(metadata.created().unwrap_or(std::time::SystemTime::now())),
+                updated_at: system_time_to_rfc3339(metadata.modified().unwrap_or(std::time::SystemTime::now())),
+                size: metadata.len(),
+            }
+        })
+        .collect())
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NotePage {
+    pub notes: Vec<Note>,
+    // Number of notes in the notebook before `offset` and `limit` were applied
+    pub total: usize,
+}
+
+// One page of list_notes for notebooks too large to load whole. Only the page's
+// files are read; without `include_content` notes come back with empty content
+// (but their frontmatter title) and read_note fetches it when opened.
+#[tauri::command]
+fn list_notes_page(
+    notebook_path: String,
+    offset: usize,
+    limit: usize,
+    include_content: bool,
+    sort_by: Option<String>,
+    ascending: Option<bool>,
+) -> Result<NotePage, AzimuthError> {
+    let files = note_files(&notebook_path, sort_by, ascending)?;
+    let total = files.len();
+    let notes = files
+        .iter()
+        .skip(offset)
+        .take(limit)
+        .map(|(file_path, metadata)| note_from_file(&notebook_path, file_path, metadata, include_content))
+        .collect();
+    Ok(NotePage { notes, total })
+}
+
+// The notebook's files in list order, from their metadata alone. Timestamps and
+// sizes tie-break on the name.
+fn note_files(notebook_path: &str, sort_by: Option<String>, ascending: Option<bool>) -> Result<Vec<(PathBuf, fs::Metadata)>, AzimuthError> {
+    let sort_by = sort_by.unwrap_or_else(|| "name".to_string());
+    if !matches!(sort_by.as_str(), "name" | "created" | "modified" | "size") {
+        return Err(AzimuthError::InvalidInput(format!("Invalid sort field: {}", sort_by)));
+    }
+    let path = PathBuf::from(notebook_path);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut files = Vec::new();
+    for entry in fs::read_dir(&path)? {
+        let entry = entry?;
+        let file_path = entry.path();
+        if file_path.is_file() && entry.file_name() != NOTE_ORDER_FILE {
+            let metadata = fs::metadata(&file_path)?;
+            files.push((file_path, metadata));
+        }
+    }
+
+    let name = |path: &Path| path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    files.sort_by(|(a, a_meta), (b, b_meta)| {
+        let by_name = name(a).to_lowercase().cmp(&name(b).to_lowercase()).then_with(|| name(a).cmp(&name(b)));
+        let ordering = match sort_by.as_str() {
+            "created" => a_meta.created().ok().cmp(&b_meta.created().ok()),
+            "modified" => a_meta.modified().ok().cmp(&b_meta.modified().ok()),
+            "size" => a_meta.len().cmp(&b_meta.len()),
+            _ => std::cmp::Ordering::Equal,
+        }
+        .then(by_name);
+        if ascending.unwrap_or(true) { ordering } else { ordering.reverse() }
+    });
+    Ok(files)
+}
+
+fn note_from_file(notebook_path: &str, file_path: &Path, metadata: &fs::Metadata, include_content: bool) -> Note {
+    let file_name = file_path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let extension = file_path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    let mut title = file_name.clone();
+    let mut frontmatter = HashMap::new();
+    let content = if is_text_extension(&extension) {
+        let content = fs::read_to_string(file_path).unwrap_or_else(|_| {
+            let asset_url = format!("asset://localhost/{}", file_path.to_string_lossy().replace(" ", "%20"));
+            format!("[📎 {}]({})", file_name, asset_url)
+        });
+        match split_frontmatter(&content) {
+            Some((fields, body)) if is_markdown_extension(&extension) => {
+                if let Some(frontmatter_title) = fields.get("title").and_then(|t| t.as_str()).map(str::trim).filter(|t| !t.is_empty()) {
+                    title = frontmatter_title.to_string();
+                }
+                frontmatter = frontmatter_to_json(&fields);
+                body.to_string()
+            }
+            _ => content,
+        }
+    } else if include_content {
+        media_embed(file_path, &extension)
+    } else {
+        String::new()
+    };
+
+    Note {
+        id: file_name,
+        title,
+        content: if include_content { content } else { String::new() },
+        folder: notebook_path.to_string(),
+        created_at: system_time_to_rfc3339(metadata.created().unwrap_or(std::time::SystemTime::now())),
+        updated_at: system_time_to_rfc3339(metadata.modified().unwrap_or(std::time::SystemTime::now())),
+        frontmatter,
+    }
+}
+
+fn frontmatter_to_json(fields: &serde_yaml::Mapping) -> HashMap<String, serde_json::Value> {
+    fields
+        .iter()
+        .filter_map(|(key, value)| Some((key.as_str()?.to_string(), serde_json::to_value(value).ok()?)))
+        .collect()
+}
+
+// `tags` as a list or a comma- or space-separated string, with any leading `#` dropped
+fn frontmatter_tags_of(note: &Note) -> Vec<String> {
+    let raw: Vec<String> = match note.frontmatter.get("tags") {
+        Some(serde_json::Value::Array(items)) => items
+            .iter()
+            .filter_map(|item| match item {
+                serde_json::Value::String(tag) => Some(tag.clone()),
+                serde_json::Value::Number(n) => Some(n.to_string()),
+                _ => None,
+            })
+            .collect(),
+        Some(serde_json::Value::String(tags)) => tags
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .map(|tag| tag.to_string())
+            .collect(),
+        _ => Vec::new(),
+    };
+    let mut tags: Vec<String> = Vec::new();
+    for tag in raw {
+        let tag = tag.trim().trim_start_matches('#').to_string();
+        if !tag.is_empty() && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+// Adds tags to each note's existing ones; settings are only written when something is new
+fn merge_note_tags(base_path: &str, tags: HashMap<String, Vec<String>>) -> Result<(), AzimuthError> {
+    let settings = load_settings(base_path.to_string())?;
+    let has_new = tags.iter().any(|(note, note_tags)| {
+        let existing = settings.tags.get(note);
+        note_tags.iter().any(|tag| !existing.is_some_and(|existing| existing.contains(tag)))
+    });
+    if !has_new {
+        return Ok(());
+    }
+    update_settings(base_path, |settings| {
+        for (note, note_tags) in tags {
+            let existing = settings.tags.entry(note).or_default();
+            for tag in note_tags {
+                if !existing.contains(&tag) {
+                    existing.push(tag);
+                }
+            }
+        }
+    })
+}
+
+fn is_image_extension(ext: &str) -> bool {
+    matches!(ext, "png" | "jpg" | "jpeg" | "gif" | "webp" | "svg" | "bmp" | "ico" | "tiff" | "tif")
+}
+
+fn is_video_extension(ext: &str) -> bool {
+    matches!(ext, "mp4" | "webm" | "mov" | "avi" | "mkv" | "m4v" | "ogv" | "3gp" | "wmv")
+}
+
+fn is_audio_extension(ext: &str) -> bool {
+    matches!(ext, "mp3" | "wav" | "ogg" | "flac" | "aac" | "m4a" | "wma" | "opus")
+}
+
+fn get_video_mime(ext: &str) -> &str {
+    match ext {
+        "mp4" | "m4v" => "mp4",
+        "webm" => "webm",
+        "mov" => "quicktime",
+        "avi" => "x-msvideo",
+        "mkv" => "x-matroska",
+        "ogv" => "ogg",
+        "3gp" => "3gpp",
+        "wmv" => "x-ms-wmv",
+        _ => "mp4"
+    }
+}
+
+fn get_audio_mime(ext: &str) -> &str {
+    match ext {
+        "mp3" => "mpeg",
+        "wav" => "wav",
+        "ogg" | "opus" => "ogg",
+        "flac" => "flac",
+        "aac" | "m4a" => "aac",
+        "wma" => "x-ms-wma",
+        "webm" => "webm",
+        _ => "mpeg"
+    }
+}
+
+fn is_text_extension(ext: &str) -> bool {
+    matches!(ext, 
+        "md" | "markdown" | "mdown" | "mkd" |
+        "txt" | "text" | "log" |
+        "json" | "yaml" | "yml" | "toml" | "ini" | "cfg" | "conf" | "config" |
+        "rs" | "py" | "js" | "ts" | "jsx" | "tsx" | "java" | "c" | "cpp" | "h" | "hpp" |
+        "go" | "rb" | "php" | "swift" | "kt" | "scala" | "cs" | "fs" | "vb" |
+        "lua" | "pl" | "pm" | "r" | "m" | "mm" | "sql" | "sh" | "bash" | "zsh" |
+        "fish" | "ps1" | "psm1" | "bat" | "cmd" |
+        "html" | "htm" | "css" | "scss" | "sass" | "less" | "xml" | "xsl" | "xslt" |
+        "svg" | "vue" | "svelte" |
+        "csv" | "tsv" |
+        "pem" | "crt" | "cer" | "key" | "pub" |
+        "rst" | "adoc" | "asciidoc" | "org" | "tex" | "latex" |
+        "env" | "gitignore" | "dockerignore" | "editorconfig" | "prettierrc" |
+        "eslintrc" | "babelrc" | "nvmrc" | "npmrc" | "yarnrc" |
+        "makefile" | "cmake" | "gradle" | "properties" |
+        ""
+    )
+}
+
+fn is_markdown_extension(ext: &str) -> bool {
+    matches!(ext, "md" | "markdown" | "mdown" | "mkd")
+}
+
+// Frontmatter
+// Splits a leading `---` YAML block from the rest of the note. Returns None when
+// there is no block or it isn't a valid mapping, so malformed frontmatter is
+// treated as ordinary body text.
+fn split_frontmatter(content: &str) -> Option<(serde_yaml::Mapping, &str)> {
+    let rest = content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))?;
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        if trimmed == "---" || trimmed == "..." {
+            let yaml = &rest[..offset];
+            let body = &rest[offset + line.len()..];
+            let fields = if yaml.trim().is_empty() {
+                serde_yaml::Mapping::new()
+            } else {
+                serde_yaml::from_str(yaml).ok()?
+            };
+            return Some((fields, body));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+fn join_frontmatter(fields: &serde_yaml::Mapping, body: &str) -> Result<String, AzimuthError> {
+    if fields.is_empty() {
+        return Ok(body.to_string());
+    }
+    let yaml = serde_yaml::to_string(fields)?;
+    Ok(format!("---\n{}---\n{}", yaml, body))
+}
+
+// Display title of a note: the frontmatter `title` if set, otherwise the file stem
+fn note_title(note_id: &str, content: &str) -> String {
+    split_frontmatter(content)
+        .and_then(|(fields, _)| fields.get("title").and_then(|t| t.as_str()).map(|t| t.trim().to_string()))
+        .filter(|title| !title.is_empty())
+        .unwrap_or_else(|| {
+            Path::new(note_id)
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_else(|| note_id.to_string())
+        })
+}
+
+// Merges default frontmatter fields into every Markdown note in the notebook,
+// creating the block where missing. Returns the number of notes rewritten.
+#[tauri::command]
+fn apply_frontmatter_defaults(
+    notebook_path: String,
+    defaults: serde_json::Value,
+    overwrite_existing: bool,
+) -> Result<usize, AzimuthError> {
+    let defaults = defaults.as_object().ok_or("Frontmatter defaults must be an object")?;
+
+    let mut modified = 0;
+    for entry in fs::read_dir(&notebook_path)? {
+        let entry = entry?;
+        let path = entry.path();
+        let extension = path.extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if !path.is_file() || !is_markdown_extension(&extension) {
+            continue;
+        }
+
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+        let (mut fields, body) = match split_frontmatter(&content) {
+            Some(split) => split,
+            // Don't stack a second block on top of one we couldn't parse
+            None if content.starts_with("---") => continue,
+            None => (serde_yaml::Mapping::new(), content.as_str()),
+        };
+
+        let mut changed = false;
+        for (key, value) in defaults {
+            let key = serde_yaml::Value::String(key.clone());
+            if fields.contains_key(&key) && !overwrite_existing {
+                continue;
+            }
+            let value = serde_yaml::to_value(value)?;
+            if fields.get(&key) != Some(&value) {
+                fields.insert(key, value);
+                changed = true;
+            }
+        }
+
+        if changed {
+            fs::write(&path, join_frontmatter(&fields, body)?)?;
+            modified += 1;
+        }
+    }
+    Ok(modified)
+}
+
+// Markdown notes in manual order paired with their titles
+fn notebook_titles(notebook_path: &Path) -> Result<Vec<(String, String)>, AzimuthError> {
+    let mut titles = Vec::new();
+    for note_id in resolve_note_order(notebook_path)? {
+        let extension = Path::new(&note_id).extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if !is_markdown_extension(&extension) {
+            continue;
+        }
+        if let Ok(content) = fs::read_to_string(notebook_path.join(&note_id)) {
+            let title = note_title(&note_id, &content);
+            titles.push((note_id, title));
+        }
+    }
+    Ok(titles)
+}
+
+// Titles are compared case-insensitively, since "Todo" and "todo" are just as confusing
+fn group_duplicate_titles(titles: &[(String, String)]) -> Vec<DuplicateTitleGroup> {
+    let mut groups: Vec<DuplicateTitleGroup> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for (note_id, title) in titles {
+        let key = title.to_lowercase();
+        match index.get(&key) {
+            Some(&i) => groups[i].notes.push(note_id.clone()),
+            None => {
+                index.insert(key, groups.len());
+                groups.push(DuplicateTitleGroup { title: title.clone(), notes: vec![note_id.clone()] });
+            }
+        }
+    }
+    groups.retain(|group| group.notes.len() > 1);
+    groups
+}
+
+#[tauri::command]
+fn find_duplicate_titles(notebook_path: String) -> Result<Vec<DuplicateTitleGroup>, AzimuthError> {
+    Ok(group_duplicate_titles(&notebook_titles(Path::new(&notebook_path))?))
+}
+
+// Keeps the first note of each duplicate group as is and gives the others a
+// numbered frontmatter title ("Title 2", "Title 3", ...). Filenames are unchanged.
+#[tauri::command]
+fn dedupe_titles(notebook_path: String) -> Result<Vec<TitleChange>, AzimuthError> {
+    let notebook = PathBuf::from(&notebook_path);
+    let titles = notebook_titles(&notebook)?;
+    let mut taken: HashSet<String> = titles.iter().map(|(_, title)| title.to_lowercase()).collect();
+
+    let mut changes = Vec::new();
+    for group in group_duplicate_titles(&titles) {
+        let mut counter = 2;
+        for note_id in group.notes.iter().skip(1) {
+            let path = notebook.join(note_id);
+            let content = fs::read_to_string(&path)?;
+            let (mut fields, body) = match split_frontmatter(&content) {
+                Some(split) => split,
+                // Leave notes with unparseable frontmatter for the user to fix
+                None if content.starts_with("---") => continue,
+                None => (serde_yaml::Mapping::new(), content.as_str()),
+            };
+
+            let mut new_title = format!("{} {}", group.title, counter);
+            while taken.contains(&new_title.to_lowercase()) {
+                counter += 1;
+                new_title = format!("{} {}", group.title, counter);
+            }
+            counter += 1;
+            taken.insert(new_title.to_lowercase());
+
+            fields.insert("title".into(), new_title.clone().into());
+            fs::write(&path, join_frontmatter(&fields, body)?)?;
+            changes.push(TitleChange {
+                note: note_id.clone(),
+                old_title: note_title(note_id, &content),
+                new_title,
+            });
+        }
+    }
+    Ok(changes)
+}
+
+// Joins a file name from the UI (a note id or attachment name) onto `base`, refusing
+// anything that could reach outside it: separators, "." and "..", and absolute or
+// drive-prefixed paths
+fn safe_join(base: &Path, component: &str) -> Result<PathBuf, AzimuthError> {
+    let mut components = Path::new(component).components();
+    match (components.next(), components.next()) {
+        (Some(std::path::Component::Normal(name)), None) if !component.contains(['/', '\\']) => Ok(base.join(name)),
+        _ => Err(AzimuthError::InvalidPath(format!("Invalid file name: {}", component))),
+    }
+}
+
+// safe_join for a '/'-separated path below `base`, such as a synced file's path as a
+// remote reports it, checked one segment at a time
+fn safe_join_relative(base: &Path, relative: &str) -> Result<PathBuf, AzimuthError> {
+    relative.split('/').try_fold(base.to_path_buf(), |path, segment| safe_join(&path, segment))
+        .map_err(|_| AzimuthError::InvalidPath(format!("Invalid path: {}", relative)))
+}
+
+#[tauri::command]
+fn save_note(notebook_path: String, note_id: String, content: String) -> Result<(), AzimuthError> {
+    let path = safe_join(Path::new(&notebook_path), &note_id)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // list_notes hands out notes without their frontmatter, so keep the file's block
+    // unless the new content brings its own
+    let extension = path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let existing = fs::read_to_string(&path).ok();
+    let content = match &existing {
+        Some(existing) if is_markdown_extension(&extension) && split_frontmatter(&content).is_none() => match split_frontmatter(existing) {
+            Some((_, body)) => format!("{}{}", &existing[..existing.len() - body.len()], content),
+            None => content,
+        },
+        _ => content,
+    };
+    if let Some(existing) = &existing {
+        save_note_version(Path::new(&notebook_path), &note_id, existing, &content)?;
+    }
+    write_atomic(&path, &content)?;
+    invalidate_notebook_activity(Path::new(&notebook_path));
+    invalidate_notebook_size(Path::new(&notebook_path));
+    Ok(())
+}
+
+// Earlier versions of each note, kept in the notebook's
+// .azimuth_history/<note_id>/<timestamp>.bak
+const HISTORY_DIR: &str = ".azimuth_history";
+const MAX_NOTE_VERSIONS: usize = 50;
+const NOTE_VERSION_FORMAT: &str = "%Y-%m-%dT%H-%M-%S%.3fZ";
+// Auto-save writes every few seconds, so a burst of saves keeps only the version
+// from before it started
+const NOTE_VERSION_INTERVAL_SECS: i64 = 5 * 60;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NoteVersion {
+    pub timestamp: String,
+    pub created_at: String,
+    pub size: u64,
+}
+
+fn note_history_dir(notebook_path: &Path, note_id: &str) -> PathBuf {
+    notebook_path.join(HISTORY_DIR).join(note_id)
+}
+
+// Newest first
+fn note_versions(history_dir: &Path) -> Vec<(String, chrono::DateTime<chrono::Utc>)> {
+    let mut versions: Vec<(String, chrono::DateTime<chrono::Utc>)> = fs::read_dir(history_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let timestamp = entry.file_name().to_string_lossy().strip_suffix(".bak")?.to_string();
+            let created_at = chrono::NaiveDateTime::parse_from_str(&timestamp, NOTE_VERSION_FORMAT).ok()?.and_utc();
+            Some((timestamp, created_at))
+        })
+        .collect();
+    versions.sort_by(|a, b| b.0.cmp(&a.0));
+    versions
+}
+
+// Keeps `previous` as a version of the note when `next` changes it
+fn save_note_version(notebook_path: &Path, note_id: &str, previous: &str, next: &str) -> Result<(), AzimuthError> {
+    if Sha256::digest(previous) == Sha256::digest(next) {
+        return Ok(());
+    }
+    let history_dir = note_history_dir(notebook_path, note_id);
+    if let Some((timestamp, created_at)) = note_versions(&history_dir).first() {
+        let latest = fs::read(history_dir.join(format!("{}.bak", timestamp))).unwrap_or_default();
+        if (chrono::Utc::now() - *created_at).num_seconds() < NOTE_VERSION_INTERVAL_SECS
+            || Sha256::digest(&latest) == Sha256::digest(previous)
+        {
+            return Ok(());
+        }
+    }
+    write_note_version(&history_dir, previous)
+}
+
+// Prunes to the newest MAX_NOTE_VERSIONS afterwards
+fn write_note_version(history_dir: &Path, content: &str) -> Result<(), AzimuthError> {
+    fs::create_dir_all(history_dir)?;
+    // Two versions in the same millisecond mustn't overwrite each other
+    let mut time = chrono::Utc::now();
+    let mut path = history_dir.join(format!("{}.bak", time.format(NOTE_VERSION_FORMAT)));
+    while path.exists() {
+        time += chrono::Duration::milliseconds(1);
+        path = history_dir.join(format!("{}.bak", time.format(NOTE_VERSION_FORMAT)));
+    }
+    fs::write(&path, content)?;
+    for (timestamp, _) in note_versions(history_dir).iter().skip(MAX_NOTE_VERSIONS) {
+        fs::remove_file(history_dir.join(format!("{}.bak", timestamp)))?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn list_note_versions(notebook_path: String, note_id: String) -> Result<Vec<NoteVersion>, AzimuthError> {
+    safe_join(Path::new(&notebook_path), &note_id)?;
+    let history_dir = note_history_dir(Path::new(&notebook_path), &note_id);
+    Ok(note_versions(&history_dir)
+        .into_iter()
+        .map(|(timestamp, created_at)| NoteVersion {
+            size: fs::metadata(history_dir.join(format!("{}.bak", timestamp))).map(|m| m.len()).unwrap_or(0),
+            created_at: created_at.to_rfc3339(),
+            timestamp,
+        })
+        .collect())
+}
+
+// Puts a version back as the note's content. The current content is kept as a
+// version first, whatever its age, so the restore can be undone.
+#[tauri::command]
+fn restore_note_version(notebook_path: String, note_id: String, timestamp: String) -> Result<String, AzimuthError> {
+    if chrono::NaiveDateTime::parse_from_str(&timestamp, NOTE_VERSION_FORMAT).is_err() {
+        return Err(AzimuthError::InvalidPath(format!("Invalid version: {}", timestamp)));
+    }
+    let notebook = PathBuf::from(&notebook_path);
+    let path = safe_join(&notebook, &note_id)?;
+    let history_dir = note_history_dir(&notebook, &note_id);
+    let restored = fs::read_to_string(history_dir.join(format!("{}.bak", timestamp)))
+        .map_err(|_| AzimuthError::NotFound(format!("Version not found: {}", timestamp)))?;
+
+    if let Ok(current) = fs::read_to_string(&path)
+        && Sha256::digest(&current) != Sha256::digest(&restored)
+    {
+        write_note_version(&history_dir, &current)?;
+    }
+    write_atomic(&path, &restored)?;
+    invalidate_notebook_activity(&notebook);
+    invalidate_notebook_size(&notebook);
+    Ok(restored)
+}
+
+// Moves the note and its attachments folder to the trash in `base_path` (the
+// notebook itself when not given), or removes them outright when `permanent`
+#[tauri::command]
+fn delete_note(notebook_path: String, note_id: String, base_path: Option<String>, permanent: Option<bool>) -> Result<(), AzimuthError> {
+    let note_path = safe_join(Path::new(&notebook_path), &note_id)?;
+    let attachments_path = note_attachments_dir(&notebook_path, &note_id);
+
+    if permanent.unwrap_or(false) {
+        if note_path.exists() {
+            fs::remove_file(&note_path)?;
+        }
+        if attachments_path.exists() && attachments_path.is_dir() {
+            fs::remove_dir_all(&attachments_path)?;
+        }
+    } else {
+        let base = PathBuf::from(base_path.unwrap_or(notebook_path.clone()));
+        let entry_dir = base.join(TRASH_DIR).join(chrono::Utc::now().format(TRASH_ID_FORMAT).to_string());
+        if note_path.exists() {
+            move_to_trash(&base, &entry_dir, &note_path)?;
+        }
+        if attachments_path.exists() && attachments_path.is_dir() {
+            move_to_trash(&base, &entry_dir, &attachments_path)?;
+        }
+    }
+    invalidate_notebook_size(Path::new(&notebook_path));
+    Ok(())
+}
+
+// Deleted notes, kept in .azimuth_trash/<timestamp>/ under their path in the vault
+const TRASH_DIR: &str = ".azimuth_trash";
+const TRASH_ID_FORMAT: &str = "%Y-%m-%dT%H-%M-%S%.3fZ";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TrashEntry {
+    pub id: String,
+    pub deleted_at: String,
+    // The note's original path, relative to the vault
+    pub note_path: String,
+    pub file_count: usize,
+}
+
+fn move_to_trash(base: &Path, entry_dir: &Path, path: &Path) -> Result<(), AzimuthError> {
+    let relative = path.strip_prefix(base).unwrap_or(Path::new(path.file_name().unwrap_or_default()));
+    let dest = entry_dir.join(relative);
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    Ok(fs::rename(path, &dest)?)
+}
+
+fn parse_trash_id(id: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    chrono::NaiveDateTime::parse_from_str(id, TRASH_ID_FORMAT).ok().map(|t| t.and_utc())
+}
+
+// Newest first
+#[tauri::command]
+fn list_trash(base_path: String) -> Result<Vec<TrashEntry>, AzimuthError> {
+    let trash_dir = PathBuf::from(&base_path).join(TRASH_DIR);
+    if !trash_dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(&trash_dir)?.flatten() {
+        let id = entry.file_name().to_string_lossy().to_string();
+        let Some(deleted_at) = parse_trash_id(&id) else {
+            continue;
+        };
+        let files = snapshot_files(&entry.path());
+        // Attachments sit one level below the note they belong to
+        let Some(note_path) = files.iter().min_by_key(|f| Path::new(f).components().count()).cloned() else {
+            continue;
+        };
+        entries.push(TrashEntry {
+            deleted_at: deleted_at.to_rfc3339(),
+            note_path,
+            file_count: files.len(),
+            id,
+        });
+    }
+    entries.sort_by(|a, b| b.id.cmp(&a.id));
+    Ok(entries)
+}
+
+// Moves a trashed note and its attachments back to where they were. Nothing is
+// moved if any of them would overwrite a file that has since taken its place.
+#[tauri::command]
+fn restore_from_trash(base_path: String, trash_entry: String) -> Result<TrashEntry, AzimuthError> {
+    let base = PathBuf::from(&base_path);
+    if parse_trash_id(&trash_entry).is_none() {
+        return Err(AzimuthError::InvalidPath(format!("Invalid trash entry: {}", trash_entry)));
+    }
+    let entry = list_trash(base_path.clone())?
+        .into_iter()
+        .find(|entry| entry.id == trash_entry)
+        .ok_or(AzimuthError::NotFound(format!("Trash entry not found: {}", trash_entry)))?;
+    let entry_dir = base.join(TRASH_DIR).join(&trash_entry);
+
+    let files = snapshot_files(&entry_dir);
+    if let Some(existing) = files.iter().find(|relative| base.join(relative).exists()) {
+        return Err(AzimuthError::AlreadyExists(format!("A file with that name already exists: {}", existing)));
+    }
+    for relative in &files {
+        let dest = base.join(relative);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(entry_dir.join(relative), &dest)?;
+        if let Some(parent) = dest.parent() {
+            invalidate_notebook_size(parent);
+        }
+    }
+    fs::remove_dir_all(&entry_dir)?;
+    Ok(entry)
+}
+
+// Permanently removes trash entries older than `older_than_days` (the vault's
+// trash_retention_days setting when not given; 0 empties the trash). Returns how many went.
+#[tauri::command]
+fn empty_trash(base_path: String, older_than_days: Option<u32>) -> Result<usize, AzimuthError> {
+    let days = match older_than_days {
+        Some(days) => days,
+        None => load_settings(base_path.clone())?.trash_retention_days,
+    };
+    let cutoff = chrono::Utc::now() - chrono::Duration::days(days as i64);
+    let mut removed = 0;
+    for entry in list_trash(base_path.clone())? {
+        if parse_trash_id(&entry.id).is_some_and(|deleted_at| deleted_at <= cutoff) {
+            fs::remove_dir_all(PathBuf::from(&base_path).join(TRASH_DIR).join(&entry.id))?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+fn remap_map_keys<V>(map: &mut HashMap<String, V>, remap_key: impl Fn(&str) -> Option<String>) {
+    let moved: Vec<(String, String)> = map.keys().filter_map(|key| Some((key.clone(), remap_key(key)?))).collect();
+    for (old_key, new_key) in moved {
+        if let Some(value) = map.remove(&old_key) {
+            map.insert(new_key, value);
+        }
+    }
+}
+
+// Points settings entries at or under `old` (tags, favorites, pins, notebook styles,
+// reminders and archived notebooks) at `new` after a note or notebook moves there
+fn remap_settings_paths(old: &Path, new: &Path) -> Result<(), AzimuthError> {
+    let Some(vault) = old.ancestors().find(|dir| dir.join(".azimuth_settings.json").exists()) else {
+        return Ok(());
+    };
+    let remap = |key: &Path| -> Option<PathBuf> {
+        let rest = key.strip_prefix(old).ok()?;
+        Some(if rest.as_os_str().is_empty() { new.to_path_buf() } else { new.join(rest) })
+    };
+    let remap_key = |key: &str| remap(Path::new(key)).map(|path| path.to_string_lossy().to_string());
+
+    update_settings(&vault.to_string_lossy(), |settings| {
+        for list in [&mut settings.favorites, &mut settings.pinned_folders, &mut settings.pinned_notes] {
+            for key in list.iter_mut() {
+                if let Some(new_key) = remap_key(key) {
+                    *key = new_key;
+                }
+            }
+        }
+        remap_map_keys(&mut settings.tags, remap_key);
+        remap_map_keys(&mut settings.notebook_styles, remap_key);
+        remap_map_keys(&mut settings.note_reminders, remap_key);
+        for entry in settings.archived.iter_mut() {
+            if let Some(relative) = remap(&vault.join(&*entry)).and_then(|path| {
+                path.strip_prefix(vault).ok().map(|p| p.to_string_lossy().to_string())
+            }) {
+                *entry = relative;
+            }
+        }
+    })
+}
+
+#[tauri::command]
+fn rename_note(notebook_path: String, old_id: String, new_id: String) -> Result<(), AzimuthError> {
+    let old_path = safe_join(Path::new(&notebook_path), &old_id)?;
+    let new_path = safe_join(Path::new(&notebook_path), &new_id)?;
+    
+    if !old_path.exists() {
+        return Err(AzimuthError::NotFound(format!("File does not exist: {}", old_id)));
+    }
+    
+    if new_path.exists() {
+        return Err(AzimuthError::AlreadyExists(format!("A file with that name already exists: {}", new_id)));
+    }
+
+    // The per-note attachments folder (see delete_note) follows the note's stem
+    let stem = |id: &str| PathBuf::from(id).file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or(id.to_string());
+    let (old_stem, new_stem) = (stem(&old_id), stem(&new_id));
+    let old_attachments = PathBuf::from(&notebook_path).join(&old_stem);
+    let new_attachments = PathBuf::from(&notebook_path).join(&new_stem);
+    let move_attachments = old_stem != new_stem && old_attachments.is_dir();
+    if move_attachments && new_attachments.exists() {
+        return Err(AzimuthError::AlreadyExists(format!("A folder with that name already exists: {}", new_stem)));
+    }
+    
+    fs::rename(&old_path, &new_path)?;
+    let old_history = note_history_dir(Path::new(&notebook_path), &old_id);
+    let new_history = note_history_dir(Path::new(&notebook_path), &new_id);
+    if old_history.is_dir() && !new_history.exists() {
+        if let Some(parent) = new_history.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(&old_history, &new_history)?;
+    }
+    if move_attachments {
+        fs::rename(&old_attachments, &new_attachments)?;
+        rewrite_attachment_links(&new_path, &old_attachments, &new_attachments, &old_stem, &new_stem)?;
+    }
+    remap_settings_paths(&old_path, &new_path)
+}
+
+// Points a note's links into its attachments folder at the folder's new name, both
+// relative links ("stem/image.png") and asset URLs
+fn rewrite_attachment_links(note_path: &Path, old_dir: &Path, new_dir: &Path, old_stem: &str, new_stem: &str) -> Result<(), AzimuthError> {
+    let content = match fs::read_to_string(note_path) {
+        Ok(content) => content,
+        // Not a text note, so there's nothing to rewrite
+        Err(_) => return Ok(()),
+    };
+    let mut updated = content
+        .replace(&format!("{}/", asset_url(old_dir)), &format!("{}/", asset_url(new_dir)))
+        .replace(&format!("]({}/", old_stem), &format!("]({}/", new_stem));
+    let (old_encoded, new_encoded) = (old_stem.replace(' ', "%20"), new_stem.replace(' ', "%20"));
+    if old_encoded != old_stem {
+        updated = updated.replace(&format!("]({}/", old_encoded), &format!("]({}/", new_encoded));
+    }
+    if updated != content {
+        fs::write(note_path, updated)?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn move_note(source_folder: String, target_folder: String, note_id: String) -> Result<(), AzimuthError> {
+    let source_path = safe_join(Path::new(&source_folder), &note_id)?;
+    let target_path = safe_join(Path::new(&target_folder), &note_id)?;
+    
+    if !source_path.exists() {
+        return Err(AzimuthError::NotFound(format!("File does not exist: {}", note_id)));
+    }
+    
+    if target_path.exists() {
+        return Err(AzimuthError::AlreadyExists(format!("A file with that name already exists in the target folder: {}", note_id)));
+    }
+    
+    // Ensure target folder exists
+    fs::create_dir_all(&target_folder)?;
+    
+    fs::rename(&source_path, &target_path)?;
+    invalidate_notebook_size(Path::new(&source_folder));
+    invalidate_notebook_size(Path::new(&target_folder));
+    remap_settings_paths(&source_path, &target_path)
+}
+
+// Note templates, kept in .azimuth_templates/ in the vault root
+const TEMPLATES_DIR: &str = ".azimuth_templates";
+
+#[tauri::command]
+fn list_templates(base_path: String) -> Result<Vec<String>, AzimuthError> {
+    let templates_dir = PathBuf::from(&base_path).join(TEMPLATES_DIR);
+    if !templates_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut templates: Vec<String> = fs::read_dir(&templates_dir)?
+        .flatten()
+        .filter(|entry| entry.path().is_file())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| !name.starts_with('.'))
+        .collect();
+    templates.sort_by_key(|name| name.to_lowercase());
+    Ok(templates)
+}
+
+// Replaces `{{name}}` placeholders. Built-ins are `date`, `time`, `datetime` and `title`;
+// `variables` can override them. Unknown placeholders are left as written.
+fn fill_template(template: &str, title: &str, variables: &HashMap<String, String>) -> String {
+    let now = chrono::Local::now();
+    let mut values: HashMap<String, String> = HashMap::from([
+        ("date".to_string(), now.format("%Y-%m-%d").to_string()),
+        ("time".to_string(), now.format("%H:%M").to_string()),
+        ("datetime".to_string(), now.format("%Y-%m-%d %H:%M").to_string()),
+        ("title".to_string(), title.to_string()),
+    ]);
+    values.extend(variables.iter().map(|(k, v)| (k.clone(), v.clone())));
+
+    let placeholder = regex::Regex::new(r"\{\{\s*([\w.-]+)\s*\}\}").expect("valid placeholder pattern");
+    placeholder
+        .replace_all(template, |caps: &regex::Captures| {
+            values.get(&caps[1]).cloned().unwrap_or_else(|| caps[0].to_string())
+        })
+        .to_string()
+}
+
+// Creates `note_name` in the notebook from a template in the nearest enclosing vault's
+// .azimuth_templates/. `template_name` may leave off the extension, which the note takes.
+#[tauri::command]
+fn create_note_from_template(
+    notebook_path: String,
+    template_name: String,
+    note_name: String,
+    variables: Option<HashMap<String, String>>,
+) -> Result<Note, AzimuthError> {
+    let notebook = PathBuf::from(&notebook_path);
+    let templates_dir = notebook
+        .ancestors()
+        .map(|dir| dir.join(TEMPLATES_DIR))
+        .find(|dir| dir.is_dir())
+        .ok_or("No templates folder found")?;
+    if template_name.contains(['/', '\\']) || template_name.starts_with('.') {
+        return Err(AzimuthError::InvalidPath(format!("Invalid template: {}", template_name)));
+    }
+    let template_path = std::iter::once(templates_dir.join(&template_name))
+        .chain(["md", "markdown", "txt"].iter().map(|ext| templates_dir.join(format!("{}.{}", template_name, ext))))
+        .find(|path| path.is_file())
+        .ok_or(AzimuthError::NotFound(format!("Template not found: {}", template_name)))?;
+    let template = fs::read_to_string(&template_path)?;
+
+    let title = note_name.trim().replace(['<', '>', ':', '"', '/', '\\', '|', '?', '*'], "-");
+    if title.is_empty() {
+        return Err(AzimuthError::InvalidInput("Note name is empty".to_string()));
+    }
+    let extension = template_path.extension().map(|e| e.to_string_lossy().to_string()).unwrap_or("md".to_string());
+    let note_id = format!("{}.{}", title, extension);
+    let note_path = safe_join(&notebook, &note_id)?;
+    if note_path.exists() {
+        return Err(AzimuthError::AlreadyExists(format!("A file with that name already exists: {}", note_id)));
+    }
+
+    fs::create_dir_all(&notebook)?;
+    let content = fill_template(&template, &title, &variables.unwrap_or_default());
+    write_atomic(&note_path, &content)?;
+    invalidate_notebook_activity(&notebook);
+    invalidate_notebook_size(&notebook);
+
+    list_notes(notebook_path, None, None, None)?
+        .into_iter()
+        .find(|note| note.id == note_id)
+        .ok_or(AzimuthError::NotFound(format!("Could not read the new note: {}", note_id)))
+}
+
+// Manual note ordering
+const NOTE_ORDER_FILE: &str = ".azimuth_order.json";
+
+// Stored order filtered to notes that still exist, followed by any notes not yet
+// ordered (sorted by name) so new files show up at the end of the list
+fn resolve_note_order(notebook_path: &Path) -> Result<Vec<String>, AzimuthError> {
+    let mut existing = Vec::new();
+    for entry in fs::read_dir(notebook_path)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if entry.path().is_file() && !name.starts_with('.') {
+            existing.push(name);
+        }
+    }
+    existing.sort_by_key(|name| name.to_lowercase());
+
+    let order_path = notebook_path.join(NOTE_ORDER_FILE);
+    let stored: Vec<String> = fs::read_to_string(&order_path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+
+    let mut order: Vec<String> = Vec::new();
+    for id in stored {
+        if existing.contains(&id) && !order.contains(&id) {
+            order.push(id);
+        }
+    }
+    for id in existing {
+        if !order.contains(&id) {
+            order.push(id);
+        }
+    }
+    Ok(order)
+}
+
+fn save_note_order(notebook_path: &Path, order: &[String]) -> Result<(), AzimuthError> {
+    let json = serde_json::to_string_pretty(order)?;
+    fs::write(notebook_path.join(NOTE_ORDER_FILE), json)?;
+    Ok(())
+}
+
+#[tauri::command]
+fn get_note_order(notebook_path: String) -> Result<Vec<String>, AzimuthError> {
+    resolve_note_order(&PathBuf::from(&notebook_path))
+}
+
+#[tauri::command]
+fn move_note_in_order(notebook_path: String, note_id: String, direction: String) -> Result<Vec<String>, AzimuthError> {
+    let path = PathBuf::from(&notebook_path);
+    let mut order = resolve_note_order(&path)?;
+    let index = order
+        .iter()
+        .position(|id| id == &note_id)
+        .ok_or(AzimuthError::NotFound(format!("File does not exist: {}", note_id)))?;
+
+    match direction.as_str() {
+        "up" => {
+            if index > 0 {
+                order.swap(index, index - 1);
+            }
+        }
+        "down" => {
+            if index + 1 < order.len() {
+                order.swap(index, index + 1);
+            }
+        }
+        _ => return Err(AzimuthError::InvalidInput("Invalid direction".to_string())),
+    }
+
+    save_note_order(&path, &order)?;
+    Ok(order)
+}
+
+#[tauri::command]
+fn set_note_position(notebook_path: String, note_id: String, index: usize) -> Result<Vec<String>, AzimuthError> {
+    let path = PathBuf::from(&notebook_path);
+    let mut order = resolve_note_order(&path)?;
+    let current = order
+        .iter()
+        .position(|id| id == &note_id)
+        .ok_or(AzimuthError::NotFound(format!("File does not exist: {}", note_id)))?;
+
+    let id = order.remove(current);
+    order.insert(index.min(order.len()), id);
+
+    save_note_order(&path, &order)?;
+    Ok(order)
+}
+
+// Writing progress
+// A version holds a note as it was just before the first save after it was
+// taken, so the earliest version on or after a moment is the note at that moment.
+// Saves are only versioned every few minutes, which bounds how exact this is.
+fn note_words_at(note_path: &Path, at: chrono::DateTime<chrono::Utc>) -> Result<usize, AzimuthError> {
+    let created = fs::metadata(note_path).and_then(|m| m.created()).ok().map(chrono::DateTime::<chrono::Utc>::from);
+    if created.is_some_and(|created| created > at) {
+        return Ok(0);
+    }
+    let (Some(notebook_path), Some(note_id)) = (note_path.parent(), note_path.file_name()) else {
+        return Err(AzimuthError::InvalidPath(format!("Invalid note path: {}", note_path.display())));
+    };
+    let history_dir = note_history_dir(notebook_path, &note_id.to_string_lossy());
+    let earliest_after = note_versions(&history_dir)
+        .into_iter()
+        .rev()
+        .find(|(_, created_at)| *created_at >= at);
+    let content = match earliest_after {
+        Some((timestamp, _)) => fs::read_to_string(history_dir.join(format!("{}.bak", timestamp))),
+        None => fs::read_to_string(note_path),
+    }?;
+    Ok(compute_note_stats(&content).words)
+}
+
+// A full timestamp, or a date meaning local midnight at its start
+fn parse_baseline(baseline: &str) -> Result<chrono::DateTime<chrono::Utc>, AzimuthError> {
+    if let Ok(time) = chrono::DateTime::parse_from_rfc3339(baseline) {
+        return Ok(time.with_timezone(&chrono::Utc));
+    }
+    chrono::NaiveDate::parse_from_str(baseline, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0)?.and_local_timezone(chrono::Local).earliest())
+        .map(|time| time.with_timezone(&chrono::Utc))
+        .ok_or_else(|| AzimuthError::InvalidInput(format!("Invalid date: {}", baseline)))
+}
+
+#[tauri::command]
+fn get_word_count_delta(note_path: String, baseline_iso_date: String) -> Result<i64, AzimuthError> {
+    let baseline = parse_baseline(&baseline_iso_date)?;
+    let path = Path::new(&note_path);
+    let current = compute_note_stats(&fs::read_to_string(path)?).words;
+    Ok(current as i64 - note_words_at(path, baseline)? as i64)
+}
+
+// Words written across the vault during a local day; negative when more was
+// deleted than written
+#[tauri::command]
+fn get_daily_word_count(base_path: String, date: String) -> Result<i64, AzimuthError> {
+    let start = parse_baseline(&date)?;
+    let end = chrono::NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.succ_opt()?.and_hms_opt(0, 0, 0)?.and_local_timezone(chrono::Local).earliest())
+        .map(|time| time.with_timezone(&chrono::Utc))
+        .ok_or_else(|| AzimuthError::InvalidInput(format!("Invalid date: {}", date)))?;
+    let mut total = 0;
+    for path in vault_text_files(&base_path) {
+        total += note_words_at(&path, end)? as i64 - note_words_at(&path, start)? as i64;
+    }
+    Ok(total)
+}
+
+#[tauri::command]
+// Text notes come back as written; media and other files as an embed for them
+fn read_note(notebook_path: String, note_id: String) -> Result<String, AzimuthError> {
+    let path = safe_join(Path::new(&notebook_path), &note_id)?;
+    let extension = path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if !is_text_extension(&extension) {
+        if !path.is_file() {
+            return Err(AzimuthError::NotFound(format!("Note not found: {}", note_id)));
+        }
+        return Ok(media_embed(&path, &extension));
+    }
+    Ok(fs::read_to_string(&path)?)
+}
+
+fn clean_typography(content: &str, options: &TypographyOptions) -> String {
+    let mut cleaned = String::with_capacity(content.len());
+    for c in content.chars() {
+        match c {
+            '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' if options.straight_quotes => cleaned.push('\''),
+            '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' if options.straight_quotes => cleaned.push('"'),
+            '\u{2014}' if options.normalize_dashes => cleaned.push_str("--"),
+            '\u{2010}' | '\u{2011}' | '\u{2012}' | '\u{2013}' | '\u{2015}' | '\u{2212}' if options.normalize_dashes => cleaned.push('-'),
+            '\u{00A0}' | '\u{2007}' | '\u{202F}' if options.replace_nbsp => cleaned.push(' '),
+            _ => cleaned.push(c),
+        }
+    }
+    cleaned
+}
+
+// Returns whether the note was rewritten
+#[tauri::command]
+fn clean_note_typography(notebook_path: String, note_id: String, options: TypographyOptions) -> Result<bool, AzimuthError> {
+    let path = safe_join(Path::new(&notebook_path), &note_id)?;
+    let content = fs::read_to_string(&path)?;
+    let cleaned = clean_typography(&content, &options);
+    if cleaned == content {
+        return Ok(false);
+    }
+    fs::write(&path, cleaned)?;
+    Ok(true)
+}
+
+const HEX_PREVIEW_BYTES: usize = 512;
+
+// Classic hex dump: offset, 16 hex bytes, printable ASCII
+fn hex_preview(bytes: &[u8]) -> String {
+    bytes
+        .chunks(16)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+            let ascii: String = chunk
+                .iter()
+                .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+                .collect();
+            format!("{:08x}  {:<47}  {}", i * 16, hex.join(" "), ascii)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Like read_note, but reports files that aren't valid UTF-8 text (or contain NUL
+// bytes) as binary with a hex preview instead of returning mojibake
+#[tauri::command]
+fn read_note_safe(notebook_path: String, note_id: String) -> Result<NoteContent, AzimuthError> {
+    let path = safe_join(Path::new(&notebook_path), &note_id)?;
+    let bytes = fs::read(&path)?;
+
+    if !bytes.contains(&0)
+        && let Ok(content) = std::str::from_utf8(&bytes)
+    {
+        return Ok(NoteContent::Text { content: content.to_string() });
+    }
+
+    Ok(NoteContent::Binary {
+        size: bytes.len() as u64,
+        hex_preview: hex_preview(&bytes[..bytes.len().min(HEX_PREVIEW_BYTES)]),
+    })
+}
+
+#[tauri::command]
+fn read_file_binary(file_path: String) -> Result<Vec<u8>, AzimuthError> {
+    let path = PathBuf::from(&file_path);
+    Ok(fs::read(&path)?)
+}
+
+#[tauri::command]
+// Images are re-encoded when `max_width` or `quality` is given: scaled down to
+// `max_width` and, for JPEGs, saved at `quality` (1-100). The smaller of the
+// original and the re-encoded image is kept, and must fit the vault's
+// max_attachment_bytes.
+fn save_attachment(
+    notebook_path: String,
+    note_id: String,
+    file_name: String,
+    data: String,
+    max_width: Option<u32>,
+    quality: Option<u8>,
+) -> Result<SavedAttachment, AzimuthError> {
+    let notebook_dir = PathBuf::from(&notebook_path);
+    safe_join(&notebook_dir, &note_id)?;
+    let attachments_dir = note_attachments_dir(&notebook_path, &note_id);
+    let file_path = safe_join(&attachments_dir, &file_name)?;
+    let mut decoded = STANDARD.decode(&data).map_err(|e| AzimuthError::InvalidInput(e.to_string()))?;
+    let extension = file_path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if (max_width.is_some() || quality.is_some())
+        && let Some(compressed) = compress_image(&decoded, &extension, max_width, quality)
+        && compressed.len() < decoded.len()
+    {
+        decoded = compressed;
+    }
+    let settings = notebook_dir
+        .ancestors()
+        .find(|dir| dir.join(".azimuth_settings.json").exists())
+        .map(|dir| load_settings(dir.to_string_lossy().to_string()))
+        .unwrap_or_else(|| get_notes_dir().and_then(load_settings))
+        .unwrap_or_default();
+    if let Some(limit) = settings.max_attachment_bytes
+        && decoded.len() as u64 > limit
+    {
+        return Err(AzimuthError::InvalidInput(format!(
+            "{} is {:.1} MB, over the {:.1} MB attachment limit",
+            file_name,
+            decoded.len() as f64 / 1_000_000.0,
+            limit as f64 / 1_000_000.0
+        )));
+    }
+    fs::create_dir_all(&attachments_dir)?;
+    fs::write(&file_path, &decoded)?;
+    
+    let asset_url = format!("asset://localhost/{}", file_path.to_string_lossy().replace(" ", "%20"));
+    Ok(SavedAttachment { url: asset_url, size: decoded.len() as u64 })
+}
+
+// A note's attachments live in a folder named after its stem, next to it
+// ("Ideas.md" keeps them in "Ideas/")
+fn note_attachments_dir(notebook_path: &str, note_id: &str) -> PathBuf {
+    let stem = Path::new(note_id)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or(note_id.to_string());
+    PathBuf::from(notebook_path).join(stem)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SavedAttachment {
+    pub url: String,
+    pub size: u64,
+}
+
+const DEFAULT_JPEG_QUALITY: u8 = 85;
+
+// Re-encodes an image in its own format, scaled down to `max_width` if wider.
+// None for formats that aren't worth re-encoding (SVG, animated GIF) or that
+// don't decode.
+fn compress_image(bytes: &[u8], extension: &str, max_width: Option<u32>, quality: Option<u8>) -> Option<Vec<u8>> {
+    let format = match extension {
+        "jpg" | "jpeg" => image::ImageFormat::Jpeg,
+        "png" => image::ImageFormat::Png,
+        "webp" => image::ImageFormat::WebP,
+        "bmp" => image::ImageFormat::Bmp,
+        "tiff" | "tif" => image::ImageFormat::Tiff,
+        _ => return None,
+    };
+    let mut image = image::load_from_memory_with_format(bytes, format).ok()?;
+    if let Some(max_width) = max_width
+        && max_width > 0
+        && image.width() > max_width
+    {
+        image = image.resize(max_width, u32::MAX, image::imageops::FilterType::Lanczos3);
+    }
+
+    let mut out = std::io::Cursor::new(Vec::new());
+    if format == image::ImageFormat::Jpeg {
+        let quality = quality.unwrap_or(DEFAULT_JPEG_QUALITY).clamp(1, 100);
+        let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut out, quality);
+        image.to_rgb8().write_with_encoder(encoder).ok()?;
+    } else {
+        image.write_to(&mut out, format).ok()?;
+    }
+    Some(out.into_inner())
+}
+
+// Attachment layout repair
+const ASSET_URL_PATTERN: &str = r#"asset://localhost/([^)\s"'<>]+)"#;
+
+// A link as written in a note and the attachment file it resolves to
+type AttachmentReference = (String, PathBuf);
+
+fn asset_url(path: &Path) -> String {
+    format!("asset://localhost/{}", path.to_string_lossy().replace(" ", "%20"))
+}
+
+// Renders a note to sanitized HTML with tables, task lists and strikethrough. Relative
+// images and attachment links resolve against `notebook_path` to asset URLs, as in list_notes.
+#[tauri::command]
+fn render_markdown(content: String, notebook_path: Option<String>) -> String {
+    use pulldown_cmark::{Event, Options, Parser, Tag};
+
+    let options = Options::ENABLE_TABLES | Options::ENABLE_TASKLISTS | Options::ENABLE_STRIKETHROUGH;
+    let resolve = |url: &str, is_image: bool| -> Option<String> {
+        let dir = notebook_path.as_deref()?;
+        if url.contains("://") || url.starts_with('#') || url.starts_with("mailto:") {
+            return None;
+        }
+        let decoded = urlencoding::decode(url).map(|s| s.into_owned()).unwrap_or_else(|_| url.to_string());
+        let resolved = Path::new(dir).join(decoded);
+        let extension = resolved.extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        // Links to other notes stay as they are
+        if !is_image && (!resolved.is_file() || is_text_extension(&extension)) {
+            return None;
+        }
+        Some(asset_url(&resolved))
+    };
+
+    let events = Parser::new_ext(&content, options).map(|event| match event {
+        Event::Start(Tag::Image { link_type, dest_url, title, id }) => {
+            let dest_url = resolve(&dest_url, true).map(Into::into).unwrap_or(dest_url);
+            Event::Start(Tag::Image { link_type, dest_url, title, id })
+        }
+        Event::Start(Tag::Link { link_type, dest_url, title, id }) => {
+            let dest_url = resolve(&dest_url, false).map(Into::into).unwrap_or(dest_url);
+            Event::Start(Tag::Link { link_type, dest_url, title, id })
+        }
+        event => event,
+    });
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, events);
+
+    ammonia::Builder::default()
+        .add_url_schemes(["asset"])
+        // Task list checkboxes
+        .add_tags(["input"])
+        .add_tag_attributes("input", ["type", "checked", "disabled"])
+        .clean(&html)
+        .to_string()
+}
+
+const READING_WORDS_PER_MINUTE: usize = 200;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct NoteStats {
+    pub words: usize,
+    pub characters: usize,
+    pub lines: usize,
+    pub reading_minutes: usize,
+}
+
+// Words and characters count the prose only: front matter, code blocks, HTML and
+// link targets are left out. Lines count the file as written.
+fn compute_note_stats(content: &str) -> NoteStats {
+    use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS;
+    let mut text = String::new();
+    let mut skipping = 0;
+    for event in Parser::new_ext(content, options) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) | Event::Start(Tag::MetadataBlock(_)) => skipping += 1,
+            Event::End(TagEnd::CodeBlock) | Event::End(TagEnd::MetadataBlock(_)) => skipping -= 1,
+            _ if skipping > 0 => {}
+            Event::Text(t) | Event::Code(t) => text.push_str(&t),
+            // Inline markup splits words into several events, so only breaks and the
+            // ends of blocks separate them
+            Event::End(TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough | TagEnd::Link | TagEnd::Image) => {}
+            Event::SoftBreak | Event::HardBreak | Event::End(_) => text.push('\n'),
+            _ => {}
+        }
+    }
+
+    // Runs of whitespace count as a single space
+    let words: Vec<&str> = text.split_whitespace().collect();
+    NoteStats {
+        words: words.len(),
+        characters: words.join(" ").chars().count(),
+        lines: content.lines().count(),
+        reading_minutes: words.len().div_ceil(READING_WORDS_PER_MINUTE),
+    }
+}
+
+#[tauri::command]
+fn note_stats(notebook_path: String, note_id: String) -> Result<NoteStats, AzimuthError> {
+    let content = fs::read_to_string(safe_join(Path::new(&notebook_path), &note_id)?)?;
+    Ok(compute_note_stats(&content))
+}
+
+// Every asset URL and relative link in a note, with the path it points at
+fn attachment_links(content: &str, note_dir: &Path, asset_re: &regex::Regex, link_re: &regex::Regex) -> Vec<AttachmentReference> {
+    let asset_refs = asset_re.captures_iter(content).map(|c| (c[0].to_string(), c[1].to_string()));
+    let relative_refs = link_re
+        .captures_iter(content)
+        .map(|c| c[1].to_string())
+        .filter(|target| !target.contains("://") && !target.starts_with('#') && !target.starts_with("mailto:"))
+        .map(|target| (target.clone(), target));
+
+    asset_refs
+        .chain(relative_refs)
+        .map(|(link, target)| {
+            let decoded = urlencoding::decode(&target)
+                .map(|s| s.into_owned())
+                .unwrap_or_else(|_| target.clone());
+            let resolved = if link.starts_with("asset://") {
+                PathBuf::from(decoded)
+            } else {
+                note_dir.join(decoded)
+            };
+            (link, resolved)
+        })
+        .collect()
+}
+
+// Moves every attachment a note references into the requested layout and rewrites
+// the note's links to match. "adjacent" keeps attachments next to the note;
+// "per_note" keeps them in a folder named after the note's stem (where
+// save_attachment writes them and delete_note looks for them).
+#[tauri::command]
+fn repair_attachment_layout(base_path: String, target_layout: String) -> Result<AttachmentRepairReport, AzimuthError> {
+    if target_layout != "adjacent" && target_layout != "per_note" {
+        return Err(AzimuthError::InvalidInput("Invalid attachment layout".to_string()));
+    }
+
+    let asset_re = regex::Regex::new(ASSET_URL_PATTERN)?;
+    let link_re = regex::Regex::new(r#"\]\(([^)\s"'<>]+)\)"#)?;
+
+    let mut report = AttachmentRepairReport {
+        moved: Vec::new(),
+        notes_updated: 0,
+        missing: Vec::new(),
+        skipped: Vec::new(),
+    };
+
+    // First pass: collect each note's references so attachments shared between
+    // notes can be left where they are instead of being moved out from under one
+    let mut notes: Vec<(PathBuf, String, Vec<AttachmentReference>)> = Vec::new();
+    let mut reference_counts: HashMap<PathBuf, usize> = HashMap::new();
+    for entry in WalkDir::new(&base_path)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_file())
+    {
+        let note_path = entry.into_path();
+        let extension = note_path.extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if !is_markdown_extension(&extension) {
+            continue;
+        }
+        let content = match fs::read_to_string(&note_path) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+        let note_dir = note_path.parent().unwrap_or(Path::new(&base_path)).to_path_buf();
+
+        let mut references: Vec<AttachmentReference> = Vec::new();
+        for (link, resolved) in attachment_links(&content, &note_dir, &asset_re, &link_re) {
+            if !resolved.is_file() {
+                // Dangling relative links are usually links to other notes that
+                // don't exist yet, so only report missing asset embeds
+                if link.starts_with("asset://") {
+                    report.missing.push(format!("{}: {}", note_path.to_string_lossy(), link));
+                }
+                continue;
+            }
+
+            let extension = resolved.extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            if is_text_extension(&extension) && !is_image_extension(&extension) {
+                continue;
+            }
+
+            if !references.iter().any(|(l, _)| l == &link) {
+                references.push((link, resolved));
+            }
+        }
+
+        let unique: HashSet<&PathBuf> = references.iter().map(|(_, p)| p).collect();
+        for path in unique {
+            *reference_counts.entry(path.clone()).or_default() += 1;
+        }
+        notes.push((note_path, content, references));
+    }
+
+    // Second pass: move attachments referenced by a single note and rewrite links
+    for (note_path, content, references) in notes {
+        let note_dir = note_path.parent().unwrap_or(Path::new(&base_path)).to_path_buf();
+        let stem = note_path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        let target_dir = if target_layout == "per_note" { note_dir.join(&stem) } else { note_dir.clone() };
+
+        let mut updated = content.clone();
+        let mut moved: HashMap<PathBuf, PathBuf> = HashMap::new();
+        for (link, current) in references {
+            if reference_counts.get(&current).copied().unwrap_or(0) > 1 {
+                report.skipped.push(format!("{}: shared by multiple notes", current.to_string_lossy()));
+                continue;
+            }
+
+            let file_name = match current.file_name() {
+                Some(name) => name.to_os_string(),
+                None => continue,
+            };
+            let target = target_dir.join(&file_name);
+
+            if current != target && !moved.contains_key(&current) {
+                if target.exists() {
+                    report.skipped.push(format!("{}: {} already exists", current.to_string_lossy(), target.to_string_lossy()));
+                    continue;
+                }
+                fs::create_dir_all(&target_dir)?;
+                fs::rename(&current, &target)?;
+
+                // Drop the per-note folder once it has been emptied
+                let old_folder = note_dir.join(&stem);
+                if current.parent() == Some(old_folder.as_path()) && old_folder != target_dir {
+                    let _ = fs::remove_dir(&old_folder);
+                }
+
+                report.moved.push(AttachmentMove {
+                    note: note_path.to_string_lossy().to_string(),
+                    from: current.to_string_lossy().to_string(),
+                    to: target.to_string_lossy().to_string(),
+                });
+                moved.insert(current.clone(), target.clone());
+            }
+
+            let new_link = if link.starts_with("asset://") {
+                asset_url(&target)
+            } else if target_layout == "per_note" {
+                format!("{}/{}", stem, file_name.to_string_lossy()).replace(' ', "%20")
+            } else {
+                file_name.to_string_lossy().replace(' ', "%20")
+            };
+            if link.starts_with("asset://") {
+                updated = updated.replace(&link, &new_link);
+            } else {
+                updated = updated.replace(&format!("]({})", link), &format!("]({})", new_link));
+            }
+        }
+
+        if updated != content {
+            fs::write(&note_path, updated)?;
+            report.notes_updated += 1;
+        }
+    }
+
+    Ok(report)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OrphanedAttachment {
+    pub path: String,
+    // The note whose attachments folder it is in
+    pub note: String,
+    pub size: u64,
+}
+
+// Files in notes' attachments folders (the folder named after each note's stem)
+// that no note in the vault links to. Nothing is deleted; this is the report to
+// review before delete_orphaned_attachments.
+#[tauri::command]
+fn find_orphaned_attachments(base_path: String) -> Result<Vec<OrphanedAttachment>, AzimuthError> {
+    let asset_re = regex::Regex::new(ASSET_URL_PATTERN)?;
+    let link_re = regex::Regex::new(r#"\]\(([^)\s"'<>]+)\)"#)?;
+
+    let mut referenced: HashSet<PathBuf> = HashSet::new();
+    let mut folders: Vec<(PathBuf, PathBuf)> = Vec::new();
+    for entry in WalkDir::new(&base_path)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_file())
+    {
+        let note_path = entry.into_path();
+        let extension = note_path.extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if !is_markdown_extension(&extension) {
+            continue;
+        }
+        let Ok(content) = fs::read_to_string(&note_path) else { continue };
+        let note_dir = note_path.parent().unwrap_or(Path::new(&base_path)).to_path_buf();
+        for (_, resolved) in attachment_links(&content, &note_dir, &asset_re, &link_re) {
+            referenced.insert(fs::canonicalize(&resolved).unwrap_or(resolved));
+        }
+        let attachments_dir = note_path.with_extension("");
+        if attachments_dir.is_dir() {
+            folders.push((attachments_dir, note_path));
+        }
+    }
+
+    let mut orphans = Vec::new();
+    for (attachments_dir, note_path) in folders {
+        for entry in WalkDir::new(&attachments_dir)
+            .into_iter()
+            .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+        {
+            // Notes kept in a notebook that shares the note's name aren't attachments
+            let extension = entry.path().extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            if is_text_extension(&extension) && !is_image_extension(&extension) {
+                continue;
+            }
+            let canonical = fs::canonicalize(entry.path()).unwrap_or(entry.path().to_path_buf());
+            if referenced.contains(&canonical) {
+                continue;
+            }
+            orphans.push(OrphanedAttachment {
+                path: entry.path().to_string_lossy().to_string(),
+                note: note_path.to_string_lossy().to_string(),
+                size: entry.metadata().map(|m| m.len()).unwrap_or(0),
+            });
+        }
+    }
+    orphans.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(orphans)
+}
+
+// Removes the given attachments, moving them to the trash unless `permanent`. Only
+// paths find_orphaned_attachments still reports are touched, so a file a note has
+// started linking to since the report is kept. Returns how many were removed.
+#[tauri::command]
+fn delete_orphaned_attachments(base_path: String, paths: Vec<String>, permanent: Option<bool>) -> Result<usize, AzimuthError> {
+    let orphans: HashSet<String> = find_orphaned_attachments(base_path.clone())?
+        .into_iter()
+        .map(|orphan| orphan.path)
+        .collect();
+    let base = PathBuf::from(&base_path);
+    let entry_dir = base.join(TRASH_DIR).join(chrono::Utc::now().format(TRASH_ID_FORMAT).to_string());
+
+    let mut removed = 0;
+    for path in paths.iter().filter(|path| orphans.contains(*path)) {
+        let path = Path::new(path);
+        if permanent.unwrap_or(false) {
+            fs::remove_file(path)?;
+        } else {
+            move_to_trash(&base, &entry_dir, path)?;
+        }
+        if let Some(parent) = path.parent() {
+            invalidate_notebook_size(parent);
+        }
+        removed += 1;
+    }
+    Ok(removed)
+}
+
+#[tauri::command]
+fn get_attachment_path(notebook_path: String, note_id: String, file_name: String) -> Result<String, AzimuthError> {
+    safe_join(Path::new(&notebook_path), &note_id)?;
+    let path = safe_join(&note_attachments_dir(&notebook_path, &note_id), &file_name)?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+fn list_attachments(notebook_path: String, note_id: String) -> Result<Vec<String>, AzimuthError> {
+    safe_join(Path::new(&notebook_path), &note_id)?;
+    let attachments_dir = note_attachments_dir(&notebook_path, &note_id);
+    if !attachments_dir.exists() {
+        return Ok(Vec::new());
+    }
+    
+    let mut files = Vec::new();
+    for entry in fs::read_dir(&attachments_dir)? {
+        let entry = entry?;
+        if entry.path().is_file() {
+            files.push(entry.file_name().to_string_lossy().to_string());
+        }
+    }
+    Ok(files)
+}
+
+#[tauri::command]
+fn import_folder(base_path: String, folder_path: String) -> Result<Notebook, AzimuthError> {
+    let source = PathBuf::from(&folder_path);
+    if !source.exists() || !source.is_dir() {
+        return Err(AzimuthError::InvalidPath("Invalid folder path".to_string()));
+    }
+    
+    let folder_name = source
+        .file_name()
+        .ok_or("Could not get folder name")?
+        .to_string_lossy()
+        .to_string();
+    
+    let dest = PathBuf::from(&base_path).join(&folder_name);
+    
+    if dest.exists() {
+        let children = list_notebooks_simple(&dest, &scan_settings(&base_path))?;
+        return Ok(Notebook {
+            id: dest.to_string_lossy().to_string(),
+            name: folder_name,
+            path: dest.to_string_lossy().to_string(),
+            children,
+        });
+    }
+    
+    fs::create_dir_all(&dest)?;
+    import_folder_contents(&source, &dest)?;
+    
+    let children = list_notebooks_simple(&dest, &scan_settings(&base_path))?;
+    Ok(Notebook {
+        id: dest.to_string_lossy().to_string(),
+        name: folder_name,
+        path: dest.to_string_lossy().to_string(),
+        children,
+    })
+}
+
+fn import_folder_contents(src: &PathBuf, dst: &PathBuf) -> std::io::Result<()> {
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let src_path = entry.path();
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        
+        if src_path.is_dir() {
+            let dst_subdir = dst.join(&file_name);
+            copy_dir_recursive(&src_path, &dst_subdir)?;
+        } else if src_path.is_file() {
+            let dst_path = dst.join(&file_name);
+            fs::copy(&src_path, &dst_path)?;
+        }
+    }
+    Ok(())
+}
+
+// Extracts a zip into a new notebook named after the archive, or after its single
+// top-level folder when everything is inside one. Like import_folder, an existing
+// notebook of that name is returned as is.
+#[tauri::command]
+fn import_zip(base_path: String, zip_path: String) -> Result<Notebook, AzimuthError> {
+    let file = fs::File::open(&zip_path)?;
+    let mut archive = zip::ZipArchive::new(file)?;
+
+    // Validate every entry before writing anything to guard against zip-slip.
+    // macOS resource forks aren't notes.
+    let mut entries = Vec::new();
+    for i in 0..archive.len() {
+        let entry = archive.by_index(i)?;
+        let name = entry.enclosed_name().ok_or(AzimuthError::InvalidPath("Archive contains an unsafe path".to_string()))?;
+        if name.starts_with("__MACOSX") {
+            continue;
+        }
+        entries.push((i, name, entry.is_dir()));
+    }
+
+    let first_components: HashSet<_> = entries.iter().filter_map(|(_, name, _)| name.components().next()).collect();
+    let single_root = match first_components.iter().next() {
+        Some(first) if first_components.len() == 1 => {
+            let first = PathBuf::from(first.as_os_str());
+            entries.iter().all(|(_, name, is_dir)| *is_dir || name != &first).then_some(first)
+        }
+        _ => None,
+    };
+    let folder_name = match &single_root {
+        Some(root) => root.to_string_lossy().to_string(),
+        None => Path::new(&zip_path)
+            .file_stem()
+            .ok_or("Could not get archive name")?
+            .to_string_lossy()
+            .to_string(),
+    };
+    validate_notebook_name(folder_name.clone())?;
+
+    let dest = PathBuf::from(&base_path).join(&folder_name);
+    if !dest.exists() {
+        fs::create_dir_all(&dest)?;
+        for (i, name, is_dir) in &entries {
+            let relative = match &single_root {
+                Some(root) => name.strip_prefix(root).unwrap_or(name),
+                None => name.as_path(),
+            };
+            if relative.as_os_str().is_empty() {
+                continue;
+            }
+            let target = dest.join(relative);
+            if *is_dir {
+                fs::create_dir_all(&target)?;
+                continue;
+            }
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let mut entry = archive.by_index(*i)?;
+            let mut out = fs::File::create(&target)?;
+            std::io::copy(&mut entry, &mut out)?;
+        }
+        invalidate_notebook_size(Path::new(&base_path));
+    }
+
+    let children = list_notebooks_simple(&dest, &scan_settings(&base_path))?;
+    Ok(Notebook {
+        id: dest.to_string_lossy().to_string(),
+        name: folder_name,
+        path: dest.to_string_lossy().to_string(),
+        children,
+    })
+}
+
+// Evernote export (ENEX): notes with ENML bodies and base64 resources
+#[derive(Debug, Deserialize)]
+struct EnexExport {
+    #[serde(rename = "note", default)]
+    notes: Vec<EnexNote>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EnexNote {
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    content: String,
+    created: Option<String>,
+    updated: Option<String>,
+    #[serde(rename = "tag", default)]
+    tags: Vec<String>,
+    #[serde(rename = "resource", default)]
+    resources: Vec<EnexResource>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EnexResource {
+    #[serde(default)]
+    data: String,
+    mime: Option<String>,
+    #[serde(rename = "resource-attributes")]
+    attributes: Option<EnexResourceAttributes>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EnexResourceAttributes {
+    #[serde(rename = "file-name")]
+    file_name: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EnexImportResult {
+    pub notebook: Notebook,
+    pub notes_imported: usize,
+}
+
+// ENEX timestamps look like 20240131T094500Z
+fn parse_enex_time(time: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    chrono::NaiveDateTime::parse_from_str(time.trim(), "%Y%m%dT%H%M%SZ").ok().map(|t| t.and_utc())
+}
+
+// `name`, or `name (2)`, `name (3)`... when something is already at that path
+fn unused_path(dir: &Path, stem: &str, extension: &str) -> PathBuf {
+    let file_name = |n: usize| match (n, extension.is_empty()) {
+        (1, true) => stem.to_string(),
+        (1, false) => format!("{}.{}", stem, extension),
+        (_, true) => format!("{} ({})", stem, n),
+        (_, false) => format!("{} ({}).{}", stem, n, extension),
+    };
+    (1..).map(|n| dir.join(file_name(n))).find(|path| !path.exists()).unwrap_or_default()
+}
+
+// Converts an ENML body to Markdown. `media` maps a resource's MD5 hash to the
+// Markdown that shows it, for the <en-media> elements that reference it.
+fn enml_to_markdown(enml: &str, media: &HashMap<String, String>) -> String {
+    use quick_xml::events::Event;
+
+    fn attribute(e: &quick_xml::events::BytesStart, name: &[u8]) -> Option<String> {
+        e.try_get_attribute(name).ok().flatten().and_then(|a| a.unescape_value().ok()).map(|v| v.to_string())
+    }
+    fn end_line(out: &mut String) {
+        if !out.is_empty() && !out.ends_with('\n') {
+            out.push('\n');
+        }
+    }
+    fn end_block(out: &mut String) {
+        end_line(out);
+        if !out.is_empty() && !out.ends_with("\n\n") {
+            out.push('\n');
+        }
+    }
+
+    let mut reader = quick_xml::Reader::from_str(enml);
+    let mut out = String::new();
+    // One entry per open list: None for bulleted, the next number for numbered
+    let mut lists: Vec<Option<usize>> = Vec::new();
+    let mut links: Vec<String> = Vec::new();
+    let mut in_pre = false;
+
+    let open_tag = |out: &mut String, e: &quick_xml::events::BytesStart, empty: bool, lists: &mut Vec<Option<usize>>, links: &mut Vec<String>, in_pre: &mut bool| {
+        let name = e.local_name();
+        match name.as_ref() {
+            b"div" | b"p" | b"blockquote" | b"table" => end_line(out),
+            b"br" => out.push('\n'),
+            b"hr" => {
+                end_block(out);
+                out.push_str("---\n\n");
+            }
+            tag @ (b"h1" | b"h2" | b"h3" | b"h4" | b"h5" | b"h6") => {
+                end_block(out);
+                out.push_str(&"#".repeat((tag[1] - b'0') as usize));
+                out.push(' ');
+            }
+            b"b" | b"strong" => out.push_str("**"),
+            b"i" | b"em" => out.push('*'),
+            b"s" | b"strike" | b"del" => out.push_str("~~"),
+            b"code" if !*in_pre => out.push('`'),
+            b"pre" => {
+                end_block(out);
+                out.push_str("```\n");
+                *in_pre = true;
+            }
+            b"ul" => {
+                end_line(out);
+                lists.push(None);
+            }
+            b"ol" => {
+                end_line(out);
+                lists.push(Some(1));
+            }
+            b"li" => {
+                end_line(out);
+                out.push_str(&"  ".repeat(lists.len().saturating_sub(1)));
+                match lists.last_mut() {
+                    Some(Some(n)) => {
+                        out.push_str(&format!("{}. ", n));
+                        *n += 1;
+                    }
+                    _ => out.push_str("- "),
+                }
+            }
+            b"td" | b"th" => out.push_str("| "),
+            b"a" if !empty => {
+                out.push('[');
+                links.push(attribute(e, b"href").unwrap_or_default());
+            }
+            b"en-todo" => {
+                let checked = attribute(e, b"checked").is_some_and(|v| v == "true");
+                if lists.is_empty() {
+                    out.push_str("- ");
+                }
+                out.push_str(if checked { "[x] " } else { "[ ] " });
+            }
+            b"en-media" => {
+                if let Some(markdown) = attribute(e, b"hash").and_then(|hash| media.get(&hash.to_lowercase())) {
+                    out.push_str(markdown);
+                }
+            }
+            _ => {}
+        }
+    };
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => open_tag(&mut out, &e, false, &mut lists, &mut links, &mut in_pre),
+            Ok(Event::Empty(e)) => open_tag(&mut out, &e, true, &mut lists, &mut links, &mut in_pre),
+            Ok(Event::End(e)) => match e.local_name().as_ref() {
+                b"div" | b"blockquote" | b"tr" | b"li" => end_line(&mut out),
+                b"p" | b"table" | b"h1" | b"h2" | b"h3" | b"h4" | b"h5" | b"h6" => end_block(&mut out),
+                b"b" | b"strong" => out.push_str("**"),
+                b"i" | b"em" => out.push('*'),
+                b"s" | b"strike" | b"del" => out.push_str("~~"),
+                b"code" if !in_pre => out.push('`'),
+                b"pre" => {
+                    end_line(&mut out);
+                    out.push_str("```\n\n");
+                    in_pre = false;
+                }
+                b"ul" | b"ol" => {
+                    lists.pop();
+                    if lists.is_empty() {
+                        end_block(&mut out);
+                    }
+                }
+                b"td" | b"th" => out.push(' '),
+                b"a" => {
+                    let href = links.pop().unwrap_or_default();
+                    out.push_str(&format!("]({})", href));
+                }
+                _ => {}
+            },
+            Ok(Event::Text(e)) => {
+                let text = e.decode().unwrap_or_default();
+                if in_pre {
+                    out.push_str(&text);
+                } else {
+                    // Markup whitespace collapses, as it would in the browser
+                    let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                    if text.starts_with(char::is_whitespace) && !out.ends_with([' ', '\n']) && !out.is_empty() {
+                        out.push(' ');
+                    }
+                    out.push_str(&collapsed);
+                    if text.ends_with(char::is_whitespace) && !collapsed.is_empty() {
+                        out.push(' ');
+                    }
+                }
+            }
+            Ok(Event::CData(e)) => out.push_str(&String::from_utf8_lossy(&e)),
+            Ok(Event::GeneralRef(e)) => {
+                if let Ok(Some(c)) = e.resolve_char_ref() {
+                    out.push(c);
+                } else if let Some(resolved) = e.decode().ok().and_then(|name| quick_xml::escape::resolve_html5_entity(&name)) {
+                    // A non-breaking space is just a space in Markdown
+                    out.push_str(&resolved.replace('\u{a0}', " "));
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+
+    let mut markdown = String::new();
+    for line in out.lines() {
+        let line = line.trim_end();
+        if line.is_empty() && (markdown.is_empty() || markdown.ends_with("\n\n")) {
+            continue;
+        }
+        markdown.push_str(line);
+        markdown.push('\n');
+    }
+    markdown.trim_end().to_string() + "\n"
+}
+
+// Imports an Evernote export into `notebook_name` under the vault (created if
+// needed), one Markdown note per ENEX note. Resources go in the note's attachments
+// folder, timestamps and tags go in its frontmatter, and tags are also added to
+// the vault's tags.
+#[tauri::command]
+fn import_enex(base_path: String, enex_path: String, notebook_name: String) -> Result<EnexImportResult, AzimuthError> {
+    use md5::{Digest, Md5};
+
+    validate_notebook_name(notebook_name.clone())?;
+    let xml = fs::read_to_string(&enex_path)?;
+    let export: EnexExport = quick_xml::de::from_str(&xml).map_err(|e| AzimuthError::InvalidInput(format!("Not a valid ENEX file: {}", e)))?;
+
+    let notebook = PathBuf::from(&base_path).join(notebook_name.trim());
+    fs::create_dir_all(&notebook)?;
+
+    let mut note_tags = HashMap::new();
+    let mut notes_imported = 0;
+    for note in &export.notes {
+        let title = note.title.trim();
+        let title = if title.is_empty() { "Untitled" } else { title };
+        let stem = title.replace(['<', '>', ':', '"', '/', '\\', '|', '?', '*'], "-");
+        let note_path = unused_path(&notebook, &stem, "md");
+        let note_stem = note_path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+        let attachments_dir = notebook.join(&note_stem);
+
+        let mut media = HashMap::new();
+        for (i, resource) in note.resources.iter().enumerate() {
+            let encoded: String = resource.data.chars().filter(|c| !c.is_whitespace()).collect();
+            let Ok(data) = STANDARD.decode(encoded) else { continue };
+            let mime = resource.mime.clone().unwrap_or_default();
+            let file_name = resource.attributes.as_ref()
+                .and_then(|a| a.file_name.as_deref())
+                .map(|name| name.replace(['<', '>', ':', '"', '/', '\\', '|', '?', '*'], "-"))
+                .filter(|name| !name.trim().is_empty() && !name.starts_with('.'))
+                .unwrap_or_else(|| {
+                    let extension = mime.rsplit('/').next().filter(|_| mime.contains('/')).unwrap_or("bin");
+                    format!("attachment-{}.{}", i + 1, extension)
+                });
+            fs::create_dir_all(&attachments_dir)?;
+            let file_path = Path::new(&file_name);
+            let path = unused_path(
+                &attachments_dir,
+                &file_path.file_stem().unwrap_or_default().to_string_lossy(),
+                &file_path.extension().unwrap_or_default().to_string_lossy(),
+            );
+            fs::write(&path, &data)?;
+
+            let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+            let markdown = if mime.starts_with("image/") {
+                format!("![{}]({})", name, asset_url(&path))
+            } else {
+                format!("[📎 {}]({})", name, asset_url(&path))
+            };
+            media.insert(hex::encode(Md5::digest(&data)), markdown);
+        }
+
+        let created = note.created.as_deref().and_then(parse_enex_time);
+        let updated = note.updated.as_deref().and_then(parse_enex_time).or(created);
+        let tags: Vec<String> = note.tags.iter().map(|t| t.trim().to_string()).filter(|t| !t.is_empty()).collect();
+        let mut fields = serde_yaml::Mapping::new();
+        fields.insert("title".into(), title.into());
+        if let Some(created) = created {
+            fields.insert("created".into(), created.to_rfc3339().into());
+        }
+        if let Some(updated) = updated {
+            fields.insert("updated".into(), updated.to_rfc3339().into());
+        }
+        if !tags.is_empty() {
+            fields.insert("tags".into(), tags.clone().into());
+        }
+
+        let body = enml_to_markdown(&note.content, &media);
+        write_atomic(&note_path, &join_frontmatter(&fields, &body)?)?;
+        if let Some(updated) = updated
+            && let Ok(file) = fs::File::options().write(true).open(&note_path)
+        {
+            let _ = file.set_modified(updated.into());
+        }
+        if !tags.is_empty() {
+            note_tags.insert(note_path.to_string_lossy().to_string(), tags);
+        }
+        notes_imported += 1;
+    }
+
+    merge_note_tags(&base_path, note_tags)?;
+    invalidate_notebook_activity(&notebook);
+    invalidate_notebook_size(&notebook);
+
+    let children = list_notebooks_simple(&notebook, &scan_settings(&base_path))?;
+    Ok(EnexImportResult {
+        notebook: Notebook {
+            id: notebook.to_string_lossy().to_string(),
+            name: notebook_name.trim().to_string(),
+            path: notebook.to_string_lossy().to_string(),
+            children,
+        },
+        notes_imported,
+    })
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UnresolvedLink {
+    // The note, relative to the new notebook
+    pub note: String,
+    pub target: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ObsidianImportResult {
+    pub notebook: Notebook,
+    pub notes_imported: usize,
+    pub attachments_copied: usize,
+    pub unresolved_links: Vec<UnresolvedLink>,
+}
+
+// A note being imported from an Obsidian vault and the vault it came from
+struct ObsidianNote<'a> {
+    vault: &'a Path,
+    relative: PathBuf,
+    // Where the note and its attachments folder end up
+    dest: PathBuf,
+    attachments_dir: PathBuf,
+}
+
+// Indexes of an Obsidian vault's files, for resolving links the way Obsidian does:
+// by path from the vault root, by path from the note, or by bare file name
+struct ObsidianVault {
+    notes: HashSet<String>,
+    note_paths: HashSet<PathBuf>,
+    attachments: HashMap<String, Vec<PathBuf>>,
+    attachment_paths: HashSet<PathBuf>,
+}
+
+impl ObsidianVault {
+    fn resolve_attachment(&self, note: &ObsidianNote, target: &str) -> Option<PathBuf> {
+        let target = Path::new(target);
+        let from_note = note.relative.parent().unwrap_or(Path::new("")).join(target);
+        [target.to_path_buf(), from_note]
+            .into_iter()
+            .find(|path| self.attachment_paths.contains(path))
+            .or_else(|| {
+                let name = target.file_name()?.to_string_lossy().to_lowercase();
+                self.attachments.get(&name)?.iter().min_by_key(|path| path.components().count()).cloned()
+            })
+    }
+
+    fn has_note(&self, target: &str) -> bool {
+        let path = Path::new(target);
+        self.notes.contains(&wikilink_key(target))
+            || self.note_paths.contains(path)
+            || self.note_paths.contains(&path.with_extension("md"))
+    }
+}
+
+// Copies a vault attachment into the note's attachments folder, once per note, and
+// returns the Markdown that shows it
+fn copy_obsidian_attachment(
+    note: &ObsidianNote,
+    source: &Path,
+    label: Option<&str>,
+    copied: &mut HashMap<(PathBuf, PathBuf), PathBuf>,
+) -> Result<String, AzimuthError> {
+    let key = (note.dest.clone(), source.to_path_buf());
+    let dest = match copied.get(&key) {
+        Some(dest) => dest.clone(),
+        None => {
+            fs::create_dir_all(&note.attachments_dir)?;
+            let dest = unused_path(
+                &note.attachments_dir,
+                &source.file_stem().unwrap_or_default().to_string_lossy(),
+                &source.extension().unwrap_or_default().to_string_lossy(),
+            );
+            fs::copy(note.vault.join(source), &dest)?;
+            copied.insert(key, dest.clone());
+            dest
+        }
+    };
+    let name = dest.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let extension = dest.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+    Ok(if is_image_extension(&extension) {
+        format!("![{}]({})", label.unwrap_or(&name), asset_url(&dest))
+    } else {
+        format!("[📎 {}]({})", label.unwrap_or(&name), asset_url(&dest))
+    })
+}
+
+// Rewrites one Obsidian note: attachment embeds and links become Markdown links to
+// copies in the note's attachments folder, note embeds become plain wikilinks, and
+// vault-relative wikilink paths gain the notebook's folder. Returns the targets that
+// resolve to nothing.
+fn rewrite_obsidian_note(
+    content: &str,
+    note: &ObsidianNote,
+    vault: &ObsidianVault,
+    notebook_name: &str,
+    copied: &mut HashMap<(PathBuf, PathBuf), PathBuf>,
+) -> Result<(String, Vec<String>), AzimuthError> {
+    let mut unresolved = Vec::new();
+
+    let mut rewritten = String::with_capacity(content.len());
+    let mut last = 0;
+    for (target, (start, end)) in find_wikilinks(content) {
+        let is_embed = content[..start].ends_with('!');
+        let inner = &content[start + 2..end - 2];
+        let (link, alias) = match inner.split_once('|') {
+            Some((link, alias)) => (link, Some(alias.trim())),
+            None => (inner, None),
+        };
+        let replacement = if vault.has_note(&target) {
+            let link = if target.contains('/') { format!("{}/{}", notebook_name, link.trim()) } else { link.trim().to_string() };
+            match alias {
+                Some(alias) => format!("[[{}|{}]]", link, alias),
+                None => format!("[[{}]]", link),
+            }
+        } else if let Some(source) = vault.resolve_attachment(note, &target) {
+            // Obsidian's `|300` sizes an embedded image rather than labelling it
+            let label = alias.filter(|alias| !alias.chars().all(|c| c.is_ascii_digit() || c == 'x'));
+            copy_obsidian_attachment(note, &source, label, copied)?
+        } else {
+            unresolved.push(target);
+            continue;
+        };
+        let start = if is_embed { start - 1 } else { start };
+        rewritten.push_str(&content[last..start]);
+        rewritten.push_str(&replacement);
+        last = end;
+    }
+    rewritten.push_str(&content[last..]);
+
+    // Relative Markdown links and images to attachments, outside code blocks
+    let link_re = regex::Regex::new(r"(!?)\[([^\]\n]*)\]\(<?([^)<>\n]+?)>?\)").expect("valid link pattern");
+    let mut result = String::with_capacity(rewritten.len());
+    let mut fence: Option<&str> = None;
+    for line in rewritten.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        match fence {
+            Some(marker) => {
+                if trimmed.starts_with(marker) {
+                    fence = None;
+                }
+                result.push_str(line);
+                continue;
+            }
+            None if trimmed.starts_with("```") || trimmed.starts_with("~~~") => {
+                fence = Some(&trimmed[..3]);
+                result.push_str(line);
+                continue;
+            }
+            None => {}
+        }
+        let mut last = 0;
+        for caps in link_re.captures_iter(line) {
+            let whole = caps.get(0).unwrap();
+            let url = caps[3].trim();
+            if url.contains("://") || url.starts_with('#') || url.starts_with("mailto:") {
+                continue;
+            }
+            let target = urlencoding::decode(url).map(|t| t.to_string()).unwrap_or(url.to_string());
+            if vault.has_note(&target) {
+                continue;
+            }
+            let Some(source) = vault.resolve_attachment(note, &target) else {
+                unresolved.push(target);
+                continue;
+            };
+            let label = Some(&caps[2]).filter(|label| !label.is_empty());
+            result.push_str(&line[last..whole.start()]);
+            result.push_str(&copy_obsidian_attachment(note, &source, label, copied)?);
+            last = whole.end();
+        }
+        result.push_str(&line[last..]);
+    }
+    Ok((result, unresolved))
+}
+
+// Copies an Obsidian vault into a new notebook named after it. Attachments that
+// notes embed or link are copied into those notes' attachments folders, and the
+// links rewritten to match; the rest keep their place. The .obsidian config and
+// other hidden folders are left behind. Links that resolve to nothing are reported.
+#[tauri::command]
+fn import_obsidian_vault(base_path: String, vault_path: String) -> Result<ObsidianImportResult, AzimuthError> {
+    let vault_dir = PathBuf::from(&vault_path);
+    if !vault_dir.is_dir() {
+        return Err(AzimuthError::InvalidPath("Invalid folder path".to_string()));
+    }
+    let notebook_name = vault_dir
+        .file_name()
+        .ok_or("Could not get folder name")?
+        .to_string_lossy()
+        .to_string();
+    let dest = PathBuf::from(&base_path).join(&notebook_name);
+    if dest.exists() {
+        return Err(AzimuthError::AlreadyExists(format!("A notebook named '{}' already exists", notebook_name)));
+    }
+
+    let mut vault = ObsidianVault {
+        notes: HashSet::new(),
+        note_paths: HashSet::new(),
+        attachments: HashMap::new(),
+        attachment_paths: HashSet::new(),
+    };
+    let mut markdown_files = Vec::new();
+    let mut other_files = Vec::new();
+    for entry in WalkDir::new(&vault_dir)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+    {
+        let relative = entry.path().strip_prefix(&vault_dir).map_err(|e| AzimuthError::InvalidPath(e.to_string()))?.to_path_buf();
+        let extension = relative.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+        if is_markdown_extension(&extension) {
+            vault.notes.insert(wikilink_key(&relative.to_string_lossy()));
+            vault.note_paths.insert(relative.clone());
+            markdown_files.push(relative);
+        } else {
+            let name = relative.file_name().unwrap_or_default().to_string_lossy().to_lowercase();
+            vault.attachments.entry(name).or_default().push(relative.clone());
+            vault.attachment_paths.insert(relative.clone());
+            other_files.push(relative);
+        }
+    }
+
+    fs::create_dir_all(&dest)?;
+    let mut copied = HashMap::new();
+    let mut unresolved_links = Vec::new();
+    for relative in &markdown_files {
+        let note_dest = dest.join(relative);
+        let note = ObsidianNote {
+            vault: &vault_dir,
+            relative: relative.clone(),
+            attachments_dir: note_dest.with_extension(""),
+            dest: note_dest,
+        };
+        let content = fs::read_to_string(vault_dir.join(relative))?;
+        let (content, unresolved) = rewrite_obsidian_note(&content, &note, &vault, &notebook_name, &mut copied)?;
+        if let Some(parent) = note.dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&note.dest, content)?;
+        for target in unresolved {
+            unresolved_links.push(UnresolvedLink { note: relative.to_string_lossy().to_string(), target });
+        }
+    }
+
+    // Attachments no note links to stay where they were
+    let linked: HashSet<&PathBuf> = copied.keys().map(|(_, source)| source).collect();
+    for relative in other_files.iter().filter(|relative| !linked.contains(relative)) {
+        let target = dest.join(relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(vault_dir.join(relative), &target)?;
+    }
+
+    let children = list_notebooks_simple(&dest, &scan_settings(&base_path))?;
+    Ok(ObsidianImportResult {
+        notebook: Notebook {
+            id: dest.to_string_lossy().to_string(),
+            name: notebook_name,
+            path: dest.to_string_lossy().to_string(),
+            children,
+        },
+        notes_imported: markdown_files.len(),
+        attachments_copied: copied.len(),
+        unresolved_links,
+    })
+}
+
+// Zips the notebook, attachments and subfolders included, under a top-level
+// folder of the notebook's name. Hidden and ignored files are left out.
+#[tauri::command]
+fn export_notebook_zip(notebook_path: String, output_path: String) -> Result<String, AzimuthError> {
+    let notebook = PathBuf::from(&notebook_path);
+    if !notebook.is_dir() {
+        return Err(AzimuthError::NotFound(format!("Notebook does not exist: {}", notebook_path)));
+    }
+    let root_name = notebook
+        .file_name()
+        .ok_or("Could not get folder name")?
+        .to_string_lossy()
+        .to_string();
+    let output = PathBuf::from(&output_path);
+    let scan = scan_settings(&notebook_path);
+
+    let file = fs::File::create(&output)?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    for entry in WalkDir::new(&notebook)
+        .into_iter()
+        .filter_entry(|e| {
+            let name = e.file_name().to_string_lossy();
+            e.depth() == 0 || !(name.starts_with('.') || scan.is_ignored(&name))
+        })
+        .filter_map(|e| e.ok())
+    {
+        // The archive itself, when it is being written inside the notebook
+        if entry.path() == output {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(&notebook).map_err(|e| AzimuthError::InvalidPath(e.to_string()))?;
+        let mut name = root_name.clone();
+        for component in relative.components() {
+            name.push('/');
+            name.push_str(&component.as_os_str().to_string_lossy());
+        }
+        if entry.file_type().is_dir() {
+            zip.add_directory(name, options)?;
+        } else if entry.file_type().is_file() {
+            zip.start_file(name, options)?;
+            let mut source = fs::File::open(entry.path())?;
+            std::io::copy(&mut source, &mut zip)?;
+        }
+    }
+    zip.finish()?;
+    Ok(output.to_string_lossy().to_string())
+}
+
+// Backup import
+#[tauri::command]
+fn import_azimuth_backup(zip_path: String, base_path: String, merge_mode: String) -> Result<BackupImportResult, AzimuthError> {
+    // "keep_local" leaves existing files and settings alone, "keep_backup" lets the backup win
+    let keep_backup = match merge_mode.as_str() {
+        "keep_local" => false,
+        "keep_backup" => true,
+        _ => return Err(AzimuthError::InvalidInput("Invalid merge mode".to_string())),
+    };
+
+    let file = fs::File::open(&zip_path)?;
+    let mut archive = zip::ZipArchive::new(file)?;
+
+    // Validate every entry before writing anything to guard against zip-slip
+    let mut entries = Vec::new();
+    for i in 0..archive.len() {
+        let entry = archive.by_index(i)?;
+        let name = entry.enclosed_name().ok_or(AzimuthError::InvalidPath("Backup contains an unsafe path".to_string()))?;
+        entries.push((name, entry.is_dir()));
+    }
+
+    // The vault root is wherever the settings live, which is a single top-level
+    // folder when the backup was zipped from the vault's parent directory
+    let root = entries
+        .iter()
+        .filter(|(path, is_dir)| {
+            !is_dir && matches!(
+                path.file_name().and_then(|n| n.to_str()),
+                Some(".azimuth_settings.json") | Some(".sync_config.json")
+            )
+        })
+        .map(|(path, _)| path.parent().map(Path::to_path_buf).unwrap_or_default())
+        .min_by_key(|path| path.components().count())
+        .ok_or("Not an Azimuth backup: no .azimuth_settings.json or .sync_config.json found")?;
+
+    let base = PathBuf::from(&base_path);
+    fs::create_dir_all(&base)?;
+
+    let mut files_imported = 0;
+    let mut files_skipped = 0;
+    let mut backup_settings = None;
+    let mut backup_sync_config = None;
+    let mut backup_paths: HashSet<PathBuf> = HashSet::new();
+
+    for (i, (name, is_dir)) in entries.iter().enumerate() {
+        let relative = match name.strip_prefix(&root) {
+            Ok(relative) if !relative.as_os_str().is_empty() => relative.to_path_buf(),
+            _ => continue,
+        };
+        backup_paths.extend(relative.ancestors().filter(|p| !p.as_os_str().is_empty()).map(Path::to_path_buf));
+
+        let target = base.join(&relative);
+        if *is_dir {
+            fs::create_dir_all(&target)?;
+            continue;
+        }
+
+        let mut entry = archive.by_index(i)?;
+        if relative == Path::new(".azimuth_settings.json") || relative == Path::new(".sync_config.json") {
+            let mut content = String::new();
+            std::io::Read::read_to_string(&mut entry, &mut content)?;
+            if relative == Path::new(".azimuth_settings.json") {
+                backup_settings = Some(content);
+            } else {
+                backup_sync_config = Some(content);
+            }
+            continue;
+        }
+
+        if target.exists() && !keep_backup {
+            files_skipped += 1;
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut out = fs::File::create(&target)?;
+        std::io::copy(&mut entry, &mut out)?;
+        files_imported += 1;
+    }
+
+    let mut settings_merged = false;
+    if let Some(content) = backup_settings {
+        let backup: AppSettings = serde_json::from_str(&content)?;
+        let old_base = find_backup_base(
+            backup.favorites.iter()
+                .chain(backup.tags.keys())
+                .chain(backup.notebook_styles.keys())
+                .chain(backup.pinned_folders.iter())
+                .chain(backup.pinned_notes.iter()),
+            &backup_paths,
+        );
+        let remap = |key: &String| -> String {
+            match old_base.as_ref().and_then(|old| Path::new(key).strip_prefix(old).ok()) {
+                Some(rest) => base.join(rest).to_string_lossy().to_string(),
+                None => key.clone(),
+            }
+        };
+
+        update_settings(&base_path, |settings| {
+            if !base.join(".azimuth_settings.json").exists() {
+                *settings = AppSettings {
+                    favorites: Vec::new(),
+                    tags: HashMap::new(),
+                    notebook_styles: HashMap::new(),
+                    pinned_folders: Vec::new(),
+                    pinned_notes: Vec::new(),
+                    ..backup.clone()
+                };
+            }
+
+            for path in backup.favorites.iter().map(&remap) {
+                if !settings.favorites.contains(&path) {
+                    settings.favorites.push(path);
+                }
+            }
+            for path in backup.pinned_folders.iter().map(&remap) {
+                if !settings.pinned_folders.contains(&path) {
+                    settings.pinned_folders.push(path);
+                }
+            }
+            for path in backup.pinned_notes.iter().map(&remap) {
+                if !settings.pinned_notes.contains(&path) {
+                    settings.pinned_notes.push(path);
+                }
+            }
+            for (path, tags) in &backup.tags {
+                let note_tags = settings.tags.entry(remap(path)).or_default();
+                for tag in tags {
+                    if !note_tags.contains(tag) {
+                        note_tags.push(tag.clone());
+                    }
+                }
+            }
+            for (path, style) in &backup.notebook_styles {
+                let path = remap(path);
+                if keep_backup || !settings.notebook_styles.contains_key(&path) {
+                    settings.notebook_styles.insert(path, style.clone());
+                }
+            }
+        })?;
+        settings_merged = true;
+    }
+
+    if let Some(content) = backup_sync_config {
+        let config_path = base.join(".sync_config.json");
+        if keep_backup || !config_path.exists() {
+            fs::write(&config_path, content)?;
+        }
+    }
+
+    Ok(BackupImportResult {
+        files_imported,
+        files_skipped,
+        settings_merged,
+    })
+}
+
+// Settings keys are absolute paths from the machine the backup was made on; the
+// original base is the prefix that, once stripped, leaves a path in the backup
+fn find_backup_base<'a>(keys: impl Iterator<Item = &'a String>, backup_paths: &HashSet<PathBuf>) -> Option<PathBuf> {
+    for key in keys {
+        let components: Vec<_> = Path::new(key).components().collect();
+        for i in 1..components.len() {
+            let suffix: PathBuf = components[i..].iter().collect();
+            if backup_paths.contains(&suffix) {
+                return Some(components[..i].iter().collect());
+            }
+        }
+    }
+    None
+}
+
+// Obsidian export
+#[tauri::command]
+fn export_to_obsidian(notebook_path: String, output_dir: String) -> Result<String, AzimuthError> {
+    let source = PathBuf::from(&notebook_path);
+    if !source.is_dir() {
+        return Err(AzimuthError::NotFound(format!("Notebook does not exist: {}", notebook_path)));
+    }
+
+    let folder_name = source
+        .file_name()
+        .ok_or("Could not get folder name")?
+        .to_string_lossy()
+        .to_string();
+    let dest = PathBuf::from(&output_dir).join(&folder_name);
+    let attachments_dir = dest.join("attachments");
+    fs::create_dir_all(&attachments_dir)?;
+
+    let files: Vec<PathBuf> = WalkDir::new(&source)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_file())
+        .map(|e| e.into_path())
+        .collect();
+
+    let is_note = |path: &PathBuf| {
+        let extension = path.extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        is_text_extension(&extension) && !is_image_extension(&extension)
+    };
+
+    // Copy attachments first so notes can link to their final names
+    let mut exported: HashMap<PathBuf, String> = HashMap::new();
+    for path in files.iter().filter(|p| !is_note(p)) {
+        export_attachment(path, &attachments_dir, &mut exported)?;
+    }
+
+    let asset_re = regex::Regex::new(ASSET_URL_PATTERN)?;
+    for path in files.iter().filter(|p| is_note(p)) {
+        let relative = path.strip_prefix(&source).map_err(|e| AzimuthError::InvalidPath(e.to_string()))?;
+        let target = dest.join(relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(_) => {
+                fs::copy(path, &target)?;
+                continue;
+            }
+        };
+
+        // Links are relative to the note, which may live in a nested folder
+        let prefix = "../".repeat(relative.components().count() - 1);
+        let mut copy_error = None;
+        let rewritten = asset_re.replace_all(&content, |caps: &regex::Captures| {
+            let decoded = urlencoding::decode(&caps[1])
+                .map(|s| s.into_owned())
+                .unwrap_or_else(|_| caps[1].to_string());
+            match export_attachment(&PathBuf::from(decoded), &attachments_dir, &mut exported) {
+                Ok(Some(name)) => format!("{}attachments/{}", prefix, name.replace(' ', "%20")),
+                Ok(None) => caps[0].to_string(),
+                Err(e) => {
+                    copy_error = Some(e);
+                    caps[0].to_string()
+                }
+            }
+        });
+        if let Some(e) = copy_error {
+            return Err(e);
+        }
+
+        fs::write(&target, rewritten.as_bytes())?;
+    }
+
+    Ok(dest.to_string_lossy().to_string())
+}
+
+// Copies an attachment into the export's attachments folder once, returning the
+// name it was stored under (None if the source file no longer exists)
+fn export_attachment(path: &Path, attachments_dir: &Path, exported: &mut HashMap<PathBuf, String>) -> Result<Option<String>, AzimuthError> {
+    if let Some(name) = exported.get(path) {
+        return Ok(Some(name.clone()));
+    }
+    if !path.is_file() {
+        return Ok(None);
+    }
+
+    let file_name = path.file_name().ok_or("Could not get file name")?.to_string_lossy().to_string();
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let extension = path.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+
+    let mut name = file_name;
+    let mut counter = 1;
+    while exported.values().any(|existing| existing == &name) {
+        name = format!("{} {}{}", stem, counter, extension);
+        counter += 1;
+    }
+
+    fs::copy(path, attachments_dir.join(&name))?;
+    exported.insert(path.to_path_buf(), name.clone());
+    Ok(Some(name))
+}
+
+// Single-note export
+const EXPORT_CSS: &str = "body { font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', sans-serif; line-height: 1.6; max-width: 46em; margin: 2em auto; padding: 0 1em; color: #222; }
+img { max-width: 100%; }
+pre, code { font-family: 'SF Mono', 'Fira Code', Consolas, monospace; background: #f5f5f5; border-radius: 4px; }
+pre { padding: 1em; overflow-x: auto; }
+code { padding: 0.1em 0.3em; }
+pre code { padding: 0; }
+blockquote { margin: 0; padding-left: 1em; border-left: 4px solid #ddd; color: #555; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.6em; }
+li:has(> input[type=checkbox]) { list-style: none; }";
+
+fn get_image_mime(ext: &str) -> &str {
+    match ext {
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "bmp" => "image/bmp",
+        "ico" => "image/x-icon",
+        "tiff" | "tif" => "image/tiff",
+        _ => "image/png",
+    }
+}
+
+// Scaled-down copies of image attachments, in the vault's .azimuth_thumbnails/
+// under a hash of the source path, its mtime and the size asked for
+const THUMBNAILS_DIR: &str = ".azimuth_thumbnails";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Thumbnail {
+    pub mime: String,
+    // Base64 of the image bytes
+    pub data: String,
+}
+
+// An image attachment scaled to fit within `max_dim` pixels, keeping its aspect
+// ratio: JPEG for JPEG sources, PNG otherwise. SVGs, images already small enough
+// and files that aren't images come back as they are.
+#[tauri::command]
+fn get_attachment_thumbnail(file_path: String, max_dim: u32) -> Result<Thumbnail, AzimuthError> {
+    let path = PathBuf::from(&file_path);
+    let extension = path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let original = |mime: &str| -> Result<Thumbnail, AzimuthError> {
+        let bytes = fs::read(&path)?;
+        Ok(Thumbnail { mime: mime.to_string(), data: STANDARD.encode(bytes) })
+    };
+    if !is_image_extension(&extension) {
+        return original("application/octet-stream");
+    }
+    if extension == "svg" || max_dim == 0 {
+        return original(get_image_mime(&extension));
+    }
+
+    let (format, mime, cache_extension) = if matches!(extension.as_str(), "jpg" | "jpeg") {
+        (image::ImageFormat::Jpeg, "image/jpeg", "jpg")
+    } else {
+        (image::ImageFormat::Png, "image/png", "png")
+    };
+    let modified = fs::metadata(&path).and_then(|m| m.modified())?;
+    let modified = modified.duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_nanos();
+    let key = hex::encode(Sha256::digest(format!("{}\n{}\n{}", file_path, modified, max_dim)));
+    let vault = path
+        .ancestors()
+        .find(|dir| dir.join(".azimuth_settings.json").exists())
+        .or(path.parent())
+        .unwrap_or(Path::new("."));
+    let cache_path = vault.join(THUMBNAILS_DIR).join(format!("{}.{}", key, cache_extension));
+    if let Ok(bytes) = fs::read(&cache_path) {
+        return Ok(Thumbnail { mime: mime.to_string(), data: STANDARD.encode(bytes) });
+    }
+
+    let image = image::ImageReader::open(&path)
+        .and_then(|reader| reader.with_guessed_format())?
+        .decode()
+        .map_err(|e| AzimuthError::InvalidInput(format!("Unsupported image: {}", e)))?;
+    if image.width() <= max_dim && image.height() <= max_dim {
+        return original(get_image_mime(&extension));
+    }
+    let thumbnail = image.thumbnail(max_dim, max_dim);
+    // JPEG has no alpha channel
+    let thumbnail = if format == image::ImageFormat::Jpeg { image::DynamicImage::ImageRgb8(thumbnail.to_rgb8()) } else { thumbnail };
+    let mut bytes = std::io::Cursor::new(Vec::new());
+    thumbnail.write_to(&mut bytes, format)?;
+    let bytes = bytes.into_inner();
+
+    if let Some(parent) = cache_path.parent()
+        && fs::create_dir_all(parent).is_ok()
+    {
+        let _ = fs::write(&cache_path, &bytes);
+    }
+    Ok(Thumbnail { mime: mime.to_string(), data: STANDARD.encode(bytes) })
+}
+
+// Renders a note to a standalone HTML file, or to PDF through a headless browser.
+// Images are embedded; other attachments are copied to "<name>_files" beside the output.
+#[tauri::command]
+fn export_note(notebook_path: String, note_id: String, format: String, output_path: String) -> Result<String, AzimuthError> {
+    if format != "html" && format != "pdf" {
+        return Err(AzimuthError::InvalidInput(format!("Unsupported export format: {}", format)));
+    }
+    let content = fs::read_to_string(safe_join(Path::new(&notebook_path), &note_id)?)?;
+    let output = PathBuf::from(&output_path);
+    let output_dir = output.parent().map(Path::to_path_buf).unwrap_or_default();
+    let output_stem = output.file_stem().ok_or(AzimuthError::InvalidPath("Invalid output path".to_string()))?.to_string_lossy().to_string();
+    fs::create_dir_all(&output_dir)?;
+
+    let body = render_markdown(content, Some(notebook_path.clone()));
+    let files_dir_name = format!("{}_files", output_stem);
+    let mut exported: HashMap<PathBuf, String> = HashMap::new();
+    let mut export_error = None;
+    let asset_re = regex::Regex::new(r#"(src|href)="asset://localhost/([^"]+)""#)?;
+    let body = asset_re.replace_all(&body, |caps: &regex::Captures| {
+        let url = caps[2].replace("&amp;", "&");
+        let path = PathBuf::from(urlencoding::decode(&url).map(|s| s.into_owned()).unwrap_or(url));
+        let extension = path.extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if &caps[1] == "src" && is_image_extension(&extension) {
+            return match fs::read(&path) {
+                Ok(data) => format!("src=\"data:{};base64,{}\"", get_image_mime(&extension), STANDARD.encode(data)),
+                Err(_) => caps[0].to_string(),
+            };
+        }
+        let files_dir = output_dir.join(&files_dir_name);
+        if let Err(e) = fs::create_dir_all(&files_dir) {
+            export_error = Some(e.into());
+            return caps[0].to_string();
+        }
+        match export_attachment(&path, &files_dir, &mut exported) {
+            Ok(Some(name)) => format!("{}=\"{}/{}\"", &caps[1], urlencoding::encode(&files_dir_name), urlencoding::encode(&name)),
+            Ok(None) => caps[0].to_string(),
+            Err(e) => {
+                export_error = Some(e);
+                caps[0].to_string()
+            }
+        }
+    });
+    if let Some(e) = export_error {
+        return Err(e);
+    }
+
+    let title = PathBuf::from(&note_id).file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or(note_id.clone());
+    let title = title.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+    let html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}\n</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        title, EXPORT_CSS, body
+    );
+
+    if format == "html" {
+        fs::write(&output, html)?;
+    } else {
+        // Written beside the output so links to copied attachments resolve
+        let html_path = output_dir.join(format!(".{}.azimuth-export.html", output_stem));
+        fs::write(&html_path, html)?;
+        let result = print_html_to_pdf(&html_path, &output);
+        let _ = fs::remove_file(&html_path);
+        result?;
+    }
+    Ok(output.to_string_lossy().to_string())
+}
+
+fn find_headless_browser() -> Option<PathBuf> {
+    let candidates: &[&str] = if cfg!(target_os = "macos") {
+        &[
+            "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome",
+            "/Applications/Chromium.app/Contents/MacOS/Chromium",
+            "/Applications/Microsoft Edge.app/Contents/MacOS/Microsoft Edge",
+            "/Applications/Brave Browser.app/Contents/MacOS/Brave Browser",
+        ]
+    } else if cfg!(target_os = "windows") {
+        &[
+            r"C:\Program Files\Google\Chrome\Application\chrome.exe",
+            r"C:\Program Files (x86)\Google\Chrome\Application\chrome.exe",
+            r"C:\Program Files (x86)\Microsoft\Edge\Application\msedge.exe",
+            r"C:\Program Files\Microsoft\Edge\Application\msedge.exe",
+        ]
+    } else {
+        &["google-chrome", "google-chrome-stable", "chromium", "chromium-browser", "microsoft-edge"]
+    };
+    candidates.iter().find_map(|candidate| {
+        let path = Path::new(candidate);
+        if path.is_absolute() {
+            return path.is_file().then(|| path.to_path_buf());
+        }
+        std::env::var_os("PATH")
+            .and_then(|paths| std::env::split_paths(&paths).map(|dir| dir.join(candidate)).find(|p| p.is_file()))
+    })
+}
+
+fn print_html_to_pdf(html_path: &Path, output: &Path) -> Result<(), AzimuthError> {
+    let browser = find_headless_browser()
+        .ok_or("PDF export needs Google Chrome, Chromium or Microsoft Edge to be installed")?;
+    let html_url = fs::canonicalize(html_path)
+        .ok()
+        .and_then(|path| reqwest::Url::from_file_path(path).ok())
+        .ok_or("Could not locate the exported HTML")?;
+    let result = std::process::Command::new(browser)
+        .arg("--headless")
+        .arg("--disable-gpu")
+        .arg("--no-pdf-header-footer")
+        .arg(format!("--print-to-pdf={}", output.to_string_lossy()))
+        .arg(html_url.as_str())
+        .output()?;
+    if !result.status.success() || !output.is_file() {
+        return Err(AzimuthError::Other(format!("PDF export failed: {}", String::from_utf8_lossy(&result.stderr).trim())));
+    }
+    Ok(())
+}
+
+// Combined Markdown export
+const TOC_HEADING: &str = "Table of Contents";
+
+// GitHub-style heading anchor, suffixed with -1, -2, ... when already taken
+fn unique_anchor(title: &str, used: &mut HashMap<String, usize>) -> String {
+    let slug: String = title
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect();
+
+    let count = used.entry(slug.clone()).or_insert(0);
+    let anchor = if *count == 0 { slug.clone() } else { format!("{}-{}", slug, count) };
+    *count += 1;
+    anchor
+}
+
+// Pushes every heading down a level so each note sits under its own top-level heading
+fn demote_headings(body: &str) -> String {
+    let mut in_fence = false;
+    let mut result = String::with_capacity(body.len());
+    for line in body.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        }
+        let level = line.chars().take_while(|&c| c == '#').count();
+        if !in_fence && (1..6).contains(&level) && line[level..].starts_with([' ', '\t']) {
+            result.push('#');
+        }
+        result.push_str(line);
+    }
+    result
+}
+
+// Joins a notebook's notes, in their manual order, into one Markdown file with a
+// heading per note. Links between those notes become links to the matching heading.
+#[tauri::command]
+fn concatenate_notebook(notebook_path: String, output_path: String, include_toc: bool) -> Result<String, AzimuthError> {
+    let source = PathBuf::from(&notebook_path);
+    if !source.is_dir() {
+        return Err(AzimuthError::NotFound(format!("Notebook does not exist: {}", notebook_path)));
+    }
+
+    let mut used_anchors = HashMap::new();
+    if include_toc {
+        unique_anchor(TOC_HEADING, &mut used_anchors);
+    }
+
+    // (note id, title, anchor, body)
+    let mut notes = Vec::new();
+    for note_id in resolve_note_order(&source)? {
+        let extension = Path::new(&note_id).extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if !is_text_extension(&extension) || is_image_extension(&extension) {
+            continue;
+        }
+        let content = match fs::read_to_string(source.join(&note_id)) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+        let title = note_title(&note_id, &content);
+        let anchor = unique_anchor(&title, &mut used_anchors);
+        let body = split_frontmatter(&content)
+            .map(|(_, body)| body.to_string())
+            .unwrap_or(content);
+        notes.push((note_id, title, anchor, body));
+    }
+
+    let anchors: HashMap<&str, &str> = notes.iter()
+        .map(|(id, _, anchor, _)| (id.as_str(), anchor.as_str()))
+        .collect();
+    let link_re = regex::Regex::new(r"\]\(([^)\s#]+)(#[^)\s]*)?\)")?;
+
+    let mut output = String::new();
+    if include_toc {
+        output.push_str(&format!("# {}\n\n", TOC_HEADING));
+        for (_, title, anchor, _) in &notes {
+            output.push_str(&format!("- [{}](#{})\n", title, anchor));
+        }
+        output.push('\n');
+    }
+
+    for (_, title, anchor, body) in &notes {
+        let body = link_re.replace_all(body, |caps: &regex::Captures| {
+            let target = urlencoding::decode(&caps[1])
+                .map(|s| s.into_owned())
+                .unwrap_or_else(|_| caps[1].to_string());
+            let target = target.strip_prefix("./").unwrap_or(&target);
+            match anchors.get(target) {
+                Some(anchor) => format!("](#{})", anchor),
+                None => caps[0].to_string(),
+            }
+        });
+        output.push_str(&format!("<a id=\"{}\"></a>\n\n# {}\n\n", anchor, title));
+        output.push_str(demote_headings(&body).trim());
+        output.push_str("\n\n");
+    }
+
+    let output_file = PathBuf::from(&output_path);
+    if let Some(parent) = output_file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&output_file, output.trim_end().to_string() + "\n")?;
+    Ok(output_file.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+fn is_directory(path: String) -> bool {
+    PathBuf::from(&path).is_dir()
+}
+
+#[tauri::command]
+fn move_notebook(source_path: String, target_path: String) -> Result<(), AzimuthError> {
+    let source = PathBuf::from(&source_path);
+    let target_dir = PathBuf::from(&target_path);
+    
+    if !source.exists() {
+        return Err(AzimuthError::NotFound(format!("Source folder does not exist: {}", source_path)));
+    }
+    
+    if !source.is_dir() {
+        return Err(AzimuthError::InvalidPath(format!("Source is not a directory: {}", source_path)));
+    }
+    
+    if !target_dir.exists() {
+        return Err(AzimuthError::NotFound(format!("Target folder does not exist: {}", target_path)));
+    }
+    
+    let folder_name = source
+        .file_name()
+        .ok_or("Could not get folder name")?
+        .to_string_lossy()
+        .to_string();
+    
+    let destination = target_dir.join(&folder_name);
+    
+    // Check if destination already exists
+    if destination.exists() {
+        return Err(AzimuthError::AlreadyExists(format!("A folder named '{}' already exists in the target location", folder_name)));
+    }
+    
+    // Check if trying to move into itself
+    if target_dir.starts_with(&source) {
+        return Err(AzimuthError::InvalidPath("Cannot move a folder into itself".to_string()));
+    }
+    
+    invalidate_notebook_size(&source);
+    if let Some(parent) = source.parent() {
+        invalidate_notebook_size(parent);
+    }
+    invalidate_notebook_size(&target_dir);
+
+    // Perform the move (rename)
+    if let Err(e) = fs::rename(&source, &destination) {
+        // If rename fails (e.g., cross-device), try copy and delete
+        copy_dir_recursive(&source, &destination)
+            .map_err(|copy_err| AzimuthError::io_context(copy_err, format!("Failed to move folder: {} (copy failed)", e)))?;
+        fs::remove_dir_all(&source)
+            .map_err(|del_err| AzimuthError::io_context(del_err, "Folder copied but failed to remove original"))?;
+    }
+    remap_settings_paths(&source, &destination)
+}
+
+// Moves a notebook folder to the vault's trash, or removes it outright when
+// `permanent`, and drops the settings that point inside it. Returns how many notes it held.
+#[tauri::command]
+fn delete_notebook(base_path: String, notebook_path: String, permanent: Option<bool>) -> Result<usize, AzimuthError> {
+    let base = PathBuf::from(&base_path);
+    let notebook = PathBuf::from(&notebook_path);
+    if !notebook.is_dir() {
+        return Err(AzimuthError::NotFound(format!("Notebook does not exist: {}", notebook_path)));
+    }
+    let root = fs::canonicalize(&base)?;
+    let canonical = fs::canonicalize(&notebook)?;
+    if canonical == root || !canonical.starts_with(&root) {
+        return Err(AzimuthError::InvalidPath(format!("Notebook is not inside the notes folder: {}", notebook_path)));
+    }
+
+    let note_count = snapshot_files(&notebook)
+        .iter()
+        .filter(|relative| Path::new(relative).file_name().is_some_and(|name| name != NOTE_ORDER_FILE))
+        .count();
+
+    if permanent.unwrap_or(false) {
+        fs::remove_dir_all(&notebook)?;
+    } else {
+        let entry_dir = base.join(TRASH_DIR).join(chrono::Utc::now().format(TRASH_ID_FORMAT).to_string());
+        move_to_trash(&base, &entry_dir, &notebook)?;
+    }
+
+    let inside = |key: &String| Path::new(key).starts_with(&notebook);
+    update_settings(&base_path, |settings| {
+        settings.favorites.retain(|path| !inside(path));
+        settings.pinned_folders.retain(|path| !inside(path));
+        settings.pinned_notes.retain(|path| !inside(path));
+        settings.tags.retain(|path, _| !inside(path));
+        settings.notebook_styles.retain(|path, _| !inside(path));
+        settings.note_reminders.retain(|path, _| !inside(path));
+        settings.archived.retain(|path| !base.join(path).starts_with(&notebook));
+    })?;
+
+    invalidate_notebook_size(&notebook);
+    if let Some(parent) = notebook.parent() {
+        invalidate_notebook_size(parent);
+        invalidate_notebook_activity(parent);
+    }
+    Ok(note_count)
+}
+
+fn copy_dir_recursive(src: &PathBuf, dst: &PathBuf) -> std::io::Result<()> {
+    if !dst.exists() {
+        fs::create_dir_all(dst)?;
+    }
+    
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let src_path = entry.path();
+        let dst_path = dst.join(entry.file_name());
+        
+        if src_path.is_dir() {
+            copy_dir_recursive(&src_path, &dst_path)?;
+        } else {
+            fs::copy(&src_path, &dst_path)?;
+        }
+    }
+    Ok(())
+}
+// Archive
+const ARCHIVE_DIR: &str = "Archive";
+
+// Archived notebook paths to leave out, or none when the user has turned exclusion off
+fn excluded_archive_paths(base_path: &str) -> Vec<String> {
+    match load_settings(base_path.to_string()) {
+        Ok(settings) if settings.exclude_archived => settings.archived,
+        _ => Vec::new(),
+    }
+}
+
+fn is_archived(base_path: &str, path: &Path, archived: &[String]) -> bool {
+    match path.strip_prefix(base_path) {
+        Ok(relative) => archived.iter().any(|entry| relative.starts_with(entry)),
+        Err(_) => false,
+    }
+}
+
+// Archived notebooks keep their original layout under Archive/, so from the
+// notebook list it's the Archive folder itself that gets hidden
+fn is_hidden_archive(base_path: &str, path: &Path, archived: &[String]) -> bool {
+    !archived.is_empty() && path == Path::new(base_path).join(ARCHIVE_DIR)
+}
+
+fn archive_relative_path(base_path: &str, notebook_path: &str) -> Result<PathBuf, AzimuthError> {
+    let notebook = PathBuf::from(notebook_path);
+    if !notebook.is_dir() {
+        return Err(AzimuthError::NotFound(format!("Notebook does not exist: {}", notebook_path)));
+    }
+    let root = fs::canonicalize(base_path)?;
+    let canonical = fs::canonicalize(&notebook)?;
+    canonical
+        .strip_prefix(&root)
+        .map(|p| p.to_path_buf())
+        .ok()
+        .filter(|p| !p.as_os_str().is_empty())
+        .ok_or_else(|| AzimuthError::InvalidPath(format!("Notebook is not inside the notes folder: {}", notebook_path)))
+}
+
+fn archive_key(relative: &Path) -> String {
+    relative.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/")
+}
+
+// Moves the notebook to Archive/<its path relative to the notes folder> and records it
+#[tauri::command]
+fn archive_notebook(base_path: String, notebook_path: String) -> Result<String, AzimuthError> {
+    let relative = archive_relative_path(&base_path, &notebook_path)?;
+    if relative.starts_with(ARCHIVE_DIR) {
+        return Err(AzimuthError::Other(format!("Notebook is already archived: {}", notebook_path)));
+    }
+
+    let destination = PathBuf::from(&base_path).join(ARCHIVE_DIR).join(&relative);
+    let target_dir = destination.parent().ok_or("Could not get archive folder")?;
+    fs::create_dir_all(target_dir)?;
+    move_notebook(notebook_path, target_dir.to_string_lossy().to_string())?;
+
+    let key = archive_key(&Path::new(ARCHIVE_DIR).join(&relative));
+    update_settings(&base_path, |settings| {
+        if !settings.archived.contains(&key) {
+            settings.archived.push(key);
+        }
+    })?;
+    Ok(destination.to_string_lossy().to_string())
+}
+
+// Moves an archived notebook back to where it was archived from
+#[tauri::command]
+fn unarchive_notebook(base_path: String, notebook_path: String) -> Result<String, AzimuthError> {
+    let relative = archive_relative_path(&base_path, &notebook_path)?;
+    let original = relative
+        .strip_prefix(ARCHIVE_DIR)
+        .ok()
+        .filter(|p| !p.as_os_str().is_empty())
+        .ok_or_else(|| format!("Notebook is not archived: {}", notebook_path))?
+        .to_path_buf();
+
+    let destination = PathBuf::from(&base_path).join(&original);
+    let target_dir = destination.parent().ok_or("Could not get notebook folder")?;
+    fs::create_dir_all(target_dir)?;
+    move_notebook(notebook_path, target_dir.to_string_lossy().to_string())?;
+
+    let key = archive_key(&relative);
+    update_settings(&base_path, |settings| settings.archived.retain(|entry| entry != &key))?;
+    Ok(destination.to_string_lossy().to_string())
+}
+
+// Single SHA-256 over every file's relative path and content hash, in sorted path
+// order, so two vaults with the same content produce the same digest
+#[tauri::command]
+fn vault_fingerprint(base_path: String) -> Result<String, AzimuthError> {
+    let base = PathBuf::from(&base_path);
+    if !base.is_dir() {
+        return Err(AzimuthError::NotFound(format!("Notes folder does not exist: {}", base_path)));
+    }
+
+    let mut files: Vec<(String, PathBuf)> = WalkDir::new(&base)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .map(|e| {
+            let relative = e.path()
+                .strip_prefix(&base)
+                .map(|p| p.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/"))
+                .unwrap_or_default();
+            (relative, e.into_path())
+        })
+        .collect();
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut hasher = Sha256::new();
+    for (relative, path) in files {
+        hasher.update(relative.as_bytes());
+        hasher.update([0]);
+        hasher.update(get_file_hash(&path)?.as_bytes());
+        hasher.update([b'\n']);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+// Cloud Sync Implementation
+
+// Managed Tauri state shared with cancel_sync, checked by the sync loops between files
+#[derive(Default)]
+pub struct SyncControl {
+    cancel: Arc<AtomicBool>,
+}
+
+impl SyncControl {
+    // Clears any cancel left over from an earlier sync
+    fn start(&self) -> Arc<AtomicBool> {
+        self.cancel.store(false, Ordering::SeqCst);
+        self.cancel.clone()
+    }
+}
+
+#[tauri::command]
+fn cancel_sync(sync: tauri::State<'_, SyncControl>) {
+    sync.cancel.store(true, Ordering::SeqCst);
+}
+
+const SYNC_UPLOAD_CONCURRENCY: usize = 4;
+const SYNC_RETRY_ATTEMPTS: u32 = 3;
+const MAX_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(60);
+
+// Sends a request, retrying 429 and 5xx responses (and dropped connections) with
+// exponential backoff, or after the server's Retry-After when it gives one
+async fn send_with_retry(request: reqwest::RequestBuilder) -> Result<reqwest::Response, AzimuthError> {
+    // A streamed body (a throttled upload) can only be sent once
+    if request.try_clone().is_none() {
+        return Ok(request.send().await?);
+    }
+    let mut delay = std::time::Duration::from_millis(500);
+    let mut attempt = 1;
+    loop {
+        let attempt_request = request.try_clone().ok_or("Request can't be retried")?;
+        let result = attempt_request.send().await;
+        let retryable = match &result {
+            Ok(response) => {
+                response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS || response.status().is_server_error()
+            }
+            Err(e) => e.is_timeout() || e.is_connect(),
+        };
+        if !retryable || attempt == SYNC_RETRY_ATTEMPTS {
+            return Ok(result?);
+        }
+
+        let retry_after = result.ok()
+            .and_then(|response| response.headers().get(reqwest::header::RETRY_AFTER)?.to_str().ok()?.parse::<u64>().ok())
+            .map(std::time::Duration::from_secs);
+        tokio::time::sleep(retry_after.unwrap_or(delay).min(MAX_RETRY_DELAY)).await;
+        delay *= 2;
+        attempt += 1;
+    }
+}
+
+// Appends per-file failures to a sync summary, listing the first few
+fn append_sync_failures(message: &mut String, failures: &[String]) {
+    const SHOWN: usize = 5;
+    if failures.is_empty() {
+        return;
+    }
+    message.push_str(&format!(", {} failed ({}", failures.len(), failures[..failures.len().min(SHOWN)].join("; ")));
+    if failures.len() > SHOWN {
+        message.push_str(&format!("; and {} more", failures.len() - SHOWN));
+    }
+    message.push(')');
+}
+
+// Stamps the vault's last sync time when `status` is a success
+fn finish_sync<E: From<AzimuthError>>(notes_path: &str, status: SyncStatus) -> Result<SyncStatus, E> {
+    if status.success {
+        record_last_sync(notes_path)?;
+    }
+    Ok(status)
+}
+
+// Partial result for a cancelled sync. Files already transferred stay transferred.
+fn cancelled_sync(files_uploaded: usize, files_downloaded: usize, conflicts: Vec<SyncConflict>) -> SyncStatus {
+    SyncStatus {
+        success: false,
+        message: "cancelled".to_string(),
+        files_uploaded,
+        files_downloaded,
+        conflicts,
+    }
+}
+
+// Optional bandwidth cap for sync transfers. It is approximate and per transfer: each
+// upload or download is held to it on its own, so transfers running side by side
+// (Dropbox uploads, for one) can add up to more.
+const THROTTLE_CHUNK_SIZE: usize = 16 * 1024;
+
+struct Throttle {
+    bytes_per_sec: u64,
+    started: std::time::Instant,
+    transferred: u64,
+}
+
+impl Throttle {
+    fn new(bytes_per_sec: u64) -> Self {
+        Throttle { bytes_per_sec: bytes_per_sec.max(1), started: std::time::Instant::now(), transferred: 0 }
+    }
+
+    // How long to wait after `bytes` more so the average rate stays under the cap
+    fn delay(&mut self, bytes: usize) -> std::time::Duration {
+        self.transferred += bytes as u64;
+        let due = std::time::Duration::from_secs_f64(self.transferred as f64 / self.bytes_per_sec as f64);
+        due.saturating_sub(self.started.elapsed())
+    }
+}
+
+// Rate-limited reader for the blocking transfers (SFTP)
+struct ThrottledReader<R> {
+    inner: R,
+    throttle: Option<Throttle>,
+}
+
+impl<R: std::io::Read> ThrottledReader<R> {
+    fn new(inner: R, max_bytes_per_sec: Option<u64>) -> Self {
+        ThrottledReader { inner, throttle: max_bytes_per_sec.map(Throttle::new) }
+    }
+}
+
+impl<R: std::io::Read> std::io::Read for ThrottledReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let Some(throttle) = &mut self.throttle else {
+            return self.inner.read(buf);
+        };
+        let len = buf.len().min(THROTTLE_CHUNK_SIZE);
+        let read = self.inner.read(&mut buf[..len])?;
+        std::thread::sleep(throttle.delay(read));
+        Ok(read)
+    }
+}
+
+// Upload body that is streamed out under the cap. Uncapped bodies stay in memory,
+// which is what lets send_with_retry resend them.
+fn throttled_body(content: Vec<u8>, max_bytes_per_sec: Option<u64>) -> reqwest::Body {
+    let Some(limit) = max_bytes_per_sec else {
+        return content.into();
+    };
+    let chunks = futures_util::stream::unfold((content, 0, Throttle::new(limit)), |(content, offset, mut throttle)| async move {
+        if offset >= content.len() {
+            return None;
+        }
+        let end = (offset + THROTTLE_CHUNK_SIZE).min(content.len());
+        tokio::time::sleep(throttle.delay(end - offset)).await;
+        let chunk = content[offset..end].to_vec();
+        Some((Ok::<_, std::io::Error>(chunk), (content, end, throttle)))
+    });
+    reqwest::Body::wrap_stream(chunks)
+}
+
+// Reads a download's body, under the cap when there is one
+async fn read_throttled(mut response: reqwest::Response, max_bytes_per_sec: Option<u64>) -> Result<Vec<u8>, AzimuthError> {
+    let Some(limit) = max_bytes_per_sec else {
+        return Ok(response.bytes().await?.to_vec());
+    };
+    let mut throttle = Throttle::new(limit);
+    let mut content = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        content.extend_from_slice(&chunk);
+        tokio::time::sleep(throttle.delay(chunk.len())).await;
+    }
+    Ok(content)
+}
+
+// `endpoint_url` points the client at an S3-compatible service (Backblaze B2, MinIO,
+// Wasabi, Cloudflare R2) instead of AWS. MinIO usually also needs path-style addressing.
+fn s3_client(
+    region: String,
+    access_key: &str,
+    secret_key: &str,
+    endpoint_url: Option<&str>,
+    force_path_style: bool,
+) -> aws_sdk_s3::Client {
+    use aws_config::Region;
+    use aws_sdk_s3::config::Credentials;
+
+    let credentials = Credentials::new(access_key, secret_key, None, None, "azimuth");
+    let mut builder = aws_sdk_s3::Config::builder()
+        .behavior_version(aws_config::BehaviorVersion::latest())
+        .region(Region::new(region))
+        .credentials_provider(credentials)
+        .force_path_style(force_path_style);
+    if let Some(endpoint_url) = endpoint_url.filter(|url| !url.is_empty()) {
+        builder = builder.endpoint_url(endpoint_url);
+    }
+
+    aws_sdk_s3::Client::from_conf(builder.build())
+}
+
+// Every object in the bucket, a page at a time. A listing that ends early is an
+// error, as deletions are planned from what is missing from it.
+async fn s3_list_objects(client: &aws_sdk_s3::Client, bucket: &str) -> Result<Vec<aws_sdk_s3::types::Object>, AzimuthError> {
+    let mut objects = Vec::new();
+    let mut continuation_token = None;
+    loop {
+        let list_result = client.list_objects_v2()
+            .bucket(bucket)
+            .set_continuation_token(continuation_token)
+            .send()
+            .await?;
+        objects.extend(list_result.contents().iter().cloned());
+
+        continuation_token = list_result.next_continuation_token().map(|t| t.to_string());
+        if continuation_token.is_none() {
+            if list_result.is_truncated() == Some(true) {
+                return Err(AzimuthError::Network("S3 listing was truncated without a continuation token".to_string()));
+            }
+            return Ok(objects);
+        }
+    }
+}
+
+// Per-file record of the last successful S3 sync, so each side's changes since then
+// can be told apart. Keyed by object key (the path relative to the notes folder).
+const SYNC_STATE_FILE: &str = ".azimuth_sync_state.json";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+struct FileSyncState {
+    local_hash: String,
+    remote_etag: String,
+    synced_at: String,
+    // ETag of the remote copy saved as a .conflict file, until the conflict is resolved
+    #[serde(default)]
+    conflict_etag: Option<String>,
+}
+
+fn load_sync_state(base_path: &Path) -> HashMap<String, FileSyncState> {
+    fs::read_to_string(base_path.join(SYNC_STATE_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_sync_state(base_path: &Path, state: &HashMap<String, FileSyncState>) -> Result<(), AzimuthError> {
+    let json = serde_json::to_string_pretty(state)?;
+    Ok(fs::write(base_path.join(SYNC_STATE_FILE), json)?)
+}
+
+// Where the remote side of a conflict is kept until resolve_conflict runs
+fn conflict_path(file_path: &Path) -> PathBuf {
+    file_path.with_extension("conflict")
+}
+
+fn synced_state(local_hash: String, remote_etag: Option<String>) -> FileSyncState {
+    FileSyncState {
+        local_hash,
+        remote_etag: remote_etag.unwrap_or_default().trim_matches('"').to_string(),
+        synced_at: chrono::Utc::now().to_rfc3339(),
+        conflict_etag: None,
+    }
+}
+
+// S3 ETags are MD5 or an opaque multipart digest, never the SHA-256 used locally,
+// so uploads carry it as x-amz-meta-sha256. Encrypted uploads carry an HMAC of it
+// instead (see s3_checksum), as a plain hash would reveal which files are the same.
+const S3_SHA256_METADATA: &str = "sha256";
+
+// The x-amz-meta-sha256 value for a file with SHA-256 `local_hash`
+fn s3_checksum(cipher: Option<&SyncCipher>, local_hash: &str) -> Result<String, AzimuthError> {
+    match cipher {
+        Some(cipher) => Ok(format!("{}:{}", hex::encode(cipher.salt), cipher.keyed_hash(&cipher.salt, local_hash)?)),
+        None => Ok(local_hash.to_string()),
+    }
+}
+
+// Whether a stored x-amz-meta-sha256 value is the checksum of `local_hash`. Keyed
+// checksums name the salt they were made with, so other devices can check them too.
+fn s3_checksum_matches(cipher: Option<&SyncCipher>, stored: &str, local_hash: &str) -> bool {
+    match stored.split_once(':') {
+        Some((salt, tag)) => {
+            let salt = hex::decode(salt).ok().and_then(|salt| <[u8; SALT_LEN]>::try_from(salt).ok());
+            match (cipher, salt) {
+                (Some(cipher), Some(salt)) => cipher.keyed_hash(&salt, local_hash).is_ok_and(|expected| expected == tag),
+                _ => false,
+            }
+        }
+        None => stored == local_hash,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum S3SyncAction {
+    InSync,
+    Upload,
+    Download,
+    Conflict,
+}
+
+// Decides what to do with a local file given its last sync record and the remote
+// object's ETag and stored SHA-256 (if it exists). A remote copy whose SHA-256
+// matches the local file is in sync however either side got there.
+fn plan_s3_sync(
+    local_hash: &str,
+    state: Option<&FileSyncState>,
+    remote_etag: Option<&str>,
+    remote_sha: Option<&str>,
+) -> S3SyncAction {
+    match (state, remote_etag) {
+        (_, None) => S3SyncAction::Upload,
+        _ if remote_sha == Some(local_hash) => S3SyncAction::InSync,
+        (Some(state), Some(remote_etag)) => {
+            let local_changed = state.local_hash != local_hash;
+            let remote_changed = state.remote_etag != remote_etag;
+            match (local_changed, remote_changed) {
+                (true, true) => S3SyncAction::Conflict,
+                (true, false) => S3SyncAction::Upload,
+                (false, true) => S3SyncAction::Download,
+                (false, false) => S3SyncAction::InSync,
+            }
+        }
+        (None, Some(_)) => match remote_sha {
+            Some(_) => S3SyncAction::Conflict,
+            // Uploaded before checksums were stored; the local copy wins as it used to
+            None => S3SyncAction::Upload,
+        },
+    }
+}
+
+// Client-side encryption for cloud sync. Encrypted files are
+// ENCRYPTION_MAGIC | salt (16) | nonce (12) | ChaCha20-Poly1305 ciphertext, with the key
+// derived from the sync passphrase by Argon2id. Only the salt is stored locally.
+const ENCRYPTION_MAGIC: &[u8] = b"AZENC1";
+const ENCRYPTION_FILE: &str = ".azimuth_encryption.json";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct EncryptionSettings {
+    salt: String,
+}
+
+struct SyncCipher {
+    passphrase: String,
+    salt: [u8; SALT_LEN],
+    // Files uploaded from another device may carry that device's salt
+    keys: Mutex<HashMap<[u8; SALT_LEN], chacha20poly1305::Key>>,
+}
+
+impl SyncCipher {
+    // None when no passphrase is set. The vault's salt is created on first use.
+    fn for_sync(base_path: &Path, passphrase: Option<String>) -> Result<Option<Self>, AzimuthError> {
+        use chacha20poly1305::aead::{rand_core::RngCore, OsRng};
+
+        let passphrase = match passphrase.filter(|p| !p.is_empty()) {
+            Some(passphrase) => passphrase,
+            None => return Ok(None),
+        };
+        let settings_path = base_path.join(ENCRYPTION_FILE);
+        let stored = fs::read_to_string(&settings_path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<EncryptionSettings>(&content).ok())
+            .and_then(|settings| STANDARD.decode(settings.salt).ok())
+            .and_then(|salt| <[u8; SALT_LEN]>::try_from(salt).ok());
+        let salt = match stored {
+            Some(salt) => salt,
+            None => {
+                let mut salt = [0u8; SALT_LEN];
+                OsRng.fill_bytes(&mut salt);
+                let settings = EncryptionSettings { salt: STANDARD.encode(salt) };
+                let json = serde_json::to_string_pretty(&settings)?;
+                fs::write(&settings_path, json)?;
+                salt
+            }
+        };
+        Ok(Some(SyncCipher { passphrase, salt, keys: Mutex::new(HashMap::new()) }))
+    }
+
+    fn key(&self, salt: &[u8; SALT_LEN]) -> Result<chacha20poly1305::Key, AzimuthError> {
+        let mut keys = self.keys.lock()?;
+        if let Some(key) = keys.get(salt) {
+            return Ok(*key);
+        }
+        let mut key = chacha20poly1305::Key::default();
+        argon2::Argon2::default()
+            .hash_password_into(self.passphrase.as_bytes(), salt, &mut key)
+            .map_err(|e| AzimuthError::Other(e.to_string()))?;
+        keys.insert(*salt, key);
+        Ok(key)
+    }
+
+    // HMAC-SHA256 of `message`, under a key kept apart from the encryption key
+    fn keyed_hash(&self, salt: &[u8; SALT_LEN], message: &str) -> Result<String, AzimuthError> {
+        use hmac::Mac;
+
+        let key = Sha256::new()
+            .chain_update(b"azimuth sync checksum")
+            .chain_update(self.key(salt)?)
+            .finalize();
+        let mut mac = hmac::Hmac::<Sha256>::new_from_slice(&key).map_err(|e| AzimuthError::Other(e.to_string()))?;
+        mac.update(message.as_bytes());
+        Ok(hex::encode(mac.finalize().into_bytes()))
+    }
+
+    fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, AzimuthError> {
+        use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+
+        let cipher = chacha20poly1305::ChaCha20Poly1305::new(&self.key(&self.salt)?);
+        let nonce = chacha20poly1305::ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let mut header = ENCRYPTION_MAGIC.to_vec();
+        header.extend_from_slice(&self.salt);
+        let ciphertext = cipher
+            .encrypt(&nonce, Payload { msg: plaintext, aad: &header })
+            .map_err(|_| AzimuthError::Other("Encryption failed".to_string()))?;
+
+        let mut output = header;
+        output.extend_from_slice(&nonce);
+        output.extend_from_slice(&ciphertext);
+        Ok(output)
+    }
+
+    fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, AzimuthError> {
+        use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+
+        let header_len = ENCRYPTION_MAGIC.len() + SALT_LEN;
+        if data.len() < header_len + NONCE_LEN {
+            return Err(AzimuthError::InvalidInput("Encrypted file is truncated".to_string()));
+        }
+        let (header, rest) = data.split_at(header_len);
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        let salt: [u8; SALT_LEN] = header[ENCRYPTION_MAGIC.len()..].try_into().map_err(|_| AzimuthError::InvalidInput("Invalid salt".to_string()))?;
+
+        let cipher = chacha20poly1305::ChaCha20Poly1305::new(&self.key(&salt)?);
+        cipher
+            .decrypt(chacha20poly1305::Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: header })
+            .map_err(|_| AzimuthError::InvalidInput("Could not decrypt: the sync passphrase is wrong or the file is corrupted".to_string()))
+    }
+}
+
+fn encrypt_for_upload(cipher: Option<&SyncCipher>, content: Vec<u8>) -> Result<Vec<u8>, AzimuthError> {
+    match cipher {
+        Some(cipher) => cipher.encrypt(&content),
+        None => Ok(content),
+    }
+}
+
+// Files uploaded before encryption was turned on come back unchanged
+fn decrypt_download(cipher: Option<&SyncCipher>, path: &str, content: &[u8]) -> Result<Vec<u8>, AzimuthError> {
+    if !content.starts_with(ENCRYPTION_MAGIC) {
+        return Ok(content.to_vec());
+    }
+    match cipher {
+        Some(cipher) => cipher.decrypt(content).map_err(|e| AzimuthError::InvalidInput(format!("{}: {}", path, e))),
+        None => Err(AzimuthError::InvalidInput(format!("{} is encrypted; set the sync passphrase to download it", path))),
+    }
+}
+
+// Downloads an object to `dest`, returning its ETag
+async fn s3_download(
+    client: &aws_sdk_s3::Client,
+    bucket: &str,
+    key: &str,
+    dest: &Path,
+    cipher: Option<&SyncCipher>,
+    max_bytes_per_sec: Option<u64>,
+) -> Result<Option<String>, AzimuthError> {
+    let mut result = client.get_object()
+        .bucket(bucket)
+        .key(key)
+        .send()
+        .await?;
+    let etag = result.e_tag.clone();
+    let mut data = Vec::new();
+    let mut throttle = max_bytes_per_sec.map(Throttle::new);
+    while let Some(chunk) = result.body.try_next().await.map_err(|e| AzimuthError::Network(e.to_string()))? {
+        data.extend_from_slice(&chunk);
+        if let Some(throttle) = &mut throttle {
+            tokio::time::sleep(throttle.delay(chunk.len())).await;
+        }
+    }
+
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let content = decrypt_download(cipher, key, &data)?;
+    fs::write(dest, content)?;
+    Ok(etag)
+}
+
+// Paths present on both sides after the last sync with a provider, so a path missing
+// from one side now can be told apart from one that was never synced
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+struct SyncManifest {
+    synced_at: Option<String>,
+    // Relative path -> local SHA-256 at the time of the sync
+    files: HashMap<String, String>,
+    // Providers that can list changes since a cursor keep it here, along with the
+    // remote listing it brings up to date
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cursor: Option<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    remote: HashMap<String, RemoteFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+struct RemoteFile {
+    // The provider's own content hash
+    hash: String,
+    modified: String,
+}
+
+fn sync_manifest_path(base_path: &Path, provider: &str) -> PathBuf {
+    base_path.join(format!(".sync_manifest_{}.json", provider))
+}
+
+fn load_sync_manifest(base_path: &Path, provider: &str) -> SyncManifest {
+    fs::read_to_string(sync_manifest_path(base_path, provider))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+// A manifest recording every path in `synced` that still exists locally
+fn new_sync_manifest<'a>(base_path: &Path, synced: impl IntoIterator<Item = &'a String>) -> SyncManifest {
+    let files = synced
+        .into_iter()
+        .filter_map(|path| {
+            get_file_hash(&base_path.join(path)).ok().map(|hash| (path.clone(), hash))
+        })
+        .collect();
+    SyncManifest {
+        synced_at: Some(chrono::Utc::now().to_rfc3339()),
+        files,
+        ..Default::default()
+    }
+}
+
+fn write_sync_manifest(base_path: &Path, provider: &str, manifest: &SyncManifest) -> Result<(), AzimuthError> {
+    let json = serde_json::to_string_pretty(manifest)?;
+    Ok(fs::write(sync_manifest_path(base_path, provider), json)?)
+}
+
+fn save_sync_manifest<'a>(
+    base_path: &Path,
+    provider: &str,
+    synced: impl IntoIterator<Item = &'a String>,
+) -> Result<(), AzimuthError> {
+    write_sync_manifest(base_path, provider, &new_sync_manifest(base_path, synced))
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct DeletionPlan {
+    delete_remote: Vec<String>,
+    delete_local: Vec<String>,
+}
+
+// A manifest path deleted on one side is deleted on the other, unless the surviving
+// copy was changed since the last sync, in which case it is synced back instead
+fn plan_deletions(
+    manifest: &SyncManifest,
+    local: &HashMap<String, String>,
+    remote: &HashMap<String, chrono::DateTime<chrono::Utc>>,
+) -> DeletionPlan {
+    let synced_at = manifest.synced_at.as_deref()
+        .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+        .map(|t| t.with_timezone(&chrono::Utc));
+
+    let mut plan = DeletionPlan::default();
+    for (path, synced_hash) in &manifest.files {
+        match (local.get(path), remote.get(path)) {
+            (None, Some(remote_modified))
+                if synced_at.is_some_and(|synced_at| *remote_modified <= synced_at) =>
+            {
+                plan.delete_remote.push(path.clone());
+            }
+            (Some(local_hash), None) if local_hash == synced_hash => {
+                plan.delete_local.push(path.clone());
+            }
+            _ => {}
+        }
+    }
+    plan.delete_remote.sort();
+    plan.delete_local.sort();
+    plan
+}
+
+fn delete_local_files(base_path: &Path, paths: &[String], snapshot: &mut SyncSnapshot) -> Result<(), AzimuthError> {
+    for path in paths {
+        let full_path = base_path.join(path);
+        if full_path.exists() {
+            snapshot.save(path)?;
+            fs::remove_file(&full_path)?;
+        }
+    }
+    Ok(())
+}
+
+// Local copies of files a sync is about to overwrite or delete, kept in
+// .azimuth_backups/<timestamp>/ so a bad sync can be undone
+const SNAPSHOTS_DIR: &str = ".azimuth_backups";
+const MAX_SNAPSHOTS: usize = 10;
+const SNAPSHOT_ID_FORMAT: &str = "%Y-%m-%dT%H-%M-%S%.3fZ";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SnapshotInfo {
+    pub id: String,
+    pub created_at: String,
+    pub file_count: usize,
+}
+
+struct SyncSnapshot {
+    base_path: PathBuf,
+    dir: PathBuf,
+    files_saved: usize,
+}
+
+impl SyncSnapshot {
+    // Nothing is written until the first file is saved
+    fn new(base_path: &Path) -> Self {
+        let id = chrono::Utc::now().format(SNAPSHOT_ID_FORMAT).to_string();
+        SyncSnapshot {
+            base_path: base_path.to_path_buf(),
+            dir: base_path.join(SNAPSHOTS_DIR).join(id),
+            files_saved: 0,
+        }
+    }
+
+    fn save(&mut self, relative: &str) -> Result<(), AzimuthError> {
+        let source = self.base_path.join(relative);
+        if !source.is_file() {
+            return Ok(());
+        }
+        let dest = self.dir.join(relative);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(&source, &dest).map_err(|e| AzimuthError::io_context(e, format!("Failed to back up {}", relative)))?;
+        self.files_saved += 1;
+        Ok(())
+    }
+
+    // Prunes to the newest MAX_SNAPSHOTS once the operation is done, so a restore
+    // never prunes the snapshot it is reading from
+    fn finish(self) -> Result<(), AzimuthError> {
+        if self.files_saved == 0 {
+            return Ok(());
+        }
+        let mut ids: Vec<String> = list_snapshots(self.base_path.to_string_lossy().to_string())?
+            .into_iter()
+            .map(|snapshot| snapshot.id)
+            .collect();
+        for id in ids.split_off(MAX_SNAPSHOTS.min(ids.len())) {
+            fs::remove_dir_all(self.base_path.join(SNAPSHOTS_DIR).join(id))?;
+        }
+        Ok(())
+    }
+}
+
+fn snapshot_files(dir: &Path) -> Vec<String> {
+    WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| Some(e.path().strip_prefix(dir).ok()?.to_string_lossy().to_string()))
+        .collect()
+}
+
+// Copies every file sync would upload into a new snapshot, returning its id
+#[tauri::command]
+fn snapshot_vault(base_path: String) -> Result<String, AzimuthError> {
+    let mut snapshot = SyncSnapshot::new(Path::new(&base_path));
+    for relative in local_sync_hashes(&base_path).keys() {
+        snapshot.save(relative)?;
+    }
+    let id = snapshot.dir.file_name().unwrap_or_default().to_string_lossy().to_string();
+    snapshot.finish()?;
+    Ok(id)
+}
+
+// Newest first
+#[tauri::command]
+fn list_snapshots(base_path: String) -> Result<Vec<SnapshotInfo>, AzimuthError> {
+    let snapshots_dir = PathBuf::from(&base_path).join(SNAPSHOTS_DIR);
+    if !snapshots_dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut snapshots = Vec::new();
+    for entry in fs::read_dir(&snapshots_dir)?.flatten() {
+        let id = entry.file_name().to_string_lossy().to_string();
+        let Ok(created_at) = chrono::NaiveDateTime::parse_from_str(&id, SNAPSHOT_ID_FORMAT) else {
+            continue;
+        };
+        snapshots.push(SnapshotInfo {
+            created_at: created_at.and_utc().to_rfc3339(),
+            file_count: snapshot_files(&entry.path()).len(),
+            id,
+        });
+    }
+    snapshots.sort_by(|a, b| b.id.cmp(&a.id));
+    Ok(snapshots)
+}
+
+// Puts a snapshot's files back, overwriting the vault's copies. Those are themselves
+// snapshotted first, so a restore can be undone too. Returns the number of files restored.
+#[tauri::command]
+fn restore_snapshot(base_path: String, snapshot_id: String) -> Result<usize, AzimuthError> {
+    let base = PathBuf::from(&base_path);
+    if chrono::NaiveDateTime::parse_from_str(&snapshot_id, SNAPSHOT_ID_FORMAT).is_err() {
+        return Err(AzimuthError::InvalidPath(format!("Invalid snapshot: {}", snapshot_id)));
+    }
+    let snapshot_dir = base.join(SNAPSHOTS_DIR).join(&snapshot_id);
+    if !snapshot_dir.is_dir() {
+        return Err(AzimuthError::NotFound(format!("Snapshot not found: {}", snapshot_id)));
+    }
+
+    let mut before_restore = SyncSnapshot::new(&base);
+    let files = snapshot_files(&snapshot_dir);
+    for relative in &files {
+        before_restore.save(relative)?;
+        let dest = base.join(relative);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(snapshot_dir.join(relative), &dest)?;
+    }
+    before_restore.finish()?;
+    Ok(files.len())
+}
+
+// Gitignore-style patterns in the vault root for paths no provider should sync,
+// in either direction, while leaving them visible locally
+const SYNC_IGNORE_FILE: &str = ".azimuthignore";
+
+fn load_sync_ignore(base_path: &Path) -> ignore::gitignore::Gitignore {
+    // A missing file matches nothing; invalid lines are skipped
+    ignore::gitignore::Gitignore::new(base_path.join(SYNC_IGNORE_FILE)).0
+}
+
+// `path` is relative to the vault, or absolute inside it. Snapshots, the trash and note
+// history never leave this device.
+fn is_sync_ignored(patterns: &ignore::gitignore::Gitignore, path: &Path, is_dir: bool) -> bool {
+    path.components().any(|c| [SNAPSHOTS_DIR, TRASH_DIR, HISTORY_DIR, THUMBNAILS_DIR].iter().any(|dir| c.as_os_str() == *dir))
+        || patterns.matched_path_or_any_parents(path, is_dir).is_ignore()
+}
+
+// Relative path -> SHA-256 of every file the cloud providers sync
+fn local_sync_hashes(notes_path: &str) -> HashMap<String, String> {
+    let base_path = Path::new(notes_path);
+    let archived = excluded_archive_paths(notes_path);
+    let ignored = load_sync_ignore(base_path);
+    WalkDir::new(base_path)
+        .into_iter()
+        .filter_entry(|e| {
+            // Hidden files and folders (.git, templates, app state) stay local
+            (e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+                && !is_archived(notes_path, e.path(), &archived)
+                && !is_sync_ignored(&ignored, e.path(), e.file_type().is_dir())
+        })
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_file())
+        .filter_map(|e| {
+            let relative = e.path().strip_prefix(base_path).ok()?.to_string_lossy().to_string();
+            get_file_hash(&e.path().to_path_buf()).ok().map(|hash| (relative, hash))
+        })
+        .collect()
+}
+
+// Each argument is a field of the frontend's invoke payload
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+async fn sync_to_s3(
+    bucket: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+    endpoint_url: Option<String>,
+    force_path_style: Option<bool>,
+    notes_path: String,
+    propagate_deletions: Option<bool>,
+    passphrase: Option<String>,
+    max_bytes_per_sec: Option<u64>,
+    sync: tauri::State<'_, SyncControl>,
+) -> Result<SyncStatus, AzimuthError> {
+    use aws_sdk_s3::primitives::ByteStream;
+
+    let cancel = sync.start();
+    let client = s3_client(region, &access_key, &secret_key, endpoint_url.as_deref(), force_path_style.unwrap_or(false));
+    let base_path = PathBuf::from(&notes_path);
+    let cipher = SyncCipher::for_sync(&base_path, passphrase)?;
+    
+    let mut files_uploaded = 0;
+    let mut files_downloaded = 0;
+    let mut files_deleted = 0;
+    let mut conflicts = Vec::new();
+    
+    let archived = excluded_archive_paths(&notes_path);
+    let ignored = load_sync_ignore(&base_path);
+    let mut sync_state = load_sync_state(&base_path);
+    let manifest = load_sync_manifest(&base_path, "s3");
+
+    // Get local files
+    let mut local_files: HashMap<String, (String, String)> = HashMap::new(); // path -> (hash, modified)
+    for entry in WalkDir::new(&base_path)
+        .into_iter()
+        .filter_entry(|e| {
+            (e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+                && !is_archived(&notes_path, e.path(), &archived)
+                && !is_sync_ignored(&ignored, e.path(), e.file_type().is_dir())
+        })
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_file())
+    {
+        let path = entry.path();
+        // Unresolved remote copies stay local
+        if path.extension().is_some_and(|ext| ext == "conflict") {
+            continue;
+        }
+        let relative = path.strip_prefix(&base_path).unwrap().to_string_lossy().to_string();
+        if let Ok(hash) = get_file_hash(&path.to_path_buf()) {
+            let modified = fs::metadata(path)
+                .and_then(|m| m.modified())
+                .map(system_time_to_rfc3339)
+                .unwrap_or_default();
+            local_files.insert(relative, (hash, modified));
+        }
+    }
+    
+    // List remote files
+    let mut remote_files: HashMap<String, (String, String)> = HashMap::new(); // path -> (etag, modified)
+    let mut remote_times = HashMap::new();
+    for obj in s3_list_objects(&client, &bucket).await? {
+        if let (Some(key), Some(etag)) = (obj.key, obj.e_tag) {
+            if let Some(modified) = obj.last_modified.and_then(|t| chrono::DateTime::from_timestamp(t.secs(), 0)) {
+                remote_times.insert(key.clone(), modified);
+            }
+            let modified = obj.last_modified.map(|t| t.to_string()).unwrap_or_default();
+            remote_files.insert(key, (etag.trim_matches('"').to_string(), modified));
+        }
+    }
+    remote_files.retain(|key, _| !is_sync_ignored(&ignored, Path::new(key), false));
+    remote_times.retain(|key, _| !is_sync_ignored(&ignored, Path::new(key), false));
+
+    let mut snapshot = SyncSnapshot::new(&base_path);
+    if propagate_deletions.unwrap_or(false) {
+        let local_hashes = local_files.iter().map(|(path, (hash, _))| (path.clone(), hash.clone())).collect();
+        let plan = plan_deletions(&manifest, &local_hashes, &remote_times);
+        for path in &plan.delete_remote {
+            client.delete_object()
+                .bucket(&bucket)
+                .key(path)
+                .send()
+                .await?;
+            remote_files.remove(path);
+            sync_state.remove(path);
+        }
+        delete_local_files(&base_path, &plan.delete_local, &mut snapshot)?;
+        for path in &plan.delete_local {
+            local_files.remove(path);
+            sync_state.remove(path);
+        }
+        files_deleted = plan.delete_remote.len() + plan.delete_local.len();
+    }
+    
+    // Upload local changes, pull remote changes, and flag files changed on both sides
+    let mut cancelled = false;
+    for (path, (local_hash, local_modified)) in &local_files {
+        if cancel.load(Ordering::SeqCst) {
+            cancelled = true;
+            break;
+        }
+        let full_path = base_path.join(path);
+        let remote = remote_files.get(path);
+
+        // When the object is new or changed since the last sync, the checksum stored
+        // with it tells whether the two copies already match
+        let remote_changed = match (remote, sync_state.get(path)) {
+            (Some((remote_etag, _)), Some(state)) => &state.remote_etag != remote_etag,
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+        let remote_sha = match remote {
+            Some(_) if remote_changed => client.head_object()
+                .bucket(&bucket)
+                .key(path)
+                .send()
+                .await?
+                .metadata
+                .and_then(|metadata| metadata.get(S3_SHA256_METADATA).cloned()),
+            _ => None,
+        };
+        // plan_s3_sync compares plain hashes, so a matching keyed checksum stands in for one
+        let remote_sha = remote_sha.map(|stored| {
+            if s3_checksum_matches(cipher.as_ref(), &stored, local_hash) {
+                local_hash.clone()
+            } else {
+                stored
+            }
+        });
+
+        let action = plan_s3_sync(
+            local_hash,
+            sync_state.get(path),
+            remote.map(|(etag, _)| etag.as_str()),
+            remote_sha.as_deref(),
+        );
+        match action {
+            S3SyncAction::InSync => {
+                if let Some((remote_etag, _)) = remote
+                    && remote_changed
+                {
+                    sync_state.insert(path.clone(), synced_state(local_hash.clone(), Some(remote_etag.clone())));
+                }
+            }
+            S3SyncAction::Conflict => {
+                let (remote_etag, remote_modified) = remote.ok_or("Conflict without a remote copy")?;
+                s3_download(&client, &bucket, path, &conflict_path(&full_path), cipher.as_ref(), max_bytes_per_sec).await?;
+                // An unknown local hash keeps the local copy counted as changed until resolved
+                let state = sync_state.entry(path.clone()).or_default();
+                state.conflict_etag = Some(remote_etag.clone());
+                conflicts.push(SyncConflict {
+                    file_path: path.clone(),
+                    local_modified: local_modified.clone(),
+                    remote_modified: remote_modified.clone(),
+                    local_hash: local_hash.clone(),
+                    remote_hash: remote_sha.unwrap_or_else(|| remote_etag.clone()),
+                });
+            }
+            S3SyncAction::Download => {
+                snapshot.save(path)?;
+                let etag = s3_download(&client, &bucket, path, &full_path, cipher.as_ref(), max_bytes_per_sec).await?;
+                let hash = get_file_hash(&full_path)?;
+                sync_state.insert(path.clone(), synced_state(hash, etag));
+                files_downloaded += 1;
+            }
+            S3SyncAction::Upload => {
+                let content = fs::read(&full_path)?;
+                let content = encrypt_for_upload(cipher.as_ref(), content)?;
+                let (throttle, size) = (max_bytes_per_sec.map(Throttle::new), content.len());
+
+                let output = client.put_object()
+                    .bucket(&bucket)
+                    .key(path)
+                    .metadata(S3_SHA256_METADATA, s3_checksum(cipher.as_ref(), local_hash)?)
+                    .body(ByteStream::from(content))
+                    .send()
+                    .await?;
+                // The SDK reads the body itself, so the cap is kept by pausing after each upload
+                if let Some(mut throttle) = throttle {
+                    tokio::time::sleep(throttle.delay(size)).await;
+                }
+
+                sync_state.insert(path.clone(), synced_state(local_hash.clone(), output.e_tag));
+                files_uploaded += 1;
+            }
+        }
+    }
+    
+    // Download new remote files
+    for path in remote_files.keys() {
+        if cancelled || cancel.load(Ordering::SeqCst) {
+            cancelled = true;
+            break;
+        }
+        // Keys naming a path outside the vault are left on the server
+        let Ok(full_path) = safe_join_relative(&base_path, path) else {
+            continue;
+        };
+        if !local_files.contains_key(path) && !is_archived(&notes_path, &full_path, &archived) {
+            let etag = s3_download(&client, &bucket, path, &full_path, cipher.as_ref(), max_bytes_per_sec).await?;
+            let hash = get_file_hash(&full_path)?;
+            sync_state.insert(path.clone(), synced_state(hash, etag));
+            files_downloaded += 1;
+        }
+    }
+
+    // Saved even when cancelled, so finished transfers aren't repeated next time
+    save_sync_state(&base_path, &sync_state)?;
+    snapshot.finish()?;
+    save_sync_manifest(&base_path, "s3", sync_state.keys())?;
+    if cancelled {
+        return Ok(cancelled_sync(files_uploaded, files_downloaded, conflicts));
+    }
+    
+    let mut message = format!("Sync complete: {} uploaded, {} downloaded", files_uploaded, files_downloaded);
+    if files_deleted > 0 {
+        message.push_str(&format!(", {} deleted", files_deleted));
+    }
+    if !conflicts.is_empty() {
+        message.push_str(&format!(", {} conflicts", conflicts.len()));
+    }
+    finish_sync(&notes_path, SyncStatus {
+        success: true,
+        message,
+        files_uploaded,
+        files_downloaded,
+        conflicts,
+    })
+}
+
+// Dropbox's content_hash: the SHA-256 of the concatenated SHA-256s of each 4 MiB block
+// (https://www.dropbox.com/developers/reference/content-hash)
+const DROPBOX_BLOCK_SIZE: usize = 4 * 1024 * 1024;
+
+fn dropbox_content_hash(content: &[u8]) -> String {
+    let mut overall = Sha256::new();
+    for block in content.chunks(DROPBOX_BLOCK_SIZE) {
+        overall.update(Sha256::digest(block));
+    }
+    hex::encode(overall.finalize())
+}
+
+// Applies list_folder entries to the cached remote listing, returning the paths they touch
+fn apply_dropbox_entries(remote: &mut HashMap<String, RemoteFile>, entries: &[serde_json::Value]) -> Vec<String> {
+    let mut changed = Vec::new();
+    for entry in entries {
+        let Some(relative) = entry["path_display"].as_str().and_then(|p| p.strip_prefix("/Azimuth/")) else {
+            continue;
+        };
+        match entry[".tag"].as_str() {
+            Some("file") => {
+                remote.insert(relative.to_string(), RemoteFile {
+                    hash: entry["content_hash"].as_str().unwrap_or_default().to_string(),
+                    modified: entry["server_modified"].as_str().unwrap_or_default().to_string(),
+                });
+            }
+            // A deleted folder takes everything under it
+            Some("deleted") => {
+                let prefix = format!("{}/", relative);
+                remote.retain(|path, _| path != relative && !path.starts_with(&prefix));
+            }
+            _ => continue,
+        }
+        changed.push(relative.to_string());
+    }
+    changed
+}
+
+// Brings the cached remote listing up to date, from `cursor` when there is one or with
+// a full listing otherwise. Returns the new cursor and the paths that changed.
+async fn fetch_dropbox_changes(
+    client: &reqwest::Client,
+    access_token: &str,
+    mut cursor: Option<String>,
+    remote: &mut HashMap<String, RemoteFile>,
+) -> Result<(Option<String>, HashSet<String>), AzimuthError> {
+    let mut changed = HashSet::new();
+    if cursor.is_none() {
+        remote.clear();
+    }
+    loop {
+        let request = match &cursor {
+            Some(cursor) => client.post("https://api.dropboxapi.com/2/files/list_folder/continue")
+                .json(&serde_json::json!({ "cursor": cursor })),
+            None => client.post("https://api.dropboxapi.com/2/files/list_folder")
+                .json(&serde_json::json!({ "path": "/Azimuth", "recursive": true })),
+        };
+        let response = send_with_retry(request.header("Authorization", format!("Bearer {}", access_token))).await?;
+
+        if response.status().as_u16() == 409 {
+            let body = response.text().await.unwrap_or_default();
+            // An expired cursor means starting over with a full listing
+            if cursor.is_some() && body.contains("reset") {
+                cursor = None;
+                remote.clear();
+                continue;
+            }
+            // The Azimuth folder doesn't exist until the first upload
+            if body.contains("not_found") {
+                return Ok((None, changed));
+            }
+            return Err(AzimuthError::Network(format!("Failed to list Dropbox files: {}", body)));
+        }
+        if !response.status().is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(AzimuthError::Network(format!("Failed to list Dropbox files: {}", body)));
+        }
+
+        let data: serde_json::Value = response.json().await?;
+        let entries = data["entries"].as_array().map(|e| e.as_slice()).unwrap_or_default();
+        changed.extend(apply_dropbox_entries(remote, entries));
+        cursor = data["cursor"].as_str().map(|c| c.to_string());
+        if !data["has_more"].as_bool().unwrap_or(false) {
+            return Ok((cursor, changed));
+        }
+    }
+}
+
+#[tauri::command]
+async fn sync_to_dropbox(
+    access_token: String,
+    notes_path: String,
+    propagate_deletions: Option<bool>,
+    passphrase: Option<String>,
+    max_bytes_per_sec: Option<u64>,
+    sync: tauri::State<'_, SyncControl>,
+) -> Result<SyncStatus, AzimuthError> {
+    let cancel = sync.start();
+    let client = reqwest::Client::new();
+    let base_path = PathBuf::from(&notes_path);
+    let cipher = SyncCipher::for_sync(&base_path, passphrase)?;
+    let ignored = load_sync_ignore(&base_path);
+
+    let mut files_uploaded = 0;
+    let mut files_downloaded = 0;
+    let mut files_deleted = 0;
+    let conflicts = Vec::new();
+    let mut local_files = local_sync_hashes(&notes_path);
+
+    // Only what changed remotely since the last sync is listed
+    let manifest = load_sync_manifest(&base_path, "dropbox");
+    let mut remote = manifest.remote.clone();
+    let (cursor, remote_changed) = fetch_dropbox_changes(&client, &access_token, manifest.cursor.clone(), &mut remote).await?;
+    remote.retain(|path, _| !is_sync_ignored(&ignored, Path::new(path), false));
+
+    if propagate_deletions.unwrap_or(false) {
+        let remote_times = remote.iter()
+            .filter_map(|(path, file)| {
+                let modified = chrono::DateTime::parse_from_rfc3339(&file.modified).ok()?;
+                Some((path.clone(), modified.with_timezone(&chrono::Utc)))
+            })
+            .collect();
+        let plan = plan_deletions(&manifest, &local_files, &remote_times);
+        for path in &plan.delete_remote {
+            let response = client.post("https://api.dropboxapi.com/2/files/delete_v2")
+                .header("Authorization", format!("Bearer {}", access_token))
+                .header("Content-Type", "application/json")
+                .json(&serde_json::json!({ "path": format!("/Azimuth/{}", path) }))
+                .send()
+                .await?;
+            if response.status().is_success() {
+                remote.remove(path);
+                files_deleted += 1;
+            }
+        }
+        let mut snapshot = SyncSnapshot::new(&base_path);
+        delete_local_files(&base_path, &plan.delete_local, &mut snapshot)?;
+        snapshot.finish()?;
+        for path in &plan.delete_local {
+            local_files.remove(path);
+        }
+        files_deleted += plan.delete_local.len();
+    }
+
+    // Upload local files that differ from Dropbox's copy, a few at a time. A file that
+    // still fails after retries is reported rather than stopping the sync.
+    let mut failures = Vec::new();
+    let permits = Arc::new(tokio::sync::Semaphore::new(SYNC_UPLOAD_CONCURRENCY));
+    let mut uploads = tokio::task::JoinSet::new();
+    for (relative, local_hash) in &local_files {
+        if cancel.load(Ordering::SeqCst) {
+            break;
+        }
+        let content = match fs::read(base_path.join(relative)) {
+            Ok(content) => content,
+            Err(e) => {
+                failures.push(format!("{}: {}", relative, e));
+                continue;
+            }
+        };
+        let unchanged = match (remote.get(relative), &cipher) {
+            (Some(file), None) => file.hash == dropbox_content_hash(&content),
+            // Encrypted copies never hash the same, so rely on neither side having changed
+            (Some(_), Some(_)) => {
+                manifest.files.get(relative) == Some(local_hash) && !remote_changed.contains(relative)
+            }
+            (None, _) => false,
+        };
+        if unchanged {
+            continue;
+        }
+
+        let permit = permits.clone().acquire_owned().await.map_err(|e| AzimuthError::Other(e.to_string()))?;
+        let content = encrypt_for_upload(cipher.as_ref(), content)?;
+        let request = client.post("https://content.dropboxapi.com/2/files/upload")
+            .header("Authorization", format!("Bearer {}", access_token))
+            .header("Dropbox-API-Arg", serde_json::json!({
+                "path": format!("/Azimuth/{}", relative),
+                "mode": "overwrite",
+                "autorename": false,
+                "mute": true
+            }).to_string())
+            .header("Content-Type", "application/octet-stream")
+            .body(throttled_body(content, max_bytes_per_sec));
+        let relative = relative.clone();
+        uploads.spawn(async move {
+            let result = send_with_retry(request).await;
+            drop(permit);
+            (relative, result)
+        });
+    }
+
+    // Uploads already under way are allowed to finish, even when cancelled
+    while let Some(joined) = uploads.join_next().await {
+        match joined {
+            Ok((relative, Ok(response))) if response.status().is_success() => {
+                files_uploaded += 1;
+                let metadata: serde_json::Value = response.json().await.unwrap_or_default();
+                apply_dropbox_entries(&mut remote, &[serde_json::json!({
+                    ".tag": "file",
+                    "path_display": format!("/Azimuth/{}", relative),
+                    "content_hash": metadata["content_hash"],
+                    "server_modified": metadata["server_modified"],
+                })]);
+            }
+            Ok((relative, Ok(response))) => failures.push(format!("{}: {}", relative, response.status())),
+            Ok((relative, Err(e))) => failures.push(format!("{}: {}", relative, e)),
+            Err(e) => failures.push(e.to_string()),
+        }
+    }
+    if cancel.load(Ordering::SeqCst) {
+        return Ok(cancelled_sync(files_uploaded, files_downloaded, conflicts));
+    }
+
+    // Download remote files missing locally
+    for relative in remote.keys() {
+        if cancel.load(Ordering::SeqCst) {
+            return Ok(cancelled_sync(files_uploaded, files_downloaded, conflicts));
+        }
+        let local_path = base_path.join(relative);
+        if local_path.exists() {
+            continue;
+        }
+
+        let request = client.post("https://content.dropboxapi.com/2/files/download")
+            .header("Authorization", format!("Bearer {}", access_token))
+            .header("Dropbox-API-Arg", serde_json::json!({
+                "path": format!("/Azimuth/{}", relative)
+            }).to_string());
+        let download_response = match send_with_retry(request).await {
+            Ok(response) => response,
+            Err(e) => {
+                failures.push(format!("{}: {}", relative, e));
+                continue;
+            }
+        };
+        if !download_response.status().is_success() {
+            failures.push(format!("{}: {}", relative, download_response.status()));
+            continue;
+        }
+
+        let content = read_throttled(download_response, max_bytes_per_sec).await?;
+        let content = decrypt_download(cipher.as_ref(), relative, &content)?;
+        if let Some(parent) = local_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&local_path, content)?;
+        files_downloaded += 1;
+    }
+
+    // Skip past this sync's own uploads, so the next one doesn't see them as remote changes
+    let (cursor, _) = fetch_dropbox_changes(&client, &access_token, cursor, &mut remote).await?;
+    remote.retain(|path, _| !is_sync_ignored(&ignored, Path::new(path), false));
+
+    let mut manifest = new_sync_manifest(&base_path, remote.keys());
+    manifest.cursor = cursor;
+    manifest.remote = remote;
+    write_sync_manifest(&base_path, "dropbox", &manifest)?;
+
+    let mut message = format!("Dropbox sync complete: {} uploaded, {} downloaded", files_uploaded, files_downloaded);
+    if files_deleted > 0 {
+        message.push_str(&format!(", {} deleted", files_deleted));
+    }
+    append_sync_failures(&mut message, &failures);
+    finish_sync(&notes_path, SyncStatus {
+        success: failures.is_empty(),
+        message,
+        files_uploaded,
+        files_downloaded,
+        conflicts,
+    })
+}
+
+// Every file under the remote Azimuth folder, keyed by path relative to it. Each
+// folder is listed page by page through @odata.nextLink. None means the folder
+// doesn't exist yet.
+async fn onedrive_list_files(
+    client: &reqwest::Client,
+    access_token: &str,
+) -> Result<Option<HashMap<String, serde_json::Value>>, AzimuthError> {
+    let mut files = HashMap::new();
+    let mut pending = vec![(
+        String::new(),
+        "https://graph.microsoft.com/v1.0/drive/root:/Azimuth:/children".to_string(),
+    )];
+    while let Some((dir, first_page)) = pending.pop() {
+        let mut next_page = Some(first_page);
+        while let Some(url) = next_page {
+            let request = client.get(&url).header("Authorization", format!("Bearer {}", access_token));
+            let response = send_with_retry(request).await?;
+            if response.status() == reqwest::StatusCode::NOT_FOUND && dir.is_empty() && files.is_empty() {
+                return Ok(None);
+            }
+            if !response.status().is_success() {
+                let body = response.text().await.unwrap_or_default();
+                return Err(AzimuthError::Network(format!("Failed to list OneDrive files: {}", body)));
+            }
+
+            let data: serde_json::Value = response.json().await?;
+            for item in data["value"].as_array().into_iter().flatten() {
+                let Some(name) = item["name"].as_str() else {
+                    continue;
+                };
+                let path = if dir.is_empty() { name.to_string() } else { format!("{}/{}", dir, name) };
+                if item["folder"].is_object() {
+                    if let Some(id) = item["id"].as_str() {
+                        pending.push((path, format!("https://graph.microsoft.com/v1.0/drive/items/{}/children", id)));
+                    }
+                } else if item["file"].is_object() {
+                    files.insert(path, item.clone());
+                }
+            }
+            next_page = data["@odata.nextLink"].as_str().map(|link| link.to_string());
+        }
+    }
+    Ok(Some(files))
+}
+
+fn onedrive_content_url(relative: &str) -> String {
+    let encoded: Vec<String> = relative
+        .split('/')
+        .map(|segment| urlencoding::encode(segment).into_owned())
+        .collect();
+    format!("https://graph.microsoft.com/v1.0/drive/root:/Azimuth/{}", encoded.join("/"))
+}
+
+#[tauri::command]
+async fn sync_to_onedrive(
+    access_token: String,
+    notes_path: String,
+    propagate_deletions: Option<bool>,
+    passphrase: Option<String>,
+    max_bytes_per_sec: Option<u64>,
+    sync: tauri::State<'_, SyncControl>,
+) -> Result<SyncStatus, AzimuthError> {
+    let cancel = sync.start();
+    let client = reqwest::Client::new();
+    let base_path = PathBuf::from(&notes_path);
+    let cipher = SyncCipher::for_sync(&base_path, passphrase)?;
+    let ignored = load_sync_ignore(&base_path);
+    
+    let mut files_uploaded = 0;
+    let mut files_downloaded = 0;
+    let mut files_deleted = 0;
+    let conflicts = Vec::new();
+    let mut failures = Vec::new();
+    let mut local_files = local_sync_hashes(&notes_path);
+
+    // The whole tree is listed before anything is deleted, and a listing that fails
+    // stops the sync
+    let mut remote = onedrive_list_files(&client, &access_token).await?.unwrap_or_default();
+    remote.retain(|path, _| {
+        !is_sync_ignored(&ignored, Path::new(path), false)
+            && !path.split('/').any(|segment| segment.starts_with('.'))
+    });
+
+    if propagate_deletions.unwrap_or(false) {
+        let remote_times = remote.iter()
+            .filter_map(|(path, item)| Some((path.clone(), parse_remote_time(&item["lastModifiedDateTime"])?)))
+            .collect();
+        let plan = plan_deletions(&load_sync_manifest(&base_path, "onedrive"), &local_files, &remote_times);
+        for path in &plan.delete_remote {
+            let request = client.delete(onedrive_content_url(path))
+                .header("Authorization", format!("Bearer {}", access_token));
+            match send_with_retry(request).await {
+                Ok(response) if response.status().is_success() => {
+                    remote.remove(path);
+                    files_deleted += 1;
+                }
+                Ok(response) => failures.push(format!("{}: {}", path, response.status())),
+                Err(e) => failures.push(format!("{}: {}", path, e)),
+            }
+        }
+        let mut snapshot = SyncSnapshot::new(&base_path);
+        delete_local_files(&base_path, &plan.delete_local, &mut snapshot)?;
+        snapshot.finish()?;
+        for path in &plan.delete_local {
+            local_files.remove(path);
+        }
+        files_deleted += plan.delete_local.len();
+    }
+    
+    // Upload local files
+    let mut synced: HashSet<String> = HashSet::new();
+    for relative in local_files.keys() {
+        if cancel.load(Ordering::SeqCst) {
+            return Ok(cancelled_sync(files_uploaded, files_downloaded, conflicts));
+        }
+        let content = fs::read(base_path.join(relative))?;
+        let content = encrypt_for_upload(cipher.as_ref(), content)?;
+        
+        let request = client.put(format!("{}:/content", onedrive_content_url(relative)))
+            .header("Authorization", format!("Bearer {}", access_token))
+            .header("Content-Type", "application/octet-stream")
+            .body(throttled_body(content, max_bytes_per_sec));
+        match send_with_retry(request).await {
+            Ok(response) if response.status().is_success() => {
+                synced.insert(relative.clone());
+                files_uploaded += 1;
+            }
+            Ok(response) => failures.push(format!("{}: {}", relative, response.status())),
+            Err(e) => failures.push(format!("{}: {}", relative, e)),
+        }
+    }
+    
+    // Download remote files missing locally
+    for (relative, item) in &remote {
+        if cancel.load(Ordering::SeqCst) {
+            return Ok(cancelled_sync(files_uploaded, files_downloaded, conflicts));
+        }
+        synced.insert(relative.clone());
+        let local_path = base_path.join(relative);
+        if local_path.exists() {
+            continue;
+        }
+        let Some(download_url) = item["@microsoft.graph.downloadUrl"].as_str() else {
+            continue;
+        };
+        let download_response = match send_with_retry(client.get(download_url)).await {
+            Ok(response) if response.status().is_success() => response,
+            Ok(response) => {
+                failures.push(format!("{}: {}", relative, response.status()));
+                continue;
+            }
+            Err(e) => {
+                failures.push(format!("{}: {}", relative, e));
+                continue;
+            }
+        };
+        let content = read_throttled(download_response, max_bytes_per_sec).await?;
+        let content = decrypt_download(cipher.as_ref(), relative, &content)?;
+        if let Some(parent) = local_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&local_path, content)?;
+        files_downloaded += 1;
+    }
+    
+    save_sync_manifest(&base_path, "onedrive", &synced)?;
+
+    let mut message = format!("OneDrive sync complete: {} uploaded, {} downloaded", files_uploaded, files_downloaded);
+    if files_deleted > 0 {
+        message.push_str(&format!(", {} deleted", files_deleted));
+    }
+    append_sync_failures(&mut message, &failures);
+    finish_sync(&notes_path, SyncStatus {
+        success: failures.is_empty(),
+        message,
+        files_uploaded,
+        files_downloaded,
+        conflicts,
+    })
+}
+
+// Google Drive resumable uploads are sent in chunks, which must be multiples of 256 KiB
+const GDRIVE_CHUNK_SIZE: usize = 32 * 256 * 1024;
+const GDRIVE_FOLDER_MIME: &str = "application/vnd.google-apps.folder";
+
+#[derive(Debug, Clone)]
+struct GdriveItem {
+    id: String,
+    mime: String,
+    modified: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+// Name → item, for everything directly inside a Drive folder
+type GdriveChildren = HashMap<String, GdriveItem>;
+
+async fn gdrive_list_children(
+    client: &reqwest::Client,
+    access_token: &str,
+    folder_id: &str,
+) -> Result<GdriveChildren, AzimuthError> {
+    let mut children = HashMap::new();
+    let mut page_token: Option<String> = None;
+    loop {
+        let mut query = vec![
+            ("q", format!("'{}' in parents and trashed=false", folder_id)),
+            ("fields", "nextPageToken,files(id,name,mimeType,modifiedTime)".to_string()),
+            ("pageSize", "1000".to_string()),
+        ];
+        if let Some(token) = &page_token {
+            query.push(("pageToken", token.clone()));
+        }
+        let request = client.get("https://www.googleapis.com/drive/v3/files")
+            .header("Authorization", format!("Bearer {}", access_token))
+            .query(&query);
+        let response = send_with_retry(request).await?;
+        if !response.status().is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(AzimuthError::Network(format!("Failed to list Google Drive folder: {}", body)));
+        }
+
+        let data: serde_json::Value = response.json().await?;
+        for file in data["files"].as_array().into_iter().flatten() {
+            if let (Some(name), Some(id)) = (file["name"].as_str(), file["id"].as_str()) {
+                children.insert(name.to_string(), GdriveItem {
+                    id: id.to_string(),
+                    mime: file["mimeType"].as_str().unwrap_or_default().to_string(),
+                    modified: parse_remote_time(&file["modifiedTime"]),
+                });
+            }
+        }
+        page_token = data["nextPageToken"].as_str().map(|t| t.to_string());
+        if page_token.is_none() {
+            return Ok(children);
+        }
+    }
+}
+
+async fn gdrive_create_folder(
+    client: &reqwest::Client,
+    access_token: &str,
+    name: &str,
+    parent_id: Option<&str>,
+) -> Result<String, AzimuthError> {
+    let mut metadata = serde_json::json!({ "name": name, "mimeType": GDRIVE_FOLDER_MIME });
+    if let Some(parent_id) = parent_id {
+        metadata["parents"] = serde_json::json!([parent_id]);
+    }
+    let request = client.post("https://www.googleapis.com/drive/v3/files")
+        .header("Authorization", format!("Bearer {}", access_token))
+        .json(&metadata);
+    let response = send_with_retry(request).await?;
+    if !response.status().is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(AzimuthError::Network(format!("Failed to create Google Drive folder {}: {}", name, body)));
+    }
+    let data: serde_json::Value = response.json().await?;
+    data["id"].as_str().map(|id| id.to_string()).ok_or_else(|| AzimuthError::Network("Google Drive returned no folder id".to_string()))
+}
+
+// Drive allows names that aren't valid, or safe, as a single path component
+fn gdrive_safe_name(name: &str) -> bool {
+    !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\'])
+}
+
+// Where an interrupted resumable upload should carry on from. Drive answers 308 with
+// `Range: bytes=0-N` once it holds N + 1 bytes, and without a Range header when it holds none.
+fn gdrive_resume_offset(response: &reqwest::Response) -> usize {
+    response.headers()
+        .get(reqwest::header::RANGE)
+        .and_then(|range| range.to_str().ok())
+        .and_then(|range| range.rsplit('-').next())
+        .and_then(|end| end.parse::<usize>().ok())
+        .map(|end| end + 1)
+        .unwrap_or(0)
+}
+
+// Uploads `content` through a resumable session, creating the file in `parent_id` or
+// replacing the contents of `existing_id`
+async fn gdrive_upload(
+    client: &reqwest::Client,
+    access_token: &str,
+    name: &str,
+    parent_id: &str,
+    existing_id: Option<&str>,
+    content: &[u8],
+    max_bytes_per_sec: Option<u64>,
+) -> Result<(), AzimuthError> {
+    let total = content.len();
+    let start_request = match existing_id {
+        Some(id) => client.patch(format!("https://www.googleapis.com/upload/drive/v3/files/{}?uploadType=resumable", id))
+            .json(&serde_json::json!({})),
+        None => client.post("https://www.googleapis.com/upload/drive/v3/files?uploadType=resumable")
+            .json(&serde_json::json!({ "name": name, "parents": [parent_id] })),
+    };
+    let response = send_with_retry(start_request
+        .header("Authorization", format!("Bearer {}", access_token))
+        .header("X-Upload-Content-Type", "application/octet-stream")
+        .header("X-Upload-Content-Length", total.to_string()))
+        .await?;
+    if !response.status().is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(AzimuthError::Network(format!("Failed to start upload: {}", body)));
+    }
+    let session_url = response.headers()
+        .get(reqwest::header::LOCATION)
+        .and_then(|location| location.to_str().ok())
+        .ok_or("Google Drive returned no upload session")?
+        .to_string();
+
+    let mut offset = 0;
+    let mut failed_attempts = 0;
+    loop {
+        let end = (offset + GDRIVE_CHUNK_SIZE).min(total);
+        let content_range = if total == 0 {
+            "bytes */0".to_string()
+        } else {
+            format!("bytes {}-{}/{}", offset, end - 1, total)
+        };
+        let result = client.put(&session_url)
+            .header(reqwest::header::CONTENT_RANGE, content_range)
+            .body(throttled_body(content[offset..end].to_vec(), max_bytes_per_sec))
+            .send()
+            .await;
+
+        let response = match result {
+            Ok(response) if response.status().is_success() => return Ok(()),
+            Ok(response) if response.status().as_u16() == 308 => {
+                offset = gdrive_resume_offset(&response);
+                failed_attempts = 0;
+                continue;
+            }
+            // 404 means the session expired, which only a fresh upload can fix
+            Ok(response) if response.status() == reqwest::StatusCode::NOT_FOUND => {
+                return Err(AzimuthError::Network("upload session expired".to_string()));
+            }
+            Ok(response) if !response.status().is_server_error() => {
+                let body = response.text().await.unwrap_or_default();
+                return Err(AzimuthError::Network(format!("upload failed: {}", body)));
+            }
+            other => other,
+        };
+
+        // The chunk may have partly arrived: ask Drive how much it holds before resending
+        failed_attempts += 1;
+        if failed_attempts == SYNC_RETRY_ATTEMPTS {
+            return Err(match response {
+                Ok(response) => AzimuthError::Network(format!("upload failed: {}", response.status())),
+                Err(e) => e.into(),
+            });
+        }
+        let status_request = client.put(&session_url)
+            .header(reqwest::header::CONTENT_RANGE, format!("bytes */{}", total));
+        let status = send_with_retry(status_request).await?;
+        if status.status().is_success() {
+            return Ok(());
+        }
+        if status.status().as_u16() != 308 {
+            return Err(AzimuthError::Network(format!("upload failed: {}", status.status())));
+        }
+        offset = gdrive_resume_offset(&status);
+    }
+}
+
+#[tauri::command]
+async fn sync_to_google_drive(
+    access_token: String,
+    notes_path: String,
+    propagate_deletions: Option<bool>,
+    passphrase: Option<String>,
+    max_bytes_per_sec: Option<u64>,
+    sync: tauri::State<'_, SyncControl>,
+) -> Result<SyncStatus, AzimuthError> {
+    let cancel = sync.start();
+    let client = reqwest::Client::new();
+    let base_path = PathBuf::from(&notes_path);
+    let cipher = SyncCipher::for_sync(&base_path, passphrase)?;
+    
+    let mut files_uploaded = 0;
+    let mut files_downloaded = 0;
+    let mut files_deleted = 0;
+    let conflicts = Vec::new();
+    
+    // Find or create Azimuth folder
+    let search_response = client.get("https://www.googleapis.com/drive/v3/files")
+        .header("Authorization", format!("Bearer {}", access_token))
+        .query(&[
+            ("q", "name='Azimuth' and mimeType='application/vnd.google-apps.folder' and trashed=false"),
+            ("fields", "files(id,name)")
+        ])
+        .send()
+        .await?;
+    
+    let search_data: serde_json::Value = search_response.json().await?;
+    
+    let folder_id = if let Some(files) = search_data["files"].as_array() {
+        if let Some(folder) = files.first() {
+            folder["id"].as_str().unwrap_or("").to_string()
+        } else {
+            gdrive_create_folder(&client, &access_token, "Azimuth", None).await?
+        }
+    } else {
+        return Err(AzimuthError::Network("Failed to search for folder".to_string()));
+    };
+    
+    // List the whole tree under the Azimuth folder, skipping ignored folders
+    let ignored = load_sync_ignore(&base_path);
+    let mut folders: HashMap<PathBuf, (String, GdriveChildren)> = HashMap::new();
+    let mut pending = vec![(PathBuf::new(), folder_id)];
+    while let Some((dir, id)) = pending.pop() {
+        let children = gdrive_list_children(&client, &access_token, &id).await?;
+        for (name, item) in &children {
+            let child = dir.join(name);
+            if item.mime == GDRIVE_FOLDER_MIME
+                && gdrive_safe_name(name)
+                && !name.starts_with('.')
+                && !is_sync_ignored(&ignored, &child, true)
+            {
+                pending.push((child, item.id.clone()));
+            }
+        }
+        folders.insert(dir, (id, children));
+    }
+
+    // Remote files under the same rules as upload
+    let mut failures = Vec::new();
+    let mut remote_files: Vec<(PathBuf, GdriveItem)> = folders.iter()
+        .flat_map(|(dir, (_, children))| {
+            children.iter()
+                .filter(|(name, item)| {
+                    // Google Docs have no file contents to download
+                    !item.mime.starts_with("application/vnd.google-apps.") && gdrive_safe_name(name) && !name.starts_with('.')
+                })
+                .map(move |(name, item)| (dir.join(name), item.clone()))
+        })
+        .filter(|(relative, _)| !is_sync_ignored(&ignored, relative, false))
+        .collect();
+    remote_files.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut local_files = local_sync_hashes(&notes_path);
+    if propagate_deletions.unwrap_or(false) {
+        let remote_times = remote_files.iter()
+            .filter_map(|(relative, item)| Some((relative.to_string_lossy().to_string(), item.modified?)))
+            .collect();
+        let plan = plan_deletions(&load_sync_manifest(&base_path, "googledrive"), &local_files, &remote_times);
+        // Drive's trash keeps remote deletions recoverable
+        let mut deleted = HashSet::new();
+        for (relative, item) in &remote_files {
+            let path = relative.to_string_lossy().to_string();
+            if !plan.delete_remote.contains(&path) {
+                continue;
+            }
+            let request = client.patch(format!("https://www.googleapis.com/drive/v3/files/{}", item.id))
+                .header("Authorization", format!("Bearer {}", access_token))
+                .json(&serde_json::json!({ "trashed": true }));
+            match send_with_retry(request).await {
+                Ok(response) if response.status().is_success() => {
+                    deleted.insert(relative.clone());
+                    files_deleted += 1;
+                }
+                Ok(response) => failures.push(format!("{}: {}", path, response.status())),
+                Err(e) => failures.push(format!("{}: {}", path, e)),
+            }
+        }
+        remote_files.retain(|(relative, _)| !deleted.contains(relative));
+        let mut snapshot = SyncSnapshot::new(&base_path);
+        delete_local_files(&base_path, &plan.delete_local, &mut snapshot)?;
+        snapshot.finish()?;
+        for path in &plan.delete_local {
+            local_files.remove(path);
+        }
+        files_deleted += plan.delete_local.len();
+    }
+
+    // Download remote files missing locally
+    let mut downloaded = HashSet::new();
+    let mut synced: HashSet<String> = remote_files.iter().map(|(relative, _)| relative.to_string_lossy().to_string()).collect();
+    for (relative, item) in &remote_files {
+        if cancel.load(Ordering::SeqCst) {
+            return Ok(cancelled_sync(files_uploaded, files_downloaded, conflicts));
+        }
+        let local_path = base_path.join(relative);
+        if local_path.exists() {
+            continue;
+        }
+
+        let request = client.get(format!("https://www.googleapis.com/drive/v3/files/{}", item.id))
+            .header("Authorization", format!("Bearer {}", access_token))
+            .query(&[("alt", "media")]);
+        let response = match send_with_retry(request).await {
+            Ok(response) if response.status().is_success() => response,
+            Ok(response) => {
+                failures.push(format!("{}: {}", relative.display(), response.status()));
+                continue;
+            }
+            Err(e) => {
+                failures.push(format!("{}: {}", relative.display(), e));
+                continue;
+            }
+        };
+        let content = read_throttled(response, max_bytes_per_sec).await?;
+        let content = decrypt_download(cipher.as_ref(), &relative.to_string_lossy(), &content)?;
+        if let Some(parent) = local_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&local_path, content)?;
+        downloaded.insert(relative.clone());
+        files_downloaded += 1;
+    }
+
+    // Upload local files, creating any folders Drive doesn't have yet
+    let mut local_files: Vec<String> = local_files.into_keys().collect();
+    local_files.sort();
+    for relative in &local_files {
+        if cancel.load(Ordering::SeqCst) {
+            return Ok(cancelled_sync(files_uploaded, files_downloaded, conflicts));
+        }
+        let relative_path = Path::new(relative);
+        if downloaded.contains(relative_path) {
+            continue;
+        }
+        let file_name = relative_path.file_name().unwrap_or_default().to_string_lossy().to_string();
+
+        let mut dir = PathBuf::new();
+        for component in relative_path.parent().into_iter().flat_map(|p| p.components()) {
+            let name = component.as_os_str().to_string_lossy().to_string();
+            let child = dir.join(&name);
+            if !folders.contains_key(&child) {
+                let (parent_id, parent_children) = &folders[&dir];
+                let id = match parent_children.get(&name) {
+                    Some(item) if item.mime == GDRIVE_FOLDER_MIME => item.id.clone(),
+                    _ => gdrive_create_folder(&client, &access_token, &name, Some(parent_id.as_str())).await?,
+                };
+                let children = gdrive_list_children(&client, &access_token, &id).await?;
+                folders.insert(child.clone(), (id, children));
+            }
+            dir = child;
+        }
+
+        let content = match fs::read(base_path.join(relative)) {
+            Ok(content) => content,
+            Err(e) => {
+                failures.push(format!("{}: {}", relative, e));
+                continue;
+            }
+        };
+        let content = encrypt_for_upload(cipher.as_ref(), content)?;
+        let (parent_id, children) = &folders[&dir];
+        let existing_id = children.get(&file_name)
+            .filter(|item| item.mime != GDRIVE_FOLDER_MIME)
+            .map(|item| item.id.as_str());
+        match gdrive_upload(&client, &access_token, &file_name, parent_id, existing_id, &content, max_bytes_per_sec).await {
+            Ok(()) => {
+                synced.insert(relative.clone());
+                files_uploaded += 1;
+            }
+            Err(e) => failures.push(format!("{}: {}", relative, e)),
+        }
+    }
+
+    save_sync_manifest(&base_path, "googledrive", &synced)?;
+    
+    let mut message = format!("Google Drive sync complete: {} uploaded, {} downloaded", files_uploaded, files_downloaded);
+    if files_deleted > 0 {
+        message.push_str(&format!(", {} deleted", files_deleted));
+    }
+    append_sync_failures(&mut message, &failures);
+    finish_sync(&notes_path, SyncStatus {
+        success: failures.is_empty(),
+        message,
+        files_uploaded,
+        files_downloaded,
+        conflicts,
+    })
+}
+
+// WebDAV (Nextcloud, ownCloud and other self-hosted servers). `url` is the collection
+// notes sync under, e.g. https://cloud.example.com/remote.php/dav/files/me/Azimuth
+fn webdav_url(base_url: &str, relative: &str) -> String {
+    let encoded: Vec<String> = relative
+        .split('/')
+        .map(|segment| urlencoding::encode(segment).into_owned())
+        .collect();
+    format!("{}/{}", base_url.trim_end_matches('/'), encoded.join("/"))
+}
+
+fn webdav_check_auth(response: &reqwest::Response) -> Result<(), AzimuthError> {
+    if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+        return Err(AzimuthError::PermissionDenied("WebDAV authentication failed: check the username and password (Nextcloud with 2FA needs an app password)".to_string()));
+    }
+    Ok(())
+}
+
+async fn webdav_mkcol(client: &reqwest::Client, url: &str, username: &str, password: &str) -> Result<(), AzimuthError> {
+    let response = client.request(reqwest::Method::from_bytes(b"MKCOL").map_err(|e| AzimuthError::Other(e.to_string()))?, url)
+        .basic_auth(username, Some(password))
+        .send()
+        .await?;
+    webdav_check_auth(&response)?;
+
+    // 405 means the collection already exists
+    let status = response.status();
+    if status.is_success() || status == reqwest::StatusCode::METHOD_NOT_ALLOWED {
+        Ok(())
+    } else {
+        Err(AzimuthError::Network(format!("Failed to create WebDAV collection {}: {}", url, status)))
+    }
+}
+
+// One <response> of a PROPFIND multistatus, with its href percent-decoded
+#[derive(Debug, Default, PartialEq)]
+struct PropfindEntry {
+    path: String,
+    collection: bool,
+    modified: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+// Servers pick their own prefix for the DAV: namespace, so elements are matched on
+// their local name
+fn parse_propfind(body: &str) -> Result<Vec<PropfindEntry>, AzimuthError> {
+    use quick_xml::events::Event;
+
+    #[derive(Default)]
+    struct Response {
+        href: Option<String>,
+        collection: bool,
+        modified: String,
+    }
+
+    let mut reader = quick_xml::Reader::from_str(body);
+    let mut entries = Vec::new();
+    let mut response: Option<Response> = None;
+    // The element whose text is being read: href or getlastmodified
+    let mut reading: Option<Vec<u8>> = None;
+    let mut text = String::new();
+    loop {
+        match reader.read_event().map_err(|e| AzimuthError::InvalidInput(format!("Invalid PROPFIND response: {}", e)))? {
+            Event::Start(e) => match e.local_name().as_ref() {
+                b"response" => response = Some(Response::default()),
+                name @ (b"href" | b"getlastmodified") => {
+                    reading = Some(name.to_vec());
+                    text.clear();
+                }
+                b"collection" => {
+                    if let Some(response) = response.as_mut() {
+                        response.collection = true;
+                    }
+                }
+                _ => {}
+            },
+            Event::Empty(e) if e.local_name().as_ref() == b"collection" => {
+                if let Some(response) = response.as_mut() {
+                    response.collection = true;
+                }
+            }
+            Event::Text(e) if reading.is_some() => text.push_str(&e.decode().map_err(|e| AzimuthError::InvalidInput(e.to_string()))?),
+            Event::CData(e) if reading.is_some() => text.push_str(&String::from_utf8_lossy(&e)),
+            Event::GeneralRef(e) if reading.is_some() => {
+                if let Ok(Some(c)) = e.resolve_char_ref() {
+                    text.push(c);
+                } else if let Some(resolved) = e.decode().ok().and_then(|name| quick_xml::escape::resolve_xml_entity(&name)) {
+                    text.push_str(resolved);
+                }
+            }
+            Event::End(e) => match e.local_name().as_ref() {
+                b"response" => {
+                    if let Some(Response { href: Some(href), collection, modified }) = response.take() {
+                        // Servers may return full URLs or absolute paths
+                        let href_path = reqwest::Url::parse(&href).map(|u| u.path().to_string()).unwrap_or(href);
+                        entries.push(PropfindEntry {
+                            path: urlencoding::decode(&href_path).map_err(|e| AzimuthError::InvalidInput(e.to_string()))?.into_owned(),
+                            collection,
+                            modified: chrono::DateTime::parse_from_rfc2822(modified.trim())
+                                .ok()
+                                .map(|t| t.with_timezone(&chrono::Utc)),
+                        });
+                    }
+                }
+                name if reading.as_deref() == Some(name) => {
+                    if let Some(response) = response.as_mut() {
+                        match name {
+                            b"href" => response.href = response.href.take().or(Some(text.trim().to_string())),
+                            _ => response.modified = text.clone(),
+                        }
+                    }
+                    reading = None;
+                }
+                _ => {}
+            },
+            Event::Eof => return Ok(entries),
+            _ => {}
+        }
+    }
+}
+
+// Path of a PROPFIND entry relative to the base collection. None for anything outside
+// it, and for paths the server made up that would land outside the notes folder once
+// joined onto it, such as `..` segments or an absolute path after decoding.
+fn webdav_relative_path(base_path: &str, href_path: &str) -> Option<String> {
+    let relative = href_path.strip_prefix(base_path)?;
+    if !(relative.is_empty() || relative.starts_with('/')) {
+        return None;
+    }
+    let relative = relative.trim_matches('/');
+    if !relative.is_empty() && safe_join_relative(Path::new(""), relative).is_err() {
+        return None;
+    }
+    Some(relative.to_string())
+}
+
+// Relative path and last-modified time of every file under the base collection.
+// Walks one level per PROPFIND, as many servers (Nextcloud included) refuse Depth: infinity.
+async fn list_webdav_files(
+    client: &reqwest::Client,
+    base_url: &str,
+    username: &str,
+    password: &str,
+) -> Result<Vec<(String, Option<chrono::DateTime<chrono::Utc>>)>, AzimuthError> {
+    let base = reqwest::Url::parse(base_url).map_err(|e| AzimuthError::InvalidInput(format!("Invalid WebDAV URL: {}", e)))?;
+    let base_path = urlencoding::decode(base.path()).map_err(|e| AzimuthError::InvalidInput(e.to_string()))?.trim_end_matches('/').to_string();
+    let propfind = reqwest::Method::from_bytes(b"PROPFIND").map_err(|e| AzimuthError::Other(e.to_string()))?;
+
+    let mut files = Vec::new();
+    let mut pending = vec![String::new()];
+    while let Some(dir) = pending.pop() {
+        let url = if dir.is_empty() {
+            format!("{}/", base_url.trim_end_matches('/'))
+        } else {
+            format!("{}/", webdav_url(base_url, &dir))
+        };
+        let response = client.request(propfind.clone(), &url)
+            .basic_auth(username, Some(password))
+            .header("Depth", "1")
+            .header("Content-Type", "application/xml")
+            .body(r#"<?xml version="1.0"?><d:propfind xmlns:d="DAV:"><d:prop><d:resourcetype/><d:getlastmodified/></d:prop></d:propfind>"#)
+            .send()
+            .await?;
+        webdav_check_auth(&response)?;
+
+        // The base collection doesn't exist until the first upload
+        if dir.is_empty() && response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(files);
+        }
+        if !response.status().is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(AzimuthError::Network(format!("Failed to list WebDAV files: {}", body)));
+        }
+
+        let body = response.text().await?;
+        for entry in parse_propfind(&body)? {
+            let Some(relative) = webdav_relative_path(&base_path, &entry.path) else {
+                continue;
+            };
+            // Each listing includes the collection itself
+            if relative.is_empty() || relative == dir {
+                continue;
+            }
+
+            if entry.collection {
+                pending.push(relative);
+            } else {
+                files.push((relative, entry.modified));
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+// Each argument is a field of the frontend's invoke payload
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+async fn sync_to_webdav(
+    url: String,
+    username: String,
+    password: String,
+    notes_path: String,
+    propagate_deletions: Option<bool>,
+    passphrase: Option<String>,
+    max_bytes_per_sec: Option<u64>,
+    sync: tauri::State<'_, SyncControl>,
+) -> Result<SyncStatus, AzimuthError> {
+    let cancel = sync.start();
+    let client = reqwest::Client::new();
+    let base_path = PathBuf::from(&notes_path);
+    let cipher = SyncCipher::for_sync(&base_path, passphrase)?;
+    let ignored = load_sync_ignore(&base_path);
+
+    let mut files_uploaded = 0;
+    let mut files_downloaded = 0;
+    let mut files_deleted = 0;
+    let conflicts = Vec::new();
+    let mut failures = Vec::new();
+    let mut local_files = local_sync_hashes(&notes_path);
+
+    if propagate_deletions.unwrap_or(false) {
+        let remote = list_webdav_files(&client, &url, &username, &password).await?
+            .into_iter()
+            .filter(|(path, _)| !is_sync_ignored(&ignored, Path::new(path), false))
+            .filter_map(|(path, modified)| modified.map(|modified| (path, modified)))
+            .collect();
+        let plan = plan_deletions(&load_sync_manifest(&base_path, "webdav"), &local_files, &remote);
+        for path in &plan.delete_remote {
+            let response = client.delete(webdav_url(&url, path))
+                .basic_auth(&username, Some(&password))
+                .send()
+                .await?;
+            webdav_check_auth(&response)?;
+            if response.status().is_success() {
+                files_deleted += 1;
+            } else {
+                failures.push(format!("{}: {}", path, response.status()));
+            }
+        }
+        let mut snapshot = SyncSnapshot::new(&base_path);
+        delete_local_files(&base_path, &plan.delete_local, &mut snapshot)?;
+        snapshot.finish()?;
+        for path in &plan.delete_local {
+            local_files.remove(path);
+        }
+        files_deleted += plan.delete_local.len();
+    }
+
+    // Upload local files, creating parent collections first
+    webdav_mkcol(&client, &url, &username, &password).await?;
+    let mut collections: HashSet<String> = HashSet::new();
+    for relative in local_files.keys() {
+        if cancel.load(Ordering::SeqCst) {
+            return Ok(cancelled_sync(files_uploaded, files_downloaded, conflicts));
+        }
+        let segments: Vec<&str> = relative.split('/').collect();
+        for depth in 1..segments.len() {
+            let parent = segments[..depth].join("/");
+            if collections.insert(parent.clone()) {
+                webdav_mkcol(&client, &webdav_url(&url, &parent), &username, &password).await?;
+            }
+        }
+
+        let content = match fs::read(base_path.join(relative)) {
+            Ok(content) => content,
+            Err(e) => {
+                failures.push(format!("{}: {}", relative, e));
+                continue;
+            }
+        };
+        let content = encrypt_for_upload(cipher.as_ref(), content)?;
+        let request = client.put(webdav_url(&url, relative))
+            .basic_auth(&username, Some(&password))
+            .header("Content-Type", "application/octet-stream")
+            .body(throttled_body(content, max_bytes_per_sec));
+        let response = match send_with_retry(request).await {
+            Ok(response) => response,
+            Err(e) => {
+                failures.push(format!("{}: {}", relative, e));
+                continue;
+            }
+        };
+        webdav_check_auth(&response)?;
+
+        if response.status().is_success() {
+            files_uploaded += 1;
+        } else {
+            failures.push(format!("{}: {}", relative, response.status()));
+        }
+    }
+
+    // List and download remote files
+    let remote_files = list_webdav_files(&client, &url, &username, &password).await?;
+    let mut synced = Vec::new();
+    for (relative, _) in remote_files {
+        if cancel.load(Ordering::SeqCst) {
+            return Ok(cancelled_sync(files_uploaded, files_downloaded, conflicts));
+        }
+        if is_sync_ignored(&ignored, Path::new(&relative), false) {
+            continue;
+        }
+        let local_path = match safe_join_relative(&base_path, &relative) {
+            Ok(local_path) => local_path,
+            Err(e) => {
+                failures.push(format!("{}: {}", relative, e));
+                continue;
+            }
+        };
+        synced.push(relative.clone());
+
+        // Hidden files are never uploaded, so never pull them back down
+        let hidden = relative.split('/').any(|segment| segment.starts_with('.'));
+        if hidden || local_path.exists() {
+            continue;
+        }
+
+        let response = client.get(webdav_url(&url, &relative))
+            .basic_auth(&username, Some(&password))
+            .send()
+            .await?;
+        webdav_check_auth(&response)?;
+
+        if !response.status().is_success() {
+            failures.push(format!("{}: {}", relative, response.status()));
+            continue;
+        }
+        let content = read_throttled(response, max_bytes_per_sec).await?;
+        let content = decrypt_download(cipher.as_ref(), &relative, &content)?;
+        if let Some(parent) = local_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&local_path, content)?;
+        files_downloaded += 1;
+    }
+
+    save_sync_manifest(&base_path, "webdav", &synced)?;
+
+    let mut message = format!("WebDAV sync complete: {} uploaded, {} downloaded", files_uploaded, files_downloaded);
+    if files_deleted > 0 {
+        message.push_str(&format!(", {} deleted", files_deleted));
+    }
+    append_sync_failures(&mut message, &failures);
+    finish_sync(&notes_path, SyncStatus {
+        success: failures.is_empty(),
+        message,
+        files_uploaded,
+        files_downloaded,
+        conflicts,
+    })
+}
+
+// Git remotes. The notes folder itself becomes the repository.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct GitCredentials {
+    pub username: Option<String>,
+    // Password or personal access token for HTTPS remotes
+    pub password: Option<String>,
+    // Private key for SSH remotes; the SSH agent is used when unset
+    pub ssh_key_path: Option<String>,
+    pub ssh_passphrase: Option<String>,
+}
+
+fn git_callbacks(credentials: &GitCredentials, max_bytes_per_sec: Option<u64>) -> git2::RemoteCallbacks<'_> {
+    let mut callbacks = git2::RemoteCallbacks::new();
+    // libgit2 does its own transfers, so the cap is kept by stalling its progress callbacks
+    if let Some(limit) = max_bytes_per_sec {
+        let (mut fetch_throttle, mut fetched) = (Throttle::new(limit), 0);
+        callbacks.transfer_progress(move |progress| {
+            let received = progress.received_bytes();
+            std::thread::sleep(fetch_throttle.delay(received.saturating_sub(fetched)));
+            fetched = received;
+            true
+        });
+        let (mut push_throttle, mut pushed) = (Throttle::new(limit), 0);
+        callbacks.push_transfer_progress(move |_, _, sent| {
+            std::thread::sleep(push_throttle.delay(sent.saturating_sub(pushed)));
+            pushed = sent;
+        });
+    }
+    let mut attempts = 0;
+    callbacks.credentials(move |_url, username_from_url, allowed| {
+        // libgit2 keeps asking until credentials work, so only offer them once
+        attempts += 1;
+        if attempts > 1 {
+            return Err(git2::Error::from_str("Git authentication failed: check the sync credentials"));
+        }
+        let username = credentials.username.as_deref().or(username_from_url).unwrap_or("git");
+        if allowed.contains(git2::CredentialType::SSH_KEY) {
+            return match &credentials.ssh_key_path {
+                Some(key) => git2::Cred::ssh_key(username, None, Path::new(key), credentials.ssh_passphrase.as_deref()),
+                None => git2::Cred::ssh_key_from_agent(username),
+            };
+        }
+        if allowed.contains(git2::CredentialType::USER_PASS_PLAINTEXT)
+            && let Some(password) = &credentials.password
+        {
+            return git2::Cred::userpass_plaintext(username, password);
+        }
+        git2::Cred::default()
+    });
+    callbacks
+}
+
+fn git_signature(repo: &git2::Repository) -> Result<git2::Signature<'static>, AzimuthError> {
+    repo.signature()
+        .or_else(|_| git2::Signature::now("Azimuth", "azimuth@localhost"))
+        .map_err(AzimuthError::from)
+}
+
+// Number of files that differ between two trees (None is the empty tree)
+fn git_changed_files(repo: &git2::Repository, old: Option<&git2::Tree>, new: Option<&git2::Tree>) -> Result<usize, AzimuthError> {
+    repo.diff_tree_to_tree(old, new, None)
+        .map(|diff| diff.deltas().len())
+        .map_err(AzimuthError::from)
+}
+
+fn git_head_tree(repo: &git2::Repository) -> Option<git2::Tree<'_>> {
+    repo.head().ok()?.peel_to_tree().ok()
+}
+
+// Merges `upstream` into HEAD after a rebase hit conflicts. Files edited on both sides
+// keep the local version, with the remote one written alongside as a .conflict file
+// for resolve_conflict, so the working tree is never left mid-merge.
+fn git_merge_with_conflicts(
+    repo: &git2::Repository,
+    base_path: &Path,
+    upstream: &git2::Commit,
+) -> Result<Vec<SyncConflict>, AzimuthError> {
+    let local = repo.head().and_then(|h| h.peel_to_commit())?;
+    let mut merged = repo.merge_commits(&local, upstream, None)?;
+    let remote_modified = chrono::DateTime::from_timestamp(upstream.time().seconds(), 0)
+        .map(|t| t.to_rfc3339())
+        .unwrap_or_default();
+
+    let entries: Vec<git2::IndexConflict> = merged.conflicts()?
+        .collect::<Result<_, _>>()?;
+    let mut conflicts = Vec::new();
+    for entry in entries {
+        let Some(path_entry) = entry.our.as_ref().or(entry.their.as_ref()).or(entry.ancestor.as_ref()) else {
+            continue;
+        };
+        let relative = String::from_utf8_lossy(&path_entry.path).to_string();
+        merged.conflict_remove(Path::new(&relative))?;
+
+        // A side that deleted the file loses to the side that edited it
+        let kept = match (entry.our, entry.their) {
+            (Some(ours), Some(theirs)) => {
+                let file_path = base_path.join(&relative);
+                let blob = repo.find_blob(theirs.id)?;
+                fs::write(conflict_path(&file_path), blob.content())?;
+                let local_modified = fs::metadata(&file_path)
+                    .and_then(|m| m.modified())
+                    .map(system_time_to_rfc3339)
+                    .unwrap_or_default();
+                conflicts.push(SyncConflict {
+                    file_path: relative.clone(),
+                    local_modified,
+                    remote_modified: remote_modified.clone(),
+                    local_hash: ours.id.to_string(),
+                    remote_hash: theirs.id.to_string(),
+                });
+                ours
+            }
+            (Some(kept), None) | (None, Some(kept)) => kept,
+            (None, None) => continue,
+        };
+        // Clear the conflict stage bits (GIT_INDEX_ENTRY_STAGEMASK) so it is a normal entry
+        let mut kept = kept;
+        kept.flags &= !0x3000;
+        merged.add(&kept)?;
+    }
+
+    let tree_id = merged.write_tree_to(repo)?;
+    let tree = repo.find_tree(tree_id)?;
+    let signature = git_signature(repo)?;
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        "Merge remote changes (conflicts pending)",
+        &tree,
+        &[&local, upstream],
+    )?;
+    repo.checkout_head(Some(git2::build::CheckoutBuilder::new().safe()))?;
+
+    Ok(conflicts)
+}
+
+// Rebases local commits onto `upstream`, falling back to a merge when they conflict
+fn git_integrate_upstream(
+    repo: &git2::Repository,
+    base_path: &Path,
+    branch: &str,
+    upstream_ref: &git2::Reference,
+) -> Result<Vec<SyncConflict>, AzimuthError> {
+    let upstream = repo.reference_to_annotated_commit(upstream_ref)?;
+    let upstream_commit = upstream_ref.peel_to_commit()?;
+    let (analysis, _) = repo.merge_analysis(&[&upstream])?;
+
+    if analysis.is_up_to_date() {
+        return Ok(Vec::new());
+    }
+    if analysis.is_fast_forward() || analysis.is_unborn() {
+        repo.checkout_tree(upstream_commit.as_object(), Some(git2::build::CheckoutBuilder::new().safe()))?;
+        repo.reference(&format!("refs/heads/{}", branch), upstream_commit.id(), true, "azimuth: fast-forward")?;
+        repo.set_head(&format!("refs/heads/{}", branch))?;
+        return Ok(Vec::new());
+    }
+
+    let signature = git_signature(repo)?;
+    let mut rebase = repo.rebase(None, Some(&upstream), None, None)?;
+    let mut conflicted = false;
+    while let Some(operation) = rebase.next() {
+        operation?;
+        if repo.index()?.has_conflicts() {
+            conflicted = true;
+            break;
+        }
+        match rebase.commit(None, &signature, None) {
+            // The remote already has this change
+            Err(e) if e.code() == git2::ErrorCode::Applied => {}
+            result => {
+                result?;
+            }
+        }
+    }
+
+    if conflicted {
+        rebase.abort()?;
+        return git_merge_with_conflicts(repo, base_path, &upstream_commit);
+    }
+    rebase.finish(Some(&signature))?;
+    Ok(Vec::new())
+}
+
+fn git_sync(
+    remote_url: &str,
+    branch: &str,
+    notes_path: &str,
+    credentials: &GitCredentials,
+    max_bytes_per_sec: Option<u64>,
+    cancel: &AtomicBool,
+) -> Result<SyncStatus, AzimuthError> {
+    let base_path = Path::new(notes_path);
+    let local_branch = format!("refs/heads/{}", branch);
+
+    let repo = match git2::Repository::open(base_path) {
+        Ok(repo) => repo,
+        Err(_) => git2::Repository::init(base_path)?,
+    };
+    if repo.head_detached().unwrap_or(false) {
+        return Err(AzimuthError::Other("The notes repository has a detached HEAD; check out a branch first".to_string()));
+    }
+    match repo.head() {
+        Ok(head) if head.name() != Some(local_branch.as_str()) => {
+            return Err(AzimuthError::Other(format!(
+                "The notes repository is on {}, not {}",
+                head.shorthand().unwrap_or("another branch"),
+                branch
+            )));
+        }
+        Ok(_) => {}
+        // Nothing committed yet, so point the unborn HEAD at the sync branch
+        Err(_) => repo.set_head(&local_branch)?,
+    }
+
+    match repo.find_remote("origin") {
+        Ok(remote) if remote.url() == Some(remote_url) => {}
+        Ok(_) => repo.remote_set_url("origin", remote_url)?,
+        Err(_) => {
+            repo.remote("origin", remote_url)?;
+        }
+    }
+
+    // Stage everything the other providers would sync
+    let archived = excluded_archive_paths(notes_path);
+    let ignored = load_sync_ignore(base_path);
+    let mut index = repo.index()?;
+    let mut skip = |path: &Path, _: &[u8]| -> i32 {
+        let hidden = path.components().any(|c| c.as_os_str().to_string_lossy().starts_with('.'));
+        let conflict = path.extension().is_some_and(|ext| ext == "conflict");
+        if hidden
+            || conflict
+            || is_archived(notes_path, &base_path.join(path), &archived)
+            || is_sync_ignored(&ignored, path, false)
+        {
+            1
+        } else {
+            0
+        }
+    };
+    index.add_all(["*"], git2::IndexAddOption::DEFAULT, Some(&mut skip))?;
+    index.update_all(["*"], None)?;
+    index.write()?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+
+    let head_commit = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+    let head_tree = head_commit.as_ref().and_then(|c| c.tree().ok());
+    if git_changed_files(&repo, head_tree.as_ref(), Some(&tree))? > 0 {
+        let signature = git_signature(&repo)?;
+        let message = format!("Azimuth sync {}", chrono::Local::now().format("%Y-%m-%d %H:%M:%S"));
+        let parents: Vec<&git2::Commit> = head_commit.iter().collect();
+        repo.commit(Some("HEAD"), &signature, &signature, &message, &tree, &parents)?;
+    }
+
+    // The commit stays local; the next sync pushes it
+    if cancel.load(Ordering::SeqCst) {
+        return Ok(cancelled_sync(0, 0, Vec::new()));
+    }
+
+    // Pull with rebase
+    let mut remote = repo.find_remote("origin")?;
+    let mut fetch_options = git2::FetchOptions::new();
+    fetch_options.remote_callbacks(git_callbacks(credentials, max_bytes_per_sec));
+    let refspec = format!("+refs/heads/{0}:refs/remotes/origin/{0}", branch);
+    remote.fetch(&[refspec.as_str()], Some(&mut fetch_options), None)?;
+
+    let local_tree = git_head_tree(&repo);
+    let upstream_ref = repo.find_reference(&format!("refs/remotes/origin/{}", branch)).ok();
+    let conflicts = match &upstream_ref {
+        Some(upstream_ref) => git_integrate_upstream(&repo, base_path, branch, upstream_ref)?,
+        None => Vec::new(),
+    };
+    let merged_tree = git_head_tree(&repo);
+    let files_downloaded = git_changed_files(&repo, local_tree.as_ref(), merged_tree.as_ref())?;
+
+    // Files edited on both sides stay local until resolve_conflict has run for each
+    let pending_conflicts = WalkDir::new(base_path)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|e| e.ok())
+        .any(|e| e.path().extension().is_some_and(|ext| ext == "conflict"));
+
+    if cancel.load(Ordering::SeqCst) {
+        return Ok(cancelled_sync(0, files_downloaded, conflicts));
+    }
+
+    let upstream_tree = upstream_ref.as_ref().and_then(|r| r.peel_to_tree().ok());
+    let mut files_uploaded = 0;
+    if !pending_conflicts && merged_tree.is_some() {
+        let changed = git_changed_files(&repo, upstream_tree.as_ref(), merged_tree.as_ref())?;
+        let ahead = match (&upstream_ref, repo.head().ok().and_then(|h| h.target())) {
+            (Some(upstream_ref), Some(head)) => upstream_ref.target() != Some(head),
+            (None, Some(_)) => true,
+            (_, None) => false,
+        };
+        if ahead {
+            let mut rejection = None;
+            let mut callbacks = git_callbacks(credentials, max_bytes_per_sec);
+            callbacks.push_update_reference(|_, status| {
+                rejection = status.map(|s| s.to_string());
+                Ok(())
+            });
+            let mut push_options = git2::PushOptions::new();
+            push_options.remote_callbacks(callbacks);
+            let refspec = format!("{0}:{0}", local_branch);
+            remote.push(&[refspec.as_str()], Some(&mut push_options))?;
+            drop(push_options);
+            if let Some(rejection) = rejection {
+                return Err(AzimuthError::Network(format!("Git push was rejected: {}", rejection)));
+            }
+            files_uploaded = changed;
+        }
+    }
+
+    let mut message = format!("Git sync complete: {} uploaded, {} downloaded", files_uploaded, files_downloaded);
+    if !conflicts.is_empty() {
+        message.push_str(&format!(", {} conflicts", conflicts.len()));
+    } else if pending_conflicts {
+        message.push_str("; not pushed until conflicts are resolved");
+    }
+    finish_sync(notes_path, SyncStatus {
+        success: true,
+        message,
+        files_uploaded,
+        files_downloaded,
+        conflicts,
+    })
+}
+
+#[tauri::command]
+async fn sync_to_git(
+    remote_url: String,
+    branch: String,
+    notes_path: String,
+    credentials: GitCredentials,
+    max_bytes_per_sec: Option<u64>,
+    sync: tauri::State<'_, SyncControl>,
+) -> Result<SyncStatus, AzimuthError> {
+    let cancel = sync.start();
+    tokio::task::spawn_blocking(move || git_sync(&remote_url, &branch, &notes_path, &credentials, max_bytes_per_sec, &cancel))
+        .await?
+}
+
+// SFTP to any SSH server
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SftpAuth {
+    Password { password: String },
+    PrivateKey { key_path: String, passphrase: Option<String> },
+}
+
+struct SftpServer {
+    host: String,
+    port: u16,
+    username: String,
+    auth: SftpAuth,
+}
+
+fn sftp_connect(server: &SftpServer) -> Result<ssh2::Session, AzimuthError> {
+    let SftpServer { host, port, username, auth } = server;
+    let (host, port) = (host.as_str(), *port);
+    let tcp = std::net::TcpStream::connect((host, port))
+        .map_err(|e| AzimuthError::Network(format!("Could not connect to {}:{}: {}", host, port, e)))?;
+    let mut session = ssh2::Session::new()?;
+    session.set_tcp_stream(tcp);
+    session.handshake().map_err(|e| AzimuthError::Network(format!("SSH handshake with {} failed: {}", host, e)))?;
+
+    // Only trust servers already in known_hosts, as ssh itself does
+    let (key, _) = session.host_key().ok_or("The server did not send a host key")?;
+    let mut known_hosts = session.known_hosts()?;
+    if let Some(path) = dirs::home_dir().map(|home| home.join(".ssh").join("known_hosts"))
+        && path.exists()
+    {
+        known_hosts.read_file(&path, ssh2::KnownHostFileKind::OpenSSH)?;
+    }
+    match known_hosts.check_port(host, port, key) {
+        ssh2::CheckResult::Match => {}
+        ssh2::CheckResult::NotFound => {
+            return Err(AzimuthError::Network(format!(
+                "The host key for {} is not in ~/.ssh/known_hosts. Connect once with `ssh -p {} {}@{}` to verify and save it, then sync again",
+                host, port, username, host
+            )));
+        }
+        ssh2::CheckResult::Mismatch => {
+            return Err(AzimuthError::Network(format!(
+                "The host key for {} does not match ~/.ssh/known_hosts. The server's key has changed or the connection is being intercepted; sync stopped",
+                host
+            )));
+        }
+        ssh2::CheckResult::Failure => return Err(AzimuthError::Network(format!("Could not check the host key for {}", host))),
+    }
+
+    let result = match auth {
+        SftpAuth::Password { password } => session.userauth_password(username, password),
+        SftpAuth::PrivateKey { key_path, passphrase } => {
+            session.userauth_pubkey_file(username, None, Path::new(key_path), passphrase.as_deref())
+        }
+    };
+    if result.is_err() || !session.authenticated() {
+        let hint = match auth {
+            SftpAuth::Password { .. } => "check the username and password",
+            SftpAuth::PrivateKey { .. } => "check the username, key path and passphrase, and that the key is in the server's authorized_keys",
+        };
+        return Err(AzimuthError::PermissionDenied(format!("SSH authentication as {} failed: {}", username, hint)));
+    }
+    Ok(session)
+}
+
+// Relative path and modified time of every non-hidden file under `remote_dir`
+fn sftp_list_files(sftp: &ssh2::Sftp, remote_dir: &Path) -> Result<HashMap<String, chrono::DateTime<chrono::Utc>>, AzimuthError> {
+    let mut files = HashMap::new();
+    let mut pending = vec![remote_dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries = match sftp.readdir(&dir) {
+            Ok(entries) => entries,
+            // The remote folder doesn't exist until the first upload
+            Err(_) if dir == remote_dir => return Ok(files),
+            Err(e) => return Err(e.into()),
+        };
+        for (path, stat) in entries {
+            if path.file_name().is_none_or(|n| n.to_string_lossy().starts_with('.')) {
+                continue;
+            }
+            if stat.is_dir() {
+                pending.push(path);
+            } else if stat.is_file()
+                && let Ok(relative) = path.strip_prefix(remote_dir)
+            {
+                // A file without a time counts as just changed, so it is never deleted
+                // as unchanged since the last sync
+                let modified = stat.mtime
+                    .and_then(|mtime| chrono::DateTime::from_timestamp(mtime as i64, 0))
+                    .unwrap_or_else(chrono::Utc::now);
+                files.insert(relative.to_string_lossy().to_string(), modified);
+            }
+        }
+    }
+    Ok(files)
+}
+
+fn sftp_mkdir_all(sftp: &ssh2::Sftp, dir: &Path) -> Result<(), AzimuthError> {
+    if sftp.stat(dir).is_ok() {
+        return Ok(());
+    }
+    if let Some(parent) = dir.parent() {
+        sftp_mkdir_all(sftp, parent)?;
+    }
+    sftp.mkdir(dir, 0o755).map_err(|e| AzimuthError::io_context(e, format!("Failed to create {}", dir.display())))
+}
+
+fn sftp_sync(
+    server: &SftpServer,
+    remote_dir: &str,
+    notes_path: &str,
+    propagate_deletions: bool,
+    passphrase: Option<String>,
+    max_bytes_per_sec: Option<u64>,
+    cancel: &AtomicBool,
+) -> Result<SyncStatus, AzimuthError> {
+    use std::io::Read;
+
+    let session = sftp_connect(server)?;
+    let sftp = session.sftp()?;
+    let base_path = Path::new(notes_path);
+    let cipher = SyncCipher::for_sync(base_path, passphrase)?;
+    let remote_dir = Path::new(remote_dir);
+
+    let mut files_uploaded = 0;
+    let mut files_downloaded = 0;
+    let mut files_deleted = 0;
+    let conflicts = Vec::new();
+    let mut local_files = local_sync_hashes(notes_path);
+    let manifest = load_sync_manifest(base_path, "sftp");
+    let ignored = load_sync_ignore(base_path);
+    let mut remote_files: HashMap<String, chrono::DateTime<chrono::Utc>> = sftp_list_files(&sftp, remote_dir)?
+        .into_iter()
+        .filter(|(path, _)| !is_sync_ignored(&ignored, Path::new(path), false))
+        .collect();
+
+    if propagate_deletions {
+        let plan = plan_deletions(&manifest, &local_files, &remote_files);
+        for path in &plan.delete_remote {
+            sftp.unlink(&remote_dir.join(path)).map_err(|e| AzimuthError::io_context(e, format!("Failed to delete {}", path)))?;
+            remote_files.remove(path);
+            files_deleted += 1;
+        }
+        let mut snapshot = SyncSnapshot::new(base_path);
+        delete_local_files(base_path, &plan.delete_local, &mut snapshot)?;
+        snapshot.finish()?;
+        for path in &plan.delete_local {
+            local_files.remove(path);
+        }
+        files_deleted += plan.delete_local.len();
+    }
+
+    // Upload files that are new or changed since the last sync
+    for (relative, hash) in &local_files {
+        if cancel.load(Ordering::SeqCst) {
+            return Ok(cancelled_sync(files_uploaded, files_downloaded, conflicts));
+        }
+        if remote_files.contains_key(relative) && manifest.files.get(relative) == Some(hash) {
+            continue;
+        }
+        let remote_path = remote_dir.join(relative);
+        if let Some(parent) = remote_path.parent() {
+            sftp_mkdir_all(&sftp, parent)?;
+        }
+        let content = fs::read(base_path.join(relative))?;
+        let content = encrypt_for_upload(cipher.as_ref(), content)?;
+        let mut file = sftp.create(&remote_path).map_err(|e| AzimuthError::io_context(e, format!("Failed to upload {}", relative)))?;
+        std::io::copy(&mut ThrottledReader::new(content.as_slice(), max_bytes_per_sec), &mut file)?;
+        files_uploaded += 1;
+    }
+
+    // Download remote files missing locally
+    for relative in remote_files.keys() {
+        if cancel.load(Ordering::SeqCst) {
+            return Ok(cancelled_sync(files_uploaded, files_downloaded, conflicts));
+        }
+        let local_path = base_path.join(relative);
+        if local_path.exists() {
+            continue;
+        }
+        let mut file = sftp.open(remote_dir.join(relative)).map_err(|e| AzimuthError::io_context(e, format!("Failed to download {}", relative)))?;
+        let mut content = Vec::new();
+        ThrottledReader::new(&mut file, max_bytes_per_sec).read_to_end(&mut content)?;
+        let content = decrypt_download(cipher.as_ref(), relative, &content)?;
+        if let Some(parent) = local_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&local_path, content)?;
+        files_downloaded += 1;
+    }
+
+    save_sync_manifest(base_path, "sftp", local_files.keys().chain(remote_files.keys()))?;
+
+    let mut message = format!("SFTP sync complete: {} uploaded, {} downloaded", files_uploaded, files_downloaded);
+    if files_deleted > 0 {
+        message.push_str(&format!(", {} deleted", files_deleted));
+    }
+    finish_sync(notes_path, SyncStatus {
+        success: true,
+        message,
+        files_uploaded,
+        files_downloaded,
+        conflicts,
+    })
+}
+
+// Each argument is a field of the frontend's invoke payload
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+async fn sync_to_sftp(
+    host: String,
+    port: u16,
+    username: String,
+    auth: SftpAuth,
+    remote_dir: String,
+    notes_path: String,
+    propagate_deletions: Option<bool>,
+    passphrase: Option<String>,
+    max_bytes_per_sec: Option<u64>,
+    sync: tauri::State<'_, SyncControl>,
+) -> Result<SyncStatus, AzimuthError> {
+    let cancel = sync.start();
+    let server = SftpServer { host, port, username, auth };
+    let propagate_deletions = propagate_deletions.unwrap_or(false);
+    tokio::task::spawn_blocking(move || {
+        sftp_sync(&server, &remote_dir, &notes_path, propagate_deletions, passphrase, max_bytes_per_sec, &cancel)
+    })
+        .await?
+}
+
+// iCloud Drive is a folder the system keeps in sync on macOS, so syncing with it is a
+// two-way mirror between the vault and the Azimuth folder inside it
+fn icloud_sync_dir() -> Result<PathBuf, AzimuthError> {
+    if !cfg!(target_os = "macos") {
+        return Err(AzimuthError::Other("iCloud Drive sync is only available on macOS".to_string()));
+    }
+    let home = dirs::home_dir().ok_or("Could not find home directory")?;
+    let icloud_drive = home.join("Library").join("Mobile Documents").join("com~apple~CloudDocs");
+    if !icloud_drive.is_dir() {
+        return Err(AzimuthError::NotFound("iCloud Drive isn't available: sign in to iCloud and turn on iCloud Drive in System Settings".to_string()));
+    }
+    Ok(icloud_drive.join("Azimuth"))
+}
+
+// Files iCloud hasn't downloaded to this Mac are `.<name>.icloud` placeholders
+fn icloud_placeholder_name(file_name: &str) -> Option<&str> {
+    file_name.strip_prefix('.')?.strip_suffix(".icloud")
+}
+
+// Relative path -> modified time and SHA-256 of every file in the iCloud folder. The
+// hash is None for placeholders, which have no contents to read yet.
+fn icloud_files(
+    icloud_dir: &Path,
+    ignored: &ignore::gitignore::Gitignore,
+) -> HashMap<String, (chrono::DateTime<chrono::Utc>, Option<String>)> {
+    let relative_to_dir = |path: &Path| path.strip_prefix(icloud_dir).map(|p| p.to_path_buf()).unwrap_or_default();
+    let mut files = HashMap::new();
+    for entry in WalkDir::new(icloud_dir)
+        .into_iter()
+        .filter_entry(|e| !is_sync_ignored(ignored, &relative_to_dir(e.path()), e.file_type().is_dir()))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+    {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let (relative, hash) = match icloud_placeholder_name(&file_name) {
+            Some(name) => (relative_to_dir(entry.path()).with_file_name(name), None),
+            None if file_name.starts_with('.') => continue,
+            None => (relative_to_dir(entry.path()), get_file_hash(&entry.path().to_path_buf()).ok()),
+        };
+        if let Some(modified) = entry.metadata().ok().and_then(|m| m.modified().ok()) {
+            files.insert(relative.to_string_lossy().to_string(), (modified.into(), hash));
         }
     }
+    files
 }
 
-fn copy_dir_recursive(src: &PathBuf, dst: &PathBuf) -> std::io::Result<()> {
-    if !dst.exists() {
-        fs::create_dir_all(dst)?;
-    }
-    
-    for entry in fs::read_dir(src)? {
-        let entry = entry?;
-        let src_path = entry.path();
-        let dst_path = dst.join(entry.file_name());
-        
-        if src_path.is_dir() {
-            copy_dir_recursive(&src_path, &dst_path)?;
-        } else {
-            fs::copy(&src_path, &dst_path)?;
-        }
+fn mirror_file(from: &Path, to: &Path) -> Result<(), AzimuthError> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
     }
+    fs::copy(from, to)?;
     Ok(())
 }
 
-
-// Cloud Sync Implementation
 #[tauri::command]
-async fn sync_to_s3(
-    bucket: String,
-    region: String,
-    access_key: String,
-    secret_key: String,
+async fn sync_to_icloud(
     notes_path: String,
-) -> Result<SyncStatus, String> {
-    use aws_config::Region;
-    use aws_sdk_s3::config::Credentials;
-    use aws_sdk_s3::Client;
-    use aws_sdk_s3::primitives::ByteStream;
-    
-    let credentials = Credentials::new(&access_key, &secret_key, None, None, "azimuth");
-    let config = aws_sdk_s3::Config::builder()
-        .region(Region::new(region))
-        .credentials_provider(credentials)
-        .build();
-    
-    let client = Client::from_conf(config);
+    propagate_deletions: Option<bool>,
+    sync: tauri::State<'_, SyncControl>,
+) -> Result<SyncStatus, AzimuthError> {
+    let cancel = sync.start();
+    let icloud_dir = icloud_sync_dir()?;
+    fs::create_dir_all(&icloud_dir)?;
     let base_path = PathBuf::from(&notes_path);
-    
+    let ignored = load_sync_ignore(&base_path);
+
     let mut files_uploaded = 0;
     let mut files_downloaded = 0;
+    let mut files_deleted = 0;
     let conflicts = Vec::new();
-    
-    // Get local files
-    let mut local_files: HashMap<String, (String, String)> = HashMap::new(); // path -> (hash, modified)
-    for entry in WalkDir::new(&base_path)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.path().is_file())
-    {
-        let path = entry.path();
-        if path.file_name().map(|n| n.to_string_lossy().starts_with('.')).unwrap_or(false) {
-            continue;
+    let mut failures = Vec::new();
+    let mut local_files = local_sync_hashes(&notes_path);
+    let mut icloud = icloud_files(&icloud_dir, &ignored);
+    let manifest = load_sync_manifest(&base_path, "icloud");
+    let mut snapshot = SyncSnapshot::new(&base_path);
+
+    if propagate_deletions.unwrap_or(false) {
+        let icloud_times = icloud.iter().map(|(path, (modified, _))| (path.clone(), *modified)).collect();
+        let plan = plan_deletions(&manifest, &local_files, &icloud_times);
+        for path in &plan.delete_remote {
+            let icloud_path = icloud_dir.join(path);
+            let placeholder = icloud_path.with_file_name(format!(
+                ".{}.icloud",
+                icloud_path.file_name().unwrap_or_default().to_string_lossy()
+            ));
+            let target = if icloud_path.exists() { icloud_path } else { placeholder };
+            match fs::remove_file(&target) {
+                Ok(()) => {
+                    icloud.remove(path);
+                    files_deleted += 1;
+                }
+                Err(e) => failures.push(format!("{}: {}", path, e)),
+            }
         }
-        let relative = path.strip_prefix(&base_path).unwrap().to_string_lossy().to_string();
-        if let Ok(hash) = get_file_hash(&path.to_path_buf()) {
-            let modified = fs::metadata(path)
-                .and_then(|m| m.modified())
-                .map(|t| format!("{:?}", t))
-                .unwrap_or_default();
-            local_files.insert(relative, (hash, modified));
+        delete_local_files(&base_path, &plan.delete_local, &mut snapshot)?;
+        for path in &plan.delete_local {
+            local_files.remove(path);
         }
+        files_deleted += plan.delete_local.len();
     }
-    
-    // List remote files
-    let mut remote_files: HashMap<String, String> = HashMap::new(); // path -> etag
-    let list_result = client.list_objects_v2()
-        .bucket(&bucket)
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
-    
-    if let Some(contents) = list_result.contents {
-        for obj in contents {
-            if let (Some(key), Some(etag)) = (obj.key, obj.e_tag) {
-                remote_files.insert(key, etag.trim_matches('"').to_string());
-            }
+
+    // Whichever copy was modified more recently wins when the contents differ
+    let mut paths: Vec<&String> = local_files.keys().chain(icloud.keys()).collect();
+    paths.sort();
+    paths.dedup();
+    for path in &paths {
+        if cancel.load(Ordering::SeqCst) {
+            return Ok(cancelled_sync(files_uploaded, files_downloaded, conflicts));
         }
-    }
-    
-    // Upload new/modified local files
-    for (path, (local_hash, _)) in &local_files {
-        let should_upload = match remote_files.get(path) {
-            None => true,
-            Some(remote_etag) => remote_etag != local_hash,
+        let local_path = base_path.join(path);
+        let icloud_path = icloud_dir.join(path);
+        let upload = match (local_files.get(*path), icloud.get(*path)) {
+            (Some(_), None) => true,
+            (None, Some((_, Some(_)))) => false,
+            // Not downloaded by iCloud yet; it's picked up by a later sync once it is
+            (None, Some((_, None))) => continue,
+            (Some(local_hash), Some((_, None))) => {
+                // Only local edits since the last sync can be told apart from a placeholder
+                if manifest.files.get(*path) == Some(local_hash) {
+                    continue;
+                }
+                true
+            }
+            (Some(local_hash), Some((icloud_modified, Some(icloud_hash)))) => {
+                if local_hash == icloud_hash {
+                    continue;
+                }
+                let local_modified: chrono::DateTime<chrono::Utc> = match fs::metadata(&local_path).and_then(|m| m.modified()) {
+                    Ok(modified) => modified.into(),
+                    Err(e) => {
+                        failures.push(format!("{}: {}", path, e));
+                        continue;
+                    }
+                };
+                local_modified >= *icloud_modified
+            }
+            (None, None) => continue,
         };
-        
-        if should_upload {
-            let full_path = base_path.join(path);
-            let body = ByteStream::from_path(&full_path).await.map_err(|e| e.to_string())?;
-            
-            client.put_object()
-                .bucket(&bucket)
-                .key(path)
-                .body(body)
-                .send()
-                .await
-                .map_err(|e| e.to_string())?;
-            
-            files_uploaded += 1;
+
+        let result = if upload {
+            mirror_file(&local_path, &icloud_path)
+        } else {
+            snapshot.save(path).and_then(|_| mirror_file(&icloud_path, &local_path))
+        };
+        match result {
+            Ok(()) if upload => files_uploaded += 1,
+            Ok(()) => files_downloaded += 1,
+            Err(e) => failures.push(format!("{}: {}", path, e)),
         }
     }
-    
-    // Download new remote files
-    for (path, _) in &remote_files {
-        if !local_files.contains_key(path) {
-            let result = client.get_object()
-                .bucket(&bucket)
-                .key(path)
-                .send()
-                .await
-                .map_err(|e| e.to_string())?;
-            
-            let data = result.body.collect().await.map_err(|e| e.to_string())?;
-            let full_path = base_path.join(path);
-            
-            if let Some(parent) = full_path.parent() {
-                fs::create_dir_all(parent).map_err(|e| e.to_string())?;
-            }
-            
-            fs::write(&full_path, data.into_bytes()).map_err(|e| e.to_string())?;
-            files_downloaded += 1;
-        }
+
+    save_sync_manifest(&base_path, "icloud", paths)?;
+    snapshot.finish()?;
+
+    let mut message = format!("iCloud Drive sync complete: {} uploaded, {} downloaded", files_uploaded, files_downloaded);
+    if files_deleted > 0 {
+        message.push_str(&format!(", {} deleted", files_deleted));
     }
-    
-    Ok(SyncStatus {
-        success: true,
-        message: format!("Sync complete: {} uploaded, {} downloaded", files_uploaded, files_downloaded),
+    append_sync_failures(&mut message, &failures);
+    finish_sync(&notes_path, SyncStatus {
+        success: failures.is_empty(),
+        message,
         files_uploaded,
         files_downloaded,
         conflicts,
     })
 }
 
+// An on/off credential. The settings form saves "true", but a hand-edited config may
+// hold a JSON boolean.
+fn credential_flag(credentials: &serde_json::Value, key: &str) -> bool {
+    credentials[key].as_bool().unwrap_or(false) || credentials[key] == "true"
+}
+
+// Runs whichever provider the vault's saved SyncConfig names, with the credentials
+// stored there. Only the encryption passphrase, which is never saved, is passed in.
 #[tauri::command]
-async fn sync_to_dropbox(
-    access_token: String,
-    notes_path: String,
-) -> Result<SyncStatus, String> {
-    let client = reqwest::Client::new();
-    let base_path = PathBuf::from(&notes_path);
-    
-    let mut files_uploaded = 0;
-    let mut files_downloaded = 0;
-    let conflicts = Vec::new();
-    
-    // List local files
-    for entry in WalkDir::new(&base_path)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.path().is_file())
-    {
-        let path = entry.path();
-        if path.file_name().map(|n| n.to_string_lossy().starts_with('.')).unwrap_or(false) {
+async fn run_sync(
+    base_path: String,
+    passphrase: Option<String>,
+    sync: tauri::State<'_, SyncControl>,
+) -> Result<SyncStatus, AzimuthError> {
+    let config = load_sync_config(base_path.clone())?.ok_or("Sync isn't set up for this vault")?;
+    if !config.enabled {
+        return Err(AzimuthError::Other("Sync is turned off for this vault".to_string()));
+    }
+    let credentials = &config.credentials;
+    let credential = |key: &str| -> Result<String, String> {
+        credentials[key].as_str().map(|s| s.to_string()).ok_or(format!("Missing credential: {}", key))
+    };
+    let optional = |key: &str| credentials[key].as_str().filter(|s| !s.is_empty()).map(|s| s.to_string());
+    let propagate_deletions = Some(config.propagate_deletions);
+    let max_bytes_per_sec = config.max_bytes_per_sec;
+    let notes_path = base_path.clone();
+
+    let status = match config.provider.as_str() {
+        "s3" => {
+            sync_to_s3(
+                credential("bucket")?, credential("region")?, credential("accessKey")?, credential("secretKey")?,
+                optional("endpointUrl"), Some(credential_flag(credentials, "forcePathStyle")), notes_path, propagate_deletions, passphrase, max_bytes_per_sec, sync,
+            ).await?
+        }
+        "dropbox" => sync_to_dropbox(credential("accessToken")?, notes_path, propagate_deletions, passphrase, max_bytes_per_sec, sync).await?,
+        "onedrive" => sync_to_onedrive(credential("accessToken")?, notes_path, propagate_deletions, passphrase, max_bytes_per_sec, sync).await?,
+        "googledrive" => sync_to_google_drive(credential("accessToken")?, notes_path, propagate_deletions, passphrase, max_bytes_per_sec, sync).await?,
+        "webdav" => {
+            sync_to_webdav(
+                credential("url")?, credential("username")?, credential("password")?,
+                notes_path, propagate_deletions, passphrase, max_bytes_per_sec, sync,
+            ).await?
+        }
+        "git" => {
+            let git_credentials = GitCredentials {
+                username: optional("username"),
+                password: optional("password"),
+                ssh_key_path: optional("sshKeyPath"),
+                ssh_passphrase: None,
+            };
+            let branch = optional("branch").unwrap_or_else(|| "main".to_string());
+            sync_to_git(credential("remoteUrl")?, branch, notes_path, git_credentials, max_bytes_per_sec, sync).await?
+        }
+        "sftp" => {
+            let port = match optional("port") {
+                Some(port) => port.parse().map_err(|_| AzimuthError::InvalidInput(format!("Invalid SFTP port: {}", port)))?,
+                None => 22,
+            };
+            let auth = match optional("keyPath") {
+                Some(key_path) => SftpAuth::PrivateKey { key_path, passphrase: optional("keyPassphrase") },
+                None => SftpAuth::Password { password: credential("password")? },
+            };
+            sync_to_sftp(
+                credential("host")?, port, credential("username")?, auth, credential("remoteDir")?,
+                notes_path, propagate_deletions, passphrase, max_bytes_per_sec, sync,
+            ).await?
+        }
+        "icloud" => sync_to_icloud(notes_path, propagate_deletions, sync).await?,
+        provider => return Err(AzimuthError::InvalidInput(format!("Unknown provider: {}", provider))),
+    };
+
+    Ok(status)
+}
+
+// A sync's plan without its side effects. Built from the remote listing and the
+// manifest of the last sync, so it can only tell a file changed, not how.
+fn plan_sync_preview(
+    manifest: &SyncManifest,
+    local: &HashMap<String, String>,
+    remote: &HashMap<String, chrono::DateTime<chrono::Utc>>,
+    propagate_deletions: bool,
+) -> SyncPreview {
+    let synced_at = manifest.synced_at.as_deref()
+        .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+        .map(|t| t.with_timezone(&chrono::Utc));
+    let planned = |path: &String, change: &str| PlannedChange { path: path.clone(), change: change.to_string() };
+
+    let mut preview = SyncPreview::default();
+    let deletions = if propagate_deletions {
+        plan_deletions(manifest, local, remote)
+    } else {
+        DeletionPlan::default()
+    };
+    preview.remote.extend(deletions.delete_remote.iter().map(|path| planned(path, "deleted")));
+    preview.local.extend(deletions.delete_local.iter().map(|path| planned(path, "deleted")));
+
+    let mut paths: Vec<&String> = local.keys().chain(remote.keys()).collect();
+    paths.sort();
+    paths.dedup();
+    for path in paths {
+        if deletions.delete_remote.contains(path) || deletions.delete_local.contains(path) {
             continue;
         }
-        
-        let relative = path.strip_prefix(&base_path).unwrap().to_string_lossy().to_string();
-        let dropbox_path = format!("/Azimuth/{}", relative);
-        
-        let content = fs::read(path).map_err(|e| e.to_string())?;
-        
-        let response = client.post("https://content.dropboxapi.com/2/files/upload")
-            .header("Authorization", format!("Bearer {}", access_token))
-            .header("Dropbox-API-Arg", serde_json::json!({
-                "path": dropbox_path,
-                "mode": "overwrite",
-                "autorename": false,
-                "mute": true
-            }).to_string())
-            .header("Content-Type", "application/octet-stream")
-            .body(content)
-            .send()
-            .await
-            .map_err(|e| e.to_string())?;
-        
-        if response.status().is_success() {
-            files_uploaded += 1;
+        match (local.get(path), remote.get(path)) {
+            (Some(_), None) => preview.remote.push(planned(path, "new")),
+            (None, Some(_)) => preview.local.push(planned(path, "new")),
+            (Some(local_hash), Some(remote_modified)) => {
+                // A file the manifest doesn't know counts as changed locally, as the
+                // providers upload over the remote copy on a first sync
+                let local_changed = manifest.files.get(path) != Some(local_hash);
+                let remote_changed = synced_at.is_some_and(|synced_at| *remote_modified > synced_at);
+                match (local_changed, remote_changed) {
+                    (true, true) => preview.conflicts.push(path.clone()),
+                    (true, false) => preview.remote.push(planned(path, "modified")),
+                    (false, true) => preview.local.push(planned(path, "modified")),
+                    (false, false) => {}
+                }
+            }
+            (None, None) => {}
         }
     }
-    
-    // List and download remote files
-    let list_response = client.post("https://api.dropboxapi.com/2/files/list_folder")
+    preview
+}
+
+// Dry run of run_sync: what it would change on each side, with nothing written
+#[tauri::command]
+async fn preview_sync(base_path: String) -> Result<SyncPreview, AzimuthError> {
+    let config = load_sync_config(base_path.clone())?.ok_or("Sync isn't set up for this vault")?;
+    if matches!(config.provider.as_str(), "git" | "sftp") {
+        return Err(AzimuthError::Other(format!("Sync preview isn't available for {}", config.provider)));
+    }
+
+    plan_provider_sync(&base_path, &config.provider, &config.credentials, config.propagate_deletions).await
+}
+
+// The vault and a fresh remote listing, compared against the manifest of the last
+// sync with `provider`
+async fn plan_provider_sync(
+    base_path: &str,
+    provider: &str,
+    credentials: &serde_json::Value,
+    propagate_deletions: bool,
+) -> Result<SyncPreview, AzimuthError> {
+    let ignored = load_sync_ignore(Path::new(base_path));
+    let local = local_sync_hashes(base_path);
+    let remote = list_remote_modified(provider, credentials).await?
+        .into_iter()
+        .filter(|(path, _)| {
+            !is_sync_ignored(&ignored, Path::new(path), false) && !path.split('/').any(|segment| segment.starts_with('.'))
+        })
+        .collect();
+    let manifest = load_sync_manifest(Path::new(base_path), provider);
+    Ok(plan_sync_preview(&manifest, &local, &remote, propagate_deletions))
+}
+
+async fn find_google_drive_folder(client: &reqwest::Client, access_token: &str) -> Result<Option<String>, AzimuthError> {
+    let search_response = client.get("https://www.googleapis.com/drive/v3/files")
         .header("Authorization", format!("Bearer {}", access_token))
-        .header("Content-Type", "application/json")
-        .json(&serde_json::json!({
-            "path": "/Azimuth",
-            "recursive": true
-        }))
+        .query(&[
+            ("q", "name='Azimuth' and mimeType='application/vnd.google-apps.folder' and trashed=false"),
+            ("fields", "files(id,name)")
+        ])
         .send()
-        .await
-        .map_err(|e| e.to_string())?;
-    
-    if list_response.status().is_success() {
-        let list_data: serde_json::Value = list_response.json().await.map_err(|e| e.to_string())?;
-        
-        if let Some(entries) = list_data["entries"].as_array() {
-            for entry in entries {
-                if entry[".tag"] == "file" {
-                    let remote_path = entry["path_display"].as_str().unwrap_or("");
-                    let relative = remote_path.strip_prefix("/Azimuth/").unwrap_or(remote_path);
-                    let local_path = base_path.join(relative);
-                    
-                    if !local_path.exists() {
-                        let download_response = client.post("https://content.dropboxapi.com/2/files/download")
-                            .header("Authorization", format!("Bearer {}", access_token))
-                            .header("Dropbox-API-Arg", serde_json::json!({
-                                "path": remote_path
-                            }).to_string())
-                            .send()
-                            .await
-                            .map_err(|e| e.to_string())?;
-                        
-                        if download_response.status().is_success() {
-                            let content = download_response.bytes().await.map_err(|e| e.to_string())?;
-                            if let Some(parent) = local_path.parent() {
-                                fs::create_dir_all(parent).map_err(|e| e.to_string())?;
-                            }
-                            fs::write(&local_path, content).map_err(|e| e.to_string())?;
-                            files_downloaded += 1;
-                        }
+        .await?;
+
+    if !search_response.status().is_success() {
+        let body = search_response.text().await.unwrap_or_default();
+        return Err(AzimuthError::Network(format!("Failed to search for folder: {}", body)));
+    }
+
+    let search_data: serde_json::Value = search_response.json().await?;
+    Ok(search_data["files"]
+        .as_array()
+        .and_then(|files| files.first())
+        .and_then(|folder| folder["id"].as_str())
+        .map(|id| id.to_string()))
+}
+
+fn parse_remote_time(value: &serde_json::Value) -> Option<chrono::DateTime<chrono::Utc>> {
+    value.as_str()
+        .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+        .map(|t| t.with_timezone(&chrono::Utc))
+}
+
+// Cheap remote listing: relative path and last-modified time of every remote file
+async fn list_remote_modified(
+    provider: &str,
+    credentials: &serde_json::Value,
+) -> Result<Vec<(String, chrono::DateTime<chrono::Utc>)>, AzimuthError> {
+    let credential = |key: &str| -> Result<String, String> {
+        credentials[key].as_str().map(|s| s.to_string()).ok_or(format!("Missing credential: {}", key))
+    };
+    let client = reqwest::Client::new();
+    let mut remote = Vec::new();
+
+    match provider {
+        "s3" => {
+            let bucket = credential("bucket")?;
+            let endpoint_url = credentials["endpointUrl"].as_str();
+            let force_path_style = credential_flag(credentials, "forcePathStyle");
+            let s3 = s3_client(credential("region")?, &credential("accessKey")?, &credential("secretKey")?, endpoint_url, force_path_style);
+            for obj in s3_list_objects(&s3, &bucket).await? {
+                if let (Some(key), Some(modified)) = (obj.key(), obj.last_modified())
+                    && let Some(modified) = chrono::DateTime::from_timestamp(modified.secs(), 0)
+                {
+                    remote.push((key.to_string(), modified));
+                }
+            }
+        }
+        "dropbox" => {
+            let mut files = HashMap::new();
+            fetch_dropbox_changes(&client, &credential("accessToken")?, None, &mut files).await?;
+            remote.extend(files.into_iter().filter_map(|(path, file)| {
+                Some((path, parse_remote_time(&serde_json::Value::String(file.modified))?))
+            }));
+        }
+        "onedrive" => {
+            let files = onedrive_list_files(&client, &credential("accessToken")?).await?.unwrap_or_default();
+            remote.extend(files.into_iter().filter_map(|(path, item)| {
+                Some((path, parse_remote_time(&item["lastModifiedDateTime"])?))
+            }));
+        }
+        "googledrive" => {
+            let access_token = credential("accessToken")?;
+            let folder_id = match find_google_drive_folder(&client, &access_token).await? {
+                Some(id) => id,
+                None => return Ok(remote),
+            };
+
+            // Walk the folder tree under the same rules as the sync
+            let mut pending = vec![(String::new(), folder_id)];
+            while let Some((dir, id)) = pending.pop() {
+                for (name, item) in gdrive_list_children(&client, &access_token, &id).await? {
+                    if !gdrive_safe_name(&name) || name.starts_with('.') {
+                        continue;
+                    }
+                    let path = if dir.is_empty() { name } else { format!("{}/{}", dir, name) };
+                    if item.mime == GDRIVE_FOLDER_MIME {
+                        pending.push((path, item.id));
+                    } else if !item.mime.starts_with("application/vnd.google-apps.")
+                        && let Some(modified) = item.modified
+                    {
+                        remote.push((path, modified));
                     }
                 }
             }
         }
+        "webdav" => {
+            let files = list_webdav_files(&client, &credential("url")?, &credential("username")?, &credential("password")?).await?;
+            remote.extend(files.into_iter().filter_map(|(path, modified)| modified.map(|modified| (path, modified))));
+        }
+        "icloud" => {
+            let files = icloud_files(&icloud_sync_dir()?, &ignore::gitignore::Gitignore::empty());
+            remote.extend(files.into_iter().map(|(path, (modified, _))| (path, modified)));
+        }
+        _ => return Err(AzimuthError::InvalidInput(format!("Unknown provider: {}", provider))),
     }
-    
-    Ok(SyncStatus {
-        success: true,
-        message: format!("Dropbox sync complete: {} uploaded, {} downloaded", files_uploaded, files_downloaded),
-        files_uploaded,
-        files_downloaded,
-        conflicts,
+
+    Ok(remote)
+}
+
+// Counts what changed on each side since the last sync with `provider`, from its
+// manifest, so the UI can warn before an auto-sync overwrites edits made on both sides
+#[tauri::command]
+async fn sync_preflight(
+    base_path: String,
+    provider: String,
+    credentials: serde_json::Value,
+) -> Result<SyncPreflight, AzimuthError> {
+    let config = load_sync_config(base_path.clone())?;
+    let last_sync = config.as_ref().and_then(|config| config.last_sync.clone());
+    let propagate_deletions = config.is_some_and(|config| config.propagate_deletions);
+
+    // A conflict is a change on both sides
+    let preview = plan_provider_sync(&base_path, &provider, &credentials, propagate_deletions).await?;
+    let local_changes = preview.remote.len() + preview.conflicts.len();
+    let remote_changes = preview.local.len() + preview.conflicts.len();
+
+    let status = match (local_changes > 0, remote_changes > 0) {
+        (false, false) => "up_to_date",
+        (true, false) => "local_ahead",
+        (false, true) => "remote_ahead",
+        (true, true) => "diverged",
+    };
+
+    Ok(SyncPreflight {
+        status: status.to_string(),
+        local_changes,
+        remote_changes,
+        last_sync,
     })
 }
 
+// Share links for notes that have already been synced. `remote_path` is relative
+// to the vault, the same as the paths the sync commands upload.
 #[tauri::command]
-async fn sync_to_onedrive(
-    access_token: String,
-    notes_path: String,
-) -> Result<SyncStatus, String> {
+async fn create_share_link(
+    provider: String,
+    credentials: serde_json::Value,
+    remote_path: String,
+) -> Result<String, AzimuthError> {
     let client = reqwest::Client::new();
-    let base_path = PathBuf::from(&notes_path);
-    
-    let mut files_uploaded = 0;
-    let mut files_downloaded = 0;
-    let conflicts = Vec::new();
-    
-    // Upload local files
-    for entry in WalkDir::new(&base_path)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.path().is_file())
-    {
-        let path = entry.path();
-        if path.file_name().map(|n| n.to_string_lossy().starts_with('.')).unwrap_or(false) {
-            continue;
+    let access_token = credentials["accessToken"]
+        .as_str()
+        .ok_or("Missing access token")?
+        .to_string();
+    let relative = remote_path.trim_start_matches('/').replace('\\', "/");
+
+    match provider.as_str() {
+        "dropbox" => {
+            let dropbox_path = format!("/Azimuth/{}", relative);
+            let response = client.post("https://api.dropboxapi.com/2/sharing/create_shared_link_with_settings")
+                .header("Authorization", format!("Bearer {}", access_token))
+                .header("Content-Type", "application/json")
+                .json(&serde_json::json!({
+                    "path": dropbox_path,
+                    "settings": { "requested_visibility": "public" }
+                }))
+                .send()
+                .await?;
+
+            let status = response.status();
+            let data: serde_json::Value = response.json().await?;
+            if status.is_success() {
+                return data["url"].as_str().map(|s| s.to_string()).ok_or(AzimuthError::Network("Dropbox did not return a link".to_string()));
+            }
+
+            // Dropbox refuses to create a second link but reports the existing one
+            if let Some(url) = data["error"]["shared_link_already_exists"]["metadata"]["url"].as_str() {
+                return Ok(url.to_string());
+            }
+            Err(AzimuthError::Network(format!("Failed to create Dropbox share link: {}", data)))
         }
-        
-        let relative = path.strip_prefix(&base_path).unwrap().to_string_lossy().to_string();
-        let onedrive_path = format!("/drive/root:/Azimuth/{}:/content", relative);
-        
-        let content = fs::read(path).map_err(|e| e.to_string())?;
-        
-        let response = client.put(format!("https://graph.microsoft.com/v1.0{}", onedrive_path))
-            .header("Authorization", format!("Bearer {}", access_token))
-            .header("Content-Type", "application/octet-stream")
-            .body(content)
-            .send()
-            .await
-            .map_err(|e| e.to_string())?;
-        
-        if response.status().is_success() {
-            files_uploaded += 1;
+        "onedrive" => {
+            let response = client.post(format!(
+                    "https://graph.microsoft.com/v1.0/drive/root:/Azimuth/{}:/createLink",
+                    relative
+                ))
+                .header("Authorization", format!("Bearer {}", access_token))
+                .json(&serde_json::json!({
+                    "type": "view",
+                    "scope": "anonymous"
+                }))
+                .send()
+                .await?;
+
+            let status = response.status();
+            let data: serde_json::Value = response.json().await?;
+            if !status.is_success() {
+                return Err(AzimuthError::Network(format!("Failed to create OneDrive share link: {}", data)));
+            }
+            data["link"]["webUrl"].as_str().map(|s| s.to_string()).ok_or(AzimuthError::Network("OneDrive did not return a link".to_string()))
         }
-    }
-    
-    // List remote files
-    let list_response = client.get("https://graph.microsoft.com/v1.0/drive/root:/Azimuth:/children")
-        .header("Authorization", format!("Bearer {}", access_token))
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
-    
-    if list_response.status().is_success() {
-        let list_data: serde_json::Value = list_response.json().await.map_err(|e| e.to_string())?;
-        
-        if let Some(items) = list_data["value"].as_array() {
-            for item in items {
-                if item["file"].is_object() {
-                    let name = item["name"].as_str().unwrap_or("");
-                    let local_path = base_path.join(name);
-                    
-                    if !local_path.exists() {
-                        if let Some(download_url) = item["@microsoft.graph.downloadUrl"].as_str() {
-                            let download_response = client.get(download_url)
-                                .send()
-                                .await
-                                .map_err(|e| e.to_string())?;
-                            
-                            if download_response.status().is_success() {
-                                let content = download_response.bytes().await.map_err(|e| e.to_string())?;
-                                fs::write(&local_path, content).map_err(|e| e.to_string())?;
-                                files_downloaded += 1;
-                            }
-                        }
-                    }
+        "googledrive" => {
+            // Google Drive sync mirrors the vault's folders under the Azimuth folder, so
+            // follow the path down one folder at a time
+            let mut folder_id = find_google_drive_folder(&client, &access_token)
+                .await?
+                .ok_or(AzimuthError::NotFound("Azimuth folder not found in Google Drive".to_string()))?;
+
+            let mut file = serde_json::Value::Null;
+            let segments: Vec<&str> = relative.split('/').filter(|s| !s.is_empty()).collect();
+            for (i, segment) in segments.iter().enumerate() {
+                let is_file = i == segments.len() - 1;
+                let mut query = format!("name='{}' and '{}' in parents and trashed=false", segment.replace('\'', "\\'"), folder_id);
+                if !is_file {
+                    query.push_str(&format!(" and mimeType='{}'", GDRIVE_FOLDER_MIME));
+                }
+                let search_response = client.get("https://www.googleapis.com/drive/v3/files")
+                    .header("Authorization", format!("Bearer {}", access_token))
+                    .query(&[
+                        ("q", query),
+                        ("fields", "files(id,name,webViewLink)".to_string()),
+                    ])
+                    .send()
+                    .await?;
+
+                let search_data: serde_json::Value = search_response.json().await?;
+                let found = search_data["files"]
+                    .as_array()
+                    .and_then(|files| files.first())
+                    .ok_or(AzimuthError::NotFound(format!("File not found in Google Drive: {}", remote_path)))?;
+                if is_file {
+                    file = found.clone();
+                } else {
+                    folder_id = found["id"].as_str().ok_or(AzimuthError::Network("Google Drive did not return a folder id".to_string()))?.to_string();
                 }
             }
-        }
-    }
-    
-    Ok(SyncStatus {
-        success: true,
-        message: format!("OneDrive sync complete: {} uploaded, {} downloaded", files_uploaded, files_downloaded),
-        files_uploaded,
-        files_downloaded,
-        conflicts,
-    })
-}
+            let file_id = file["id"].as_str().ok_or(AzimuthError::Network("Google Drive did not return a file id".to_string()))?;
 
-#[tauri::command]
-async fn sync_to_google_drive(
-    access_token: String,
-    notes_path: String,
-) -> Result<SyncStatus, String> {
-    let client = reqwest::Client::new();
-    let base_path = PathBuf::from(&notes_path);
-    
-    let mut files_uploaded = 0;
-    let files_downloaded = 0;
-    let conflicts = Vec::new();
-    
-    // Find or create Azimuth folder
-    let search_response = client.get("https://www.googleapis.com/drive/v3/files")
-        .header("Authorization", format!("Bearer {}", access_token))
-        .query(&[
-            ("q", "name='Azimuth' and mimeType='application/vnd.google-apps.folder' and trashed=false"),
-            ("fields", "files(id,name)")
-        ])
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
-    
-    let search_data: serde_json::Value = search_response.json().await.map_err(|e| e.to_string())?;
-    
-    let folder_id = if let Some(files) = search_data["files"].as_array() {
-        if let Some(folder) = files.first() {
-            folder["id"].as_str().unwrap_or("").to_string()
-        } else {
-            // Create folder
-            let create_response = client.post("https://www.googleapis.com/drive/v3/files")
+            let permission_response = client.post(format!("https://www.googleapis.com/drive/v3/files/{}/permissions", file_id))
                 .header("Authorization", format!("Bearer {}", access_token))
                 .json(&serde_json::json!({
-                    "name": "Azimuth",
-                    "mimeType": "application/vnd.google-apps.folder"
+                    "role": "reader",
+                    "type": "anyone"
                 }))
                 .send()
-                .await
-                .map_err(|e| e.to_string())?;
-            
-            let create_data: serde_json::Value = create_response.json().await.map_err(|e| e.to_string())?;
-            create_data["id"].as_str().unwrap_or("").to_string()
-        }
-    } else {
-        return Err("Failed to search for folder".to_string());
-    };
-    
-    // Upload local files using simple upload (for files < 5MB)
-    for entry in WalkDir::new(&base_path)
-        .max_depth(1)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.path().is_file())
-    {
-        let path = entry.path();
-        if path.file_name().map(|n| n.to_string_lossy().starts_with('.')).unwrap_or(false) {
-            continue;
-        }
-        
-        let file_name = path.file_name().unwrap().to_string_lossy().to_string();
-        let content = fs::read(path).map_err(|e| e.to_string())?;
-        
-        // Use simple upload API
-        let upload_url = format!(
-            "https://www.googleapis.com/upload/drive/v3/files?uploadType=media&name={}&parents={}",
-            urlencoding::encode(&file_name),
-            folder_id
-        );
-        
-        let response = client.post(&upload_url)
-            .header("Authorization", format!("Bearer {}", access_token))
-            .header("Content-Type", "application/octet-stream")
-            .body(content)
-            .send()
-            .await
-            .map_err(|e| e.to_string())?;
-        
-        if response.status().is_success() {
-            files_uploaded += 1;
+                .await?;
+
+            if !permission_response.status().is_success() {
+                let body = permission_response.text().await.unwrap_or_default();
+                return Err(AzimuthError::Network(format!("Failed to share Google Drive file: {}", body)));
+            }
+            file["webViewLink"].as_str().map(|s| s.to_string()).ok_or(AzimuthError::Network("Google Drive did not return a link".to_string()))
         }
+        _ => Err(AzimuthError::Network(format!("Share links are not supported for provider: {}", provider))),
     }
-    
-    Ok(SyncStatus {
-        success: true,
-        message: format!("Google Drive sync complete: {} uploaded, {} downloaded", files_uploaded, files_downloaded),
-        files_uploaded,
-        files_downloaded,
-        conflicts,
-    })
 }
 
 #[tauri::command]
-fn resolve_conflict(base_path: String, resolution: ConflictResolution) -> Result<(), String> {
-    let file_path = PathBuf::from(&base_path).join(&resolution.file_path);
-    let conflict_path = file_path.with_extension("conflict");
+fn resolve_conflict(base_path: String, resolution: ConflictResolution) -> Result<(), AzimuthError> {
+    let file_path = safe_join_relative(Path::new(&base_path), &resolution.file_path)?;
+    let conflict_path = conflict_path(&file_path);
     
     match resolution.resolution.as_str() {
         "keep_local" => {
             if conflict_path.exists() {
-                fs::remove_file(&conflict_path).map_err(|e| e.to_string())?;
+                fs::remove_file(&conflict_path)?;
             }
         }
         "keep_remote" => {
             if conflict_path.exists() {
-                fs::rename(&conflict_path, &file_path).map_err(|e| e.to_string())?;
+                fs::rename(&conflict_path, &file_path)?;
             }
         }
         "keep_both" => {
@@ -1250,34 +9382,62 @@ fn resolve_conflict(base_path: String, resolution: ConflictResolution) -> Result
             let new_name = format!("{}_conflict.{}", stem, ext);
             let new_path = file_path.with_file_name(new_name);
             if conflict_path.exists() {
-                fs::rename(&conflict_path, &new_path).map_err(|e| e.to_string())?;
+                fs::rename(&conflict_path, &new_path)?;
             }
         }
-        _ => return Err("Invalid resolution type".to_string()),
+        _ => return Err(AzimuthError::InvalidInput("Invalid resolution type".to_string())),
+    }
+
+    // The remote version has now been seen, so only what is local counts as a change
+    let base = PathBuf::from(&base_path);
+    let mut sync_state = load_sync_state(&base);
+    if let Some(state) = sync_state.get_mut(&resolution.file_path)
+        && let Some(etag) = state.conflict_etag.take()
+    {
+        state.remote_etag = etag;
+        if resolution.resolution == "keep_remote" {
+            state.local_hash = get_file_hash(&file_path)?;
+        }
+        save_sync_state(&base, &sync_state)?;
     }
     
     Ok(())
 }
 
 #[tauri::command]
-fn save_sync_config(base_path: String, config: SyncConfig) -> Result<(), String> {
+fn save_sync_config(base_path: String, config: SyncConfig) -> Result<(), AzimuthError> {
     let config_path = PathBuf::from(&base_path).join(".sync_config.json");
-    let json = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
-    fs::write(&config_path, json).map_err(|e| e.to_string())?;
+    let json = serde_json::to_string_pretty(&config)?;
+    fs::write(&config_path, json)?;
     Ok(())
 }
 
 #[tauri::command]
-fn load_sync_config(base_path: String) -> Result<Option<SyncConfig>, String> {
+fn load_sync_config(base_path: String) -> Result<Option<SyncConfig>, AzimuthError> {
     let config_path = PathBuf::from(&base_path).join(".sync_config.json");
     if !config_path.exists() {
         return Ok(None);
     }
-    let content = fs::read_to_string(&config_path).map_err(|e| e.to_string())?;
-    let config: SyncConfig = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+    let content = fs::read_to_string(&config_path)?;
+    let config: SyncConfig = serde_json::from_str(&content)?;
     Ok(Some(config))
 }
 
+// Stamps the vault's sync config with the time of a successful sync
+fn record_last_sync(base_path: &str) -> Result<(), AzimuthError> {
+    if let Some(mut config) = load_sync_config(base_path.to_string())? {
+        config.last_sync = Some(chrono::Utc::now().to_rfc3339());
+        save_sync_config(base_path.to_string(), config)?;
+    }
+    Ok(())
+}
+
+// RFC 3339 time of the last successful sync, if the vault has ever synced
+#[tauri::command]
+fn get_last_sync(base_path: String) -> Result<Option<String>, AzimuthError> {
+    Ok(load_sync_config(base_path)?.and_then(|config| config.last_sync))
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -1288,6 +9448,8 @@ pub fn run() {
         .plugin(tauri_plugin_http::init())
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_window_state::Builder::new().build())
+        .manage(SyncControl::default())
+        .manage(NoteWatcher::default())
         .setup(|app| {
             use tauri::menu::{MenuBuilder, MenuItemBuilder, SubmenuBuilder, PredefinedMenuItem};
             
@@ -1348,7 +9510,20 @@ pub fn run() {
                 .build()?;
             
             app.set_menu(menu)?;
-            
+
+            let handle = app.handle().clone();
+            std::thread::spawn(move || watch_reminders(handle));
+
+            if let Ok(settings) = get_notes_dir().and_then(load_settings)
+                && let Some(window) = app.get_webview_window("main")
+            {
+                let _ = window.set_theme(window_theme(&settings.theme));
+            }
+
+            if let Ok(notes_dir) = get_notes_dir() {
+                let _ = start_watching(app.handle().clone(), notes_dir, app.state::<NoteWatcher>());
+            }
+
             Ok(())
         })
         .on_menu_event(|app, event| {
@@ -1369,39 +9544,127 @@ pub fn run() {
             get_notes_dir,
             set_notes_dir,
             list_notebooks,
+            get_scan_settings,
+            set_scan_settings,
+            get_notebook_size,
+            notebook_stats,
             list_notebooks_async,
+            list_notebook_tree,
+            validate_notebook_name,
             create_notebook,
             list_notes,
+            list_notes_page,
+            list_note_metadata,
             save_note,
+            apply_frontmatter_defaults,
+            find_duplicate_titles,
+            dedupe_titles,
             delete_note,
+            list_note_versions,
+            restore_note_version,
+            list_trash,
+            restore_from_trash,
+            empty_trash,
             rename_note,
             move_note,
+            get_note_order,
+            list_templates,
+            create_note_from_template,
+            move_note_in_order,
+            set_note_position,
+            get_word_count_delta,
+            get_daily_word_count,
             read_note,
+            read_note_safe,
+            clean_note_typography,
             read_file_binary,
             save_attachment,
+            repair_attachment_layout,
+            find_orphaned_attachments,
+            delete_orphaned_attachments,
+            render_markdown,
+            note_stats,
             get_attachment_path,
+            get_attachment_thumbnail,
             list_attachments,
             import_folder,
+            export_notebook_zip,
+            import_zip,
+            import_enex,
+            import_obsidian_vault,
+            import_azimuth_backup,
+            export_to_obsidian,
+            export_note,
+            concatenate_notebook,
             is_directory,
             move_notebook,
+            delete_notebook,
+            archive_notebook,
+            unarchive_notebook,
             // Settings
             load_settings,
             save_settings,
+            set_auto_save,
+            get_auto_save,
             // Favorites
             toggle_favorite,
+            toggle_favorite_v2,
             get_favorites,
+            // Pinned notes
+            toggle_pinned_note,
+            get_pinned_notes,
             // Tags
             set_note_tags,
             get_note_tags,
             get_all_tags,
+            get_tag_counts,
+            get_tag_tree,
+            suggest_tags,
+            sync_inline_tags,
             get_notes_by_tag,
+            rename_tag,
+            delete_tag,
+            merge_tags,
+            // Stable note ids
+            get_or_assign_note_id,
+            set_note_metadata,
+            get_note_metadata,
+            // Reminders
+            set_reminder,
+            clear_reminder,
+            get_due_reminders,
+            // File watching
+            start_watching,
+            stop_watching,
             // Search
             search_notes,
+            search_notes_async,
+            replace_in_notes,
+            save_search_query,
+            get_search_history,
+            rebuild_search_index,
+            collect_external_links,
+            get_backlinks,
+            get_outgoing_links,
             // Sync
             sync_to_s3,
             sync_to_dropbox,
             sync_to_onedrive,
             sync_to_google_drive,
+            sync_to_webdav,
+            sync_to_git,
+            sync_to_sftp,
+            sync_to_icloud,
+            run_sync,
+            get_last_sync,
+            preview_sync,
+            snapshot_vault,
+            list_snapshots,
+            restore_snapshot,
+            cancel_sync,
+            sync_preflight,
+            vault_fingerprint,
+            create_share_link,
             resolve_conflict,
             save_sync_config,
             load_sync_config,
@@ -1409,3 +9672,835 @@ pub fn run() {
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_vault() -> (tempfile::TempDir, String) {
+        let dir = tempfile::tempdir().unwrap();
+        let base_path = dir.path().to_string_lossy().to_string();
+        (dir, base_path)
+    }
+
+    #[test]
+    fn word_count_delta_against_earliest_version_after_baseline() {
+        let (dir, _) = temp_vault();
+        let note = dir.path().join("draft.md");
+        fs::write(&note, "one two three four five six").unwrap();
+
+        let baseline = chrono::Utc::now() + chrono::Duration::seconds(1);
+        let history_dir = note_history_dir(dir.path(), "draft.md");
+        fs::create_dir_all(&history_dir).unwrap();
+        let version = |offset: i64, content: &str| {
+            let time = baseline + chrono::Duration::seconds(offset);
+            fs::write(history_dir.join(format!("{}.bak", time.format(NOTE_VERSION_FORMAT))), content).unwrap();
+        };
+        version(-60, "one");
+        version(60, "one two");
+        version(120, "one two three four");
+
+        let delta = get_word_count_delta(note.to_string_lossy().to_string(), baseline.to_rfc3339()).unwrap();
+        assert_eq!(delta, 4);
+    }
+
+    #[test]
+    fn word_count_delta_is_zero_without_later_versions() {
+        let (dir, _) = temp_vault();
+        let note = dir.path().join("draft.md");
+        fs::write(&note, "one two three").unwrap();
+
+        let baseline = (chrono::Utc::now() + chrono::Duration::seconds(1)).to_rfc3339();
+        assert_eq!(get_word_count_delta(note.to_string_lossy().to_string(), baseline).unwrap(), 0);
+    }
+
+    #[test]
+    fn daily_word_count_sums_notes_written_today() {
+        let (dir, base_path) = temp_vault();
+        fs::create_dir_all(dir.path().join("Journal")).unwrap();
+        fs::write(dir.path().join("Journal").join("a.md"), "one two three").unwrap();
+        fs::write(dir.path().join("b.md"), "four five").unwrap();
+        fs::create_dir_all(dir.path().join(".azimuth_trash")).unwrap();
+        fs::write(dir.path().join(".azimuth_trash").join("c.md"), "ignored words here").unwrap();
+
+        let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+        // Creation times aren't available on every filesystem, in which case new notes
+        // count from their earliest content
+        if fs::metadata(dir.path().join("b.md")).and_then(|m| m.created()).is_ok() {
+            assert_eq!(get_daily_word_count(base_path.clone(), today).unwrap(), 5);
+        }
+        assert!(matches!(get_daily_word_count(base_path, "yesterday".to_string()), Err(AzimuthError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn concurrent_settings_updates_are_all_kept() {
+        let (_dir, base_path) = temp_vault();
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let base_path = base_path.clone();
+                std::thread::spawn(move || {
+                    set_note_tags(base_path.clone(), format!("note{}.md", i), vec!["draft".to_string()]).unwrap();
+                    toggle_favorite(base_path, format!("note{}.md", i)).unwrap();
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let settings = load_settings(base_path).unwrap();
+        assert_eq!(settings.tags.len(), 8);
+        assert_eq!(settings.favorites.len(), 8);
+    }
+
+    fn paths<V: Clone>(entries: &[(&str, V)]) -> HashMap<String, V> {
+        entries.iter().map(|(path, value)| (path.to_string(), value.clone())).collect()
+    }
+
+    #[test]
+    fn deletion_plan_covers_add_delete_and_modify() {
+        let synced_at = chrono::Utc::now() - chrono::Duration::hours(1);
+        let before = synced_at - chrono::Duration::minutes(5);
+        let after = synced_at + chrono::Duration::minutes(5);
+        let manifest = SyncManifest {
+            synced_at: Some(synced_at.to_rfc3339()),
+            files: paths(&[
+                ("unchanged.md", "h".to_string()),
+                ("deleted_locally.md", "h".to_string()),
+                ("deleted_remotely.md", "h".to_string()),
+                ("modified_locally_deleted_remotely.md", "h".to_string()),
+                ("deleted_locally_modified_remotely.md", "h".to_string()),
+                ("deleted_on_both.md", "h".to_string()),
+            ]),
+            ..Default::default()
+        };
+        let local = paths(&[
+            ("unchanged.md", "h".to_string()),
+            ("deleted_remotely.md", "h".to_string()),
+            ("modified_locally_deleted_remotely.md", "h2".to_string()),
+            ("added_locally.md", "x".to_string()),
+        ]);
+        let remote = paths(&[
+            ("unchanged.md", before),
+            ("deleted_locally.md", before),
+            ("deleted_locally_modified_remotely.md", after),
+            ("added_remotely.md", after),
+        ]);
+
+        let plan = plan_deletions(&manifest, &local, &remote);
+        assert_eq!(plan.delete_remote, vec!["deleted_locally.md".to_string()]);
+        assert_eq!(plan.delete_local, vec!["deleted_remotely.md".to_string()]);
+    }
+
+    #[test]
+    fn nothing_is_deleted_without_a_previous_sync() {
+        let local = paths(&[("a.md", "h".to_string())]);
+        let remote = paths(&[("b.md", chrono::Utc::now())]);
+        assert_eq!(plan_deletions(&SyncManifest::default(), &local, &remote), DeletionPlan::default());
+    }
+
+    #[test]
+    fn sync_manifest_records_files_present_locally() {
+        let (dir, _) = temp_vault();
+        fs::write(dir.path().join("a.md"), "x").unwrap();
+        save_sync_manifest(dir.path(), "dropbox", &vec!["a.md".to_string(), "missing.md".to_string()]).unwrap();
+
+        let manifest = load_sync_manifest(dir.path(), "dropbox");
+        assert_eq!(manifest.files.keys().collect::<Vec<_>>(), vec!["a.md"]);
+        assert!(manifest.synced_at.is_some());
+    }
+
+    #[test]
+    fn propfind_parses_any_prefix_and_decodes_hrefs() {
+        let body = r#"<?xml version="1.0" encoding="utf-8"?>
+<multistatus xmlns="DAV:" xmlns:x="DAV:">
+  <response>
+    <href>/dav/Azimuth/</href>
+    <propstat><prop><resourcetype><collection/></resourcetype></prop></propstat>
+  </response>
+  <x:response>
+    <x:href>https://cloud.example.com/dav/Azimuth/Tom%20%26%20Jerry/Notes%20&amp;%20Ideas.md</x:href>
+    <x:propstat><x:prop>
+      <x:resourcetype/>
+      <x:getlastmodified>Tue, 13 Oct 2026 08:30:00 GMT</x:getlastmodified>
+    </x:prop></x:propstat>
+  </x:response>
+  <response>
+    <href><![CDATA[/dav/Azimuth/Sub]]></href>
+    <propstat><prop><resourcetype><collection></collection></resourcetype></prop></propstat>
+  </response>
+</multistatus>"#;
+
+        let entries = parse_propfind(body).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0], PropfindEntry { path: "/dav/Azimuth/".to_string(), collection: true, modified: None });
+        assert_eq!(entries[1].path, "/dav/Azimuth/Tom & Jerry/Notes & Ideas.md");
+        assert!(!entries[1].collection);
+        assert_eq!(entries[1].modified.map(|t| t.to_rfc3339()).as_deref(), Some("2026-10-13T08:30:00+00:00"));
+        assert_eq!(entries[2].path, "/dav/Azimuth/Sub");
+        assert!(entries[2].collection);
+    }
+
+    #[test]
+    fn propfind_rejects_malformed_xml() {
+        assert!(parse_propfind("<multistatus><response><href>/a</response>").is_err());
+    }
+
+    #[test]
+    fn webdav_paths_that_escape_the_vault_are_skipped() {
+        let body = r#"<multistatus xmlns="DAV:">
+  <response><href>/dav/Azimuth/ok.md</href></response>
+  <response><href>/dav/Azimuth/../outside.md</href></response>
+  <response><href>/dav/Azimuth/Sub/%2E%2E/%2E%2E/outside.md</href></response>
+  <response><href>/dav/Azimuth/a%2F..%2F..%2Foutside.md</href></response>
+  <response><href>/dav/Azimuth/..%5Coutside.md</href></response>
+  <response><href>/dav/Azimuth-other/b.md</href></response>
+</multistatus>"#;
+        let relative: Vec<Option<String>> = parse_propfind(body)
+            .unwrap()
+            .iter()
+            .map(|entry| webdav_relative_path("/dav/Azimuth", &entry.path))
+            .collect();
+        assert_eq!(relative, vec![Some("ok.md".to_string()), None, None, None, None, None]);
+        assert_eq!(webdav_relative_path("/dav/Azimuth", "/dav/Azimuth/"), Some(String::new()));
+
+        let (dir, _) = temp_vault();
+        assert!(safe_join_relative(dir.path(), "../outside.md").is_err());
+        assert_eq!(safe_join_relative(dir.path(), "Sub/a.md").unwrap(), dir.path().join("Sub").join("a.md"));
+    }
+
+    #[test]
+    fn sync_cipher_round_trips_and_rejects_a_wrong_passphrase() {
+        let (dir, _) = temp_vault();
+        let cipher = SyncCipher::for_sync(dir.path(), Some("correct horse".to_string())).unwrap().unwrap();
+        let encrypted = encrypt_for_upload(Some(&cipher), b"secret note".to_vec()).unwrap();
+        assert!(encrypted.starts_with(ENCRYPTION_MAGIC));
+        assert_eq!(decrypt_download(Some(&cipher), "a.md", &encrypted).unwrap(), b"secret note");
+
+        let wrong = SyncCipher::for_sync(dir.path(), Some("battery staple".to_string())).unwrap().unwrap();
+        let err = decrypt_download(Some(&wrong), "a.md", &encrypted).unwrap_err();
+        assert!(matches!(err, AzimuthError::InvalidInput(_)) && err.to_string().contains("passphrase is wrong"), "{:?}", err);
+        assert!(decrypt_download(None, "a.md", &encrypted).is_err());
+    }
+
+    #[test]
+    fn encrypted_s3_checksum_hides_the_plain_hash() {
+        let (dir, _) = temp_vault();
+        let local_hash = hex::encode(Sha256::digest(b"secret note"));
+        assert_eq!(s3_checksum(None, &local_hash).unwrap(), local_hash);
+
+        let cipher = SyncCipher::for_sync(dir.path(), Some("correct horse".to_string())).unwrap().unwrap();
+        let stored = s3_checksum(Some(&cipher), &local_hash).unwrap();
+        assert!(!stored.contains(&local_hash));
+        assert!(s3_checksum_matches(Some(&cipher), &stored, &local_hash));
+        assert!(!s3_checksum_matches(Some(&cipher), &stored, &hex::encode(Sha256::digest(b"other"))));
+        assert!(!s3_checksum_matches(None, &stored, &local_hash));
+
+        let wrong = SyncCipher::for_sync(dir.path(), Some("battery staple".to_string())).unwrap().unwrap();
+        assert!(!s3_checksum_matches(Some(&wrong), &stored, &local_hash));
+    }
+
+    #[test]
+    fn local_sync_hashes_skip_hidden_folders() {
+        let (dir, base_path) = temp_vault();
+        for (path, content) in [
+            ("note.md", "a"),
+            ("Work/plan.md", "b"),
+            (".hidden.md", "c"),
+            (".git/config", "d"),
+            (".azimuth_templates/meeting.md", "e"),
+            ("Work/.cache/state.json", "f"),
+            (".azimuth_history/note.md/2026-01-01T00-00-00.000Z.bak", "g"),
+        ] {
+            let full_path = dir.path().join(path);
+            fs::create_dir_all(full_path.parent().unwrap()).unwrap();
+            fs::write(full_path, content).unwrap();
+        }
+
+        let mut synced: Vec<String> = local_sync_hashes(&base_path).into_keys().collect();
+        synced.sort();
+        assert_eq!(synced, vec!["Work/plan.md".to_string(), "note.md".to_string()]);
+    }
+
+    #[test]
+    fn dropbox_content_hash_matches_the_reference_hasher() {
+        // Computed independently with the block scheme of Dropbox's reference hasher: an empty
+        // file, and content spanning two full blocks and a partial one
+        assert_eq!(
+            dropbox_content_hash(b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        let content: Vec<u8> = (0..DROPBOX_BLOCK_SIZE * 2 + 1000).map(|i| (i % 251) as u8).collect();
+        assert_eq!(
+            dropbox_content_hash(&content),
+            "9cc7196d04bf8c6dbd7684e42e33bc1a6e21fd79423c52f22577a9bc3f7ce698"
+        );
+    }
+
+    #[test]
+    fn dropbox_entries_update_the_cached_listing() {
+        let mut remote = HashMap::new();
+        let changed = apply_dropbox_entries(&mut remote, &[
+            serde_json::json!({ ".tag": "file", "path_display": "/Azimuth/Work/plan.md", "content_hash": "x", "server_modified": "2026-01-01T00:00:00Z" }),
+            serde_json::json!({ ".tag": "file", "path_display": "/Azimuth/note.md", "content_hash": "y", "server_modified": "2026-01-01T00:00:00Z" }),
+            serde_json::json!({ ".tag": "folder", "path_display": "/Azimuth/Work" }),
+        ]);
+        assert_eq!(changed.len(), 2);
+
+        apply_dropbox_entries(&mut remote, &[serde_json::json!({ ".tag": "deleted", "path_display": "/Azimuth/Work" })]);
+        assert_eq!(remote.keys().collect::<Vec<_>>(), vec!["note.md"]);
+    }
+
+    #[test]
+    fn successful_sync_records_last_sync() {
+        let (remote_dir, _) = temp_vault();
+        git2::Repository::init_bare(remote_dir.path()).unwrap();
+        let remote_url = remote_dir.path().to_string_lossy().to_string();
+        let (dir, base_path) = temp_vault();
+        fs::write(dir.path().join("note.md"), "hello").unwrap();
+        let config = SyncConfig {
+            provider: "git".to_string(),
+            enabled: true,
+            credentials: serde_json::json!({ "remoteUrl": remote_url }),
+            last_sync: None,
+            propagate_deletions: false,
+            max_bytes_per_sec: None,
+        };
+        save_sync_config(base_path.clone(), config).unwrap();
+
+        let cancel = AtomicBool::new(true);
+        let status = git_sync(&remote_url, "main", &base_path, &GitCredentials::default(), None, &cancel).unwrap();
+        assert!(!status.success);
+        assert_eq!(get_last_sync(base_path.clone()).unwrap(), None);
+
+        let cancel = AtomicBool::new(false);
+        let status = git_sync(&remote_url, "main", &base_path, &GitCredentials::default(), None, &cancel).unwrap();
+        assert!(status.success, "{}", status.message);
+        let last_sync = get_last_sync(base_path).unwrap().expect("last_sync is recorded");
+        let age = chrono::Utc::now() - chrono::DateTime::parse_from_rfc3339(&last_sync).unwrap().with_timezone(&chrono::Utc);
+        assert!(age < chrono::Duration::minutes(1));
+    }
+
+    fn write_files(dir: &Path, files: &[(&str, &str)]) {
+        for (path, content) in files {
+            let full_path = dir.join(path);
+            fs::create_dir_all(full_path.parent().unwrap()).unwrap();
+            fs::write(full_path, content).unwrap();
+        }
+    }
+
+    #[test]
+    fn search_and_replace_skip_hidden_folders() {
+        let (dir, base_path) = temp_vault();
+        write_files(dir.path(), &[
+            ("Work/plan.md", "ship the draft"),
+            (".azimuth_trash/2026-01-01T00-00-00.000Z/old.md", "the draft was trashed"),
+            (".azimuth_backups/2026-01-01T00-00-00.000Z/Work/plan.md", "snapshot of the draft"),
+            (".azimuth_templates/meeting.md", "draft agenda"),
+        ]);
+
+        let page = search_notes(base_path.clone(), "draft".to_string(), None).unwrap();
+        let found: Vec<&str> = page.results.iter().map(|r| r.note_id.as_str()).collect();
+        assert_eq!(found, vec!["plan.md"]);
+
+        let summary = replace_in_notes(base_path, "draft".to_string(), "release".to_string(), None, false).unwrap();
+        assert_eq!(summary.files_changed, 1);
+        assert_eq!(fs::read_to_string(dir.path().join("Work/plan.md")).unwrap(), "ship the release");
+        assert_eq!(
+            fs::read_to_string(dir.path().join(".azimuth_trash/2026-01-01T00-00-00.000Z/old.md")).unwrap(),
+            "the draft was trashed"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.path().join(".azimuth_backups/2026-01-01T00-00-00.000Z/Work/plan.md")).unwrap(),
+            "snapshot of the draft"
+        );
+    }
+
+    #[test]
+    fn search_index_skips_hidden_folders() {
+        let (dir, _) = temp_vault();
+        write_files(dir.path(), &[("a.md", "x"), (".azimuth_trash/t/b.md", "y"), (".git/notes.txt", "z")]);
+        let files = indexable_files(dir.path());
+        assert_eq!(files.keys().collect::<Vec<_>>(), vec!["a.md"]);
+    }
+
+    #[test]
+    fn template_notes_stay_in_their_notebook() {
+        let (dir, base_path) = temp_vault();
+        write_files(dir.path(), &[(".azimuth_templates/meeting.md", "# {{title}}"), ("Work/existing.md", "x")]);
+        let notebook = dir.path().join("Work").to_string_lossy().to_string();
+
+        let note = create_note_from_template(notebook.clone(), "meeting".to_string(), "../../escape".to_string(), None).unwrap();
+        assert_eq!(note.id, "..-..-escape.md");
+        assert!(dir.path().join("Work/..-..-escape.md").is_file());
+        assert!(!Path::new(&base_path).parent().unwrap().join("escape.md").exists());
+        assert!(create_note_from_template(notebook, "../meeting".to_string(), "x".to_string(), None).is_err());
+    }
+
+    #[test]
+    fn attachments_over_the_limit_are_invalid_input() {
+        let (dir, base_path) = temp_vault();
+        fs::create_dir_all(dir.path().join("Work")).unwrap();
+        update_settings(&base_path, |settings| settings.max_attachment_bytes = Some(10)).unwrap();
+        let notebook = dir.path().join("Work").to_string_lossy().to_string();
+
+        let err = save_attachment(notebook.clone(), "note.md".to_string(), "big.bin".to_string(), STANDARD.encode([0u8; 11]), None, None)
+            .unwrap_err();
+        assert!(matches!(err, AzimuthError::InvalidInput(_)), "{:?}", err);
+        assert!(!dir.path().join("Work/note/big.bin").exists());
+        assert!(save_attachment(notebook, "note.md".to_string(), "ok.bin".to_string(), STANDARD.encode([0u8; 10]), None, None).is_ok());
+    }
+
+    #[test]
+    fn sync_preview_compares_both_sides_with_the_manifest() {
+        let synced_at = chrono::Utc::now() - chrono::Duration::hours(1);
+        let before = synced_at - chrono::Duration::minutes(5);
+        let after = synced_at + chrono::Duration::minutes(5);
+        let manifest = SyncManifest {
+            synced_at: Some(synced_at.to_rfc3339()),
+            files: paths(&[
+                ("same.md", "h".to_string()),
+                ("local_edit.md", "h".to_string()),
+                ("remote_edit.md", "h".to_string()),
+                ("both.md", "h".to_string()),
+                ("local_delete.md", "h".to_string()),
+                ("remote_delete.md", "h".to_string()),
+            ]),
+            ..Default::default()
+        };
+        let local = paths(&[
+            ("same.md", "h".to_string()),
+            ("local_edit.md", "h2".to_string()),
+            ("remote_edit.md", "h".to_string()),
+            ("both.md", "h2".to_string()),
+            ("remote_delete.md", "h".to_string()),
+            ("local_new.md", "x".to_string()),
+        ]);
+        let remote = paths(&[
+            ("same.md", before),
+            ("local_edit.md", before),
+            ("remote_edit.md", after),
+            ("both.md", after),
+            ("local_delete.md", before),
+            ("remote_new.md", after),
+        ]);
+        let changes = |planned: &[PlannedChange]| -> Vec<String> {
+            planned.iter().map(|c| format!("{} {}", c.path, c.change)).collect()
+        };
+
+        let preview = plan_sync_preview(&manifest, &local, &remote, true);
+        assert_eq!(changes(&preview.remote), ["local_delete.md deleted", "local_edit.md modified", "local_new.md new"]);
+        assert_eq!(changes(&preview.local), ["remote_delete.md deleted", "remote_edit.md modified", "remote_new.md new"]);
+        assert_eq!(preview.conflicts, ["both.md"]);
+
+        // Without deletions, a file missing on one side is copied back from the other
+        let preview = plan_sync_preview(&manifest, &local, &remote, false);
+        assert_eq!(changes(&preview.remote), ["local_edit.md modified", "local_new.md new", "remote_delete.md new"]);
+        assert_eq!(changes(&preview.local), ["local_delete.md new", "remote_edit.md modified", "remote_new.md new"]);
+    }
+
+    #[test]
+    fn read_note_safe_reports_binary_files() {
+        let (dir, _) = temp_vault();
+        fs::write(dir.path().join("note.md"), "héllo").unwrap();
+        fs::write(dir.path().join("nul.md"), b"ab\0cd").unwrap();
+        fs::write(dir.path().join("latin1.md"), [0x68, 0xe9, 0x6c]).unwrap();
+        let notebook = dir.path().to_string_lossy().to_string();
+
+        match read_note_safe(notebook.clone(), "note.md".to_string()).unwrap() {
+            NoteContent::Text { content } => assert_eq!(content, "héllo"),
+            other => panic!("expected text, got {:?}", other),
+        }
+        match read_note_safe(notebook.clone(), "nul.md".to_string()).unwrap() {
+            NoteContent::Binary { size, hex_preview } => {
+                assert_eq!(size, 5);
+                assert!(hex_preview.starts_with("00000000  61 62 00 63 64"), "{}", hex_preview);
+                assert!(hex_preview.ends_with("ab.cd"), "{}", hex_preview);
+            }
+            other => panic!("expected binary, got {:?}", other),
+        }
+        assert!(matches!(
+            read_note_safe(notebook, "latin1.md".to_string()).unwrap(),
+            NoteContent::Binary { size: 3, .. }
+        ));
+    }
+
+    #[test]
+    fn auto_save_defaults_on_and_persists() {
+        let (dir, base_path) = temp_vault();
+        assert!(get_auto_save(base_path.clone()).unwrap());
+
+        // Settings saved before the option existed
+        let mut settings = serde_json::to_value(AppSettings::default()).unwrap();
+        settings.as_object_mut().unwrap().remove("auto_save");
+        fs::write(dir.path().join(".azimuth_settings.json"), settings.to_string()).unwrap();
+        assert!(get_auto_save(base_path.clone()).unwrap());
+
+        assert!(!set_auto_save(base_path.clone(), false).unwrap());
+        assert!(!get_auto_save(base_path.clone()).unwrap());
+        assert!(set_auto_save(base_path.clone(), true).unwrap());
+        assert!(get_auto_save(base_path).unwrap());
+    }
+
+    #[test]
+    fn search_index_rereads_only_changed_notes() {
+        let (dir, base_path) = temp_vault();
+        write_files(dir.path(), &[("a.md", "apple shared"), ("Work/b.md", "banana shared")]);
+        assert_eq!(rebuild_search_index(base_path.clone()).unwrap(), 2);
+        assert_eq!(rebuild_search_index(base_path.clone()).unwrap(), 0);
+
+        let note = dir.path().join("a.md");
+        fs::write(&note, "cherry shared").unwrap();
+        let later = std::time::SystemTime::now() + std::time::Duration::from_secs(10);
+        fs::File::options().write(true).open(&note).unwrap().set_modified(later).unwrap();
+        assert_eq!(rebuild_search_index(base_path.clone()).unwrap(), 1);
+
+        let index = load_search_index(dir.path()).unwrap();
+        assert!(!index.terms.contains_key("apple"));
+        assert_eq!(index.terms["cherry"], HashSet::from(["a.md".to_string()]));
+        assert_eq!(index.terms["banana"], HashSet::from(["Work/b.md".to_string()]));
+        assert_eq!(index.terms["shared"].len(), 2);
+        assert_eq!(index.files["Work/b.md"], modified_nanos(&dir.path().join("Work/b.md")));
+    }
+
+    fn boolean_count(query: &str, text: &str) -> usize {
+        QueryMatcher::new(query, SearchMode::Boolean, false).unwrap().find(text, usize::MAX).0
+    }
+
+    #[test]
+    fn boolean_queries_apply_each_operator() {
+        let text = "Rust makes async code safe. Async rust is fun.";
+        // Bare terms are ANDed, and match_count sums the required terms' hits
+        assert_eq!(boolean_count("rust async", text), 4);
+        assert_eq!(boolean_count("rust AND async", text), 4);
+        assert_eq!(boolean_count("rust AND python", text), 0);
+        assert_eq!(boolean_count("python OR rust", text), 2);
+        assert_eq!(boolean_count("python OR java", text), 0);
+        assert_eq!(boolean_count("rust NOT tokio", text), 2);
+        assert_eq!(boolean_count("rust NOT async", text), 0);
+    }
+
+    #[test]
+    fn boolean_queries_mix_operators() {
+        let text = "rust with async and tokio";
+        assert_eq!(boolean_count("rust AND async NOT tokio", text), 0);
+        assert_eq!(boolean_count("rust AND async NOT java", text), 2);
+        // OR binds tighter than AND: rust AND (python OR tokio)
+        assert_eq!(boolean_count("rust AND python OR tokio", text), 2);
+        assert_eq!(boolean_count("python OR java AND rust", text), 0);
+        assert_eq!(boolean_count("NOT java rust", text), 1);
+
+        for invalid in ["AND rust", "OR rust", "rust AND", "rust NOT", "rust AND OR async", "NOT rust"] {
+            assert!(QueryMatcher::new(invalid, SearchMode::Boolean, false).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn boolean_search_filters_notes() {
+        let (dir, base_path) = temp_vault();
+        write_files(dir.path(), &[
+            ("a.md", "rust and async"),
+            ("b.md", "rust with tokio and async"),
+            ("c.md", "python async"),
+        ]);
+        let options = SearchOptions { mode: SearchMode::Boolean, ..Default::default() };
+
+        let page = search_notes(base_path, "rust async NOT tokio".to_string(), Some(options)).unwrap();
+        let found: Vec<&str> = page.results.iter().map(|r| r.note_id.as_str()).collect();
+        assert_eq!(found, vec!["a.md"]);
+        assert_eq!(page.results[0].match_count, 2);
+    }
+
+    #[test]
+    fn unchanged_files_are_not_reuploaded_to_s3() {
+        let (dir, _) = temp_vault();
+        let note = dir.path().join("note.md");
+        fs::write(&note, "hello").unwrap();
+        let local_hash = get_file_hash(&note).unwrap();
+
+        // First sync: nothing remote yet
+        assert_eq!(plan_s3_sync(&local_hash, None, None, None), S3SyncAction::Upload);
+        // S3 returns a quoted MD5 ETag, never the SHA-256, and the state records it
+        let state = synced_state(local_hash.clone(), Some("\"5d41402abc4b2a76b9719d911017c592\"".to_string()));
+
+        // Second sync, nothing changed: the listing's ETag matches the recorded one
+        let etag = Some("5d41402abc4b2a76b9719d911017c592");
+        assert_eq!(plan_s3_sync(&local_hash, Some(&state), etag, None), S3SyncAction::InSync);
+        // Local edit uploads, remote edit downloads, both conflict
+        assert_eq!(plan_s3_sync("edited", Some(&state), etag, None), S3SyncAction::Upload);
+        assert_eq!(plan_s3_sync(&local_hash, Some(&state), Some("other"), None), S3SyncAction::Download);
+        assert_eq!(plan_s3_sync("edited", Some(&state), Some("other"), None), S3SyncAction::Conflict);
+    }
+
+    #[test]
+    fn s3_checksum_metadata_settles_matching_copies() {
+        let hash = "abc";
+        // No sync record, but the stored SHA-256 shows the copies already match
+        assert_eq!(plan_s3_sync(hash, None, Some("etag"), Some(hash)), S3SyncAction::InSync);
+        assert_eq!(plan_s3_sync(hash, None, Some("etag"), Some("other")), S3SyncAction::Conflict);
+        // Uploaded before checksums were stored
+        assert_eq!(plan_s3_sync(hash, None, Some("etag"), None), S3SyncAction::Upload);
+
+        let state = synced_state(hash.to_string(), Some("etag".to_string()));
+        assert_eq!(plan_s3_sync("new", Some(&state), Some("e2"), Some("new")), S3SyncAction::InSync);
+        assert_eq!(plan_s3_sync(hash, Some(&state), Some("e2"), Some("other")), S3SyncAction::Download);
+    }
+
+    #[test]
+    fn streamed_hash_matches_one_shot_hash() {
+        let (dir, _) = temp_vault();
+        let file = dir.path().join("large.bin");
+        // Around the buffer boundary and well past 10MB
+        for size in [0usize, 1, 65535, 65536, 65537, 10 * 1024 * 1024 + 7] {
+            let data: Vec<u8> = (0..size).map(|i| (i * 31 % 256) as u8).collect();
+            fs::write(&file, &data).unwrap();
+            assert_eq!(get_file_hash(&file).unwrap(), hex::encode(Sha256::digest(&data)));
+        }
+        assert!(get_file_hash(&dir.path().join("missing.bin")).is_err());
+    }
+
+    #[test]
+    fn renaming_a_note_moves_its_attachment_folder() {
+        let (dir, notebook) = temp_vault();
+        fs::create_dir(dir.path().join("old note")).unwrap();
+        fs::write(dir.path().join("old note/img.png"), "png").unwrap();
+        let old_asset = asset_url(&dir.path().join("old note/img.png"));
+        let content = format!("![a](old%20note/img.png) ![b]({}) [c](old note/x)", old_asset);
+        fs::write(dir.path().join("old note.md"), content).unwrap();
+
+        rename_note(notebook, "old note.md".to_string(), "new.md".to_string()).unwrap();
+
+        assert!(dir.path().join("new/img.png").is_file());
+        assert!(!dir.path().join("old note").exists());
+        let new_asset = asset_url(&dir.path().join("new/img.png"));
+        assert_eq!(
+            fs::read_to_string(dir.path().join("new.md")).unwrap(),
+            format!("![a](new/img.png) ![b]({}) [c](new/x)", new_asset)
+        );
+    }
+
+    #[test]
+    fn note_stats_counts_a_known_paragraph() {
+        let paragraph = "The quick brown fox jumps over the lazy dog.\nIt was not amused.";
+        assert_eq!(
+            compute_note_stats(paragraph),
+            NoteStats { words: 13, characters: 63, lines: 2, reading_minutes: 1 }
+        );
+
+        // Front matter, code blocks, HTML tags and link targets are not prose
+        let marked_up = "---\ntitle: x y z\n---\n# Hello *wor*ld\n\nThe [quick](http://a.b/c) `fox` <b>jumps</b>.\n\n```\nlet a = 1;\n```\n";
+        assert_eq!(
+            compute_note_stats(marked_up),
+            NoteStats { words: 6, characters: 32, lines: 10, reading_minutes: 1 }
+        );
+        assert_eq!(compute_note_stats("").reading_minutes, 0);
+        assert_eq!(compute_note_stats(&"word ".repeat(READING_WORDS_PER_MINUTE + 1)).reading_minutes, 2);
+    }
+
+    #[test]
+    fn inline_tags_skip_headings_and_code() {
+        assert_eq!(extract_tags("#done"), vec!["done"]);
+        assert!(extract_tags("# Title\n## heading\n### deeper").is_empty());
+        assert!(extract_tags("```\n#code\n```\nsee `#inline`").is_empty());
+
+        let content = "#done and #work/project-x, not#mid or #123.\n```\n#code\n```\n[l](http://x.y/#frag) https://a.b/c#d (#paren) #done\n";
+        // Issue numbers, mid-word hashes and URL fragments aren't tags either
+        assert_eq!(extract_tags(content), vec!["done", "work/project-x", "paren"]);
+    }
+
+    #[test]
+    fn inline_tags_merge_with_manual_tags() {
+        let (_dir, base_path) = temp_vault();
+        let note = format!("{}/n.md", base_path);
+        fs::write(&note, "#a #b").unwrap();
+        set_note_tags(base_path.clone(), note.clone(), vec!["manual".to_string(), "a".to_string()]).unwrap();
+        assert_eq!(sync_inline_tags(base_path, note).unwrap(), vec!["manual", "a", "b"]);
+    }
+
+    #[test]
+    fn saved_attachments_are_listed() {
+        let (_dir, notebook) = temp_vault();
+        let saved = save_attachment(
+            notebook.clone(),
+            "My Note.md".to_string(),
+            "pic.png".to_string(),
+            STANDARD.encode("x"),
+            None,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(list_attachments(notebook.clone(), "My Note.md".to_string()).unwrap(), vec!["pic.png"]);
+        let path = get_attachment_path(notebook, "My Note.md".to_string(), "pic.png".to_string()).unwrap();
+        assert!(Path::new(&path).is_file());
+        assert_eq!(saved.url, asset_url(Path::new(&path)));
+    }
+
+    #[test]
+    fn renaming_a_tag_updates_every_note() {
+        let (_dir, base_path) = temp_vault();
+        set_note_tags(base_path.clone(), "a.md".to_string(), vec!["work".to_string(), "x".to_string()]).unwrap();
+        set_note_tags(base_path.clone(), "b.md".to_string(), vec!["work".to_string(), "job".to_string()]).unwrap();
+        set_note_tags(base_path.clone(), "c.md".to_string(), vec!["other".to_string()]).unwrap();
+
+        assert_eq!(rename_tag(base_path.clone(), "work".to_string(), "#job".to_string()).unwrap(), 2);
+        assert_eq!(get_note_tags(base_path.clone(), "a.md".to_string()).unwrap(), vec!["job", "x"]);
+        // Already tagged with the new name, so the two collapse into one
+        assert_eq!(get_note_tags(base_path.clone(), "b.md".to_string()).unwrap(), vec!["job"]);
+        assert_eq!(get_note_tags(base_path.clone(), "c.md".to_string()).unwrap(), vec!["other"]);
+        assert!(rename_tag(base_path, "job".to_string(), " ".to_string()).is_err());
+    }
+
+    #[test]
+    fn tags_and_favorites_follow_renamed_and_moved_notes() {
+        let (dir, base_path) = temp_vault();
+        fs::create_dir_all(dir.path().join("nb/sub")).unwrap();
+        fs::create_dir_all(dir.path().join("other")).unwrap();
+        fs::write(dir.path().join("nb/a.md"), "# A").unwrap();
+        let notebook = dir.path().join("nb").to_string_lossy().to_string();
+        let other = dir.path().join("other").to_string_lossy().to_string();
+        let key = format!("{}/a.md", notebook);
+        update_settings(&base_path, |s| {
+            s.tags.insert(key.clone(), vec!["x".to_string()]);
+            s.favorites.push(key.clone());
+            s.notebook_styles.insert(
+                format!("{}/sub", notebook),
+                NotebookStyle { icon: "i".to_string(), color: "c".to_string() },
+            );
+        })
+        .unwrap();
+
+        rename_note(notebook.clone(), "a.md".to_string(), "b.md".to_string()).unwrap();
+        let renamed = format!("{}/b.md", notebook);
+        let settings = load_settings(base_path.clone()).unwrap();
+        assert_eq!(settings.tags.get(&renamed), Some(&vec!["x".to_string()]));
+        assert_eq!(settings.favorites, vec![renamed]);
+
+        move_note(notebook.clone(), other.clone(), "b.md".to_string()).unwrap();
+        let moved = format!("{}/b.md", other);
+        let settings = load_settings(base_path.clone()).unwrap();
+        assert_eq!(settings.tags.get(&moved), Some(&vec!["x".to_string()]));
+        assert_eq!(settings.favorites, vec![moved]);
+        assert_eq!(settings.tags.len(), 1);
+
+        move_notebook(notebook, other.clone()).unwrap();
+        let settings = load_settings(base_path).unwrap();
+        assert!(settings.notebook_styles.contains_key(&format!("{}/nb/sub", other)));
+    }
+
+    #[test]
+    fn traversal_in_note_and_attachment_names_is_rejected() {
+        let (dir, _) = temp_vault();
+        fs::create_dir_all(dir.path().join("nb")).unwrap();
+        fs::write(dir.path().join("secret.md"), "s").unwrap();
+        let notebook = dir.path().join("nb").to_string_lossy().to_string();
+
+        for bad in ["../secret.md", "..", ".", "", "/etc/passwd", "a/b.md", "a\\b.md", "sub/../../secret.md"] {
+            assert!(matches!(read_note(notebook.clone(), bad.to_string()), Err(AzimuthError::InvalidPath(_))), "{}", bad);
+            assert!(save_note(notebook.clone(), bad.to_string(), "x".to_string()).is_err(), "{}", bad);
+            assert!(delete_note(notebook.clone(), bad.to_string(), None, Some(true)).is_err(), "{}", bad);
+            assert!(get_attachment_path(notebook.clone(), "n.md".to_string(), bad.to_string()).is_err(), "{}", bad);
+            let saved = save_attachment(notebook.clone(), bad.to_string(), "a.png".to_string(), STANDARD.encode("x"), None, None);
+            assert!(saved.is_err(), "{}", bad);
+        }
+        assert!(rename_note(notebook.clone(), "x.md".to_string(), "../y.md".to_string()).is_err());
+        fs::write(dir.path().join("secret.conflict"), "theirs").unwrap();
+        let resolution = ConflictResolution { file_path: "../secret.md".to_string(), resolution: "keep_remote".to_string() };
+        assert!(matches!(resolve_conflict(notebook.clone(), resolution), Err(AzimuthError::InvalidPath(_))));
+        assert_eq!(fs::read_to_string(dir.path().join("secret.md")).unwrap(), "s");
+
+        // Dots inside a name are fine
+        save_note(notebook.clone(), "ok..md".to_string(), "fine".to_string()).unwrap();
+        assert_eq!(read_note(notebook, "ok..md".to_string()).unwrap(), "fine");
+    }
+
+    #[test]
+    fn errors_serialize_with_kind_and_message() {
+        let missing = AzimuthError::NotFound("no such note".to_string());
+        assert_eq!(
+            serde_json::to_value(&missing).unwrap(),
+            serde_json::json!({ "kind": "not_found", "message": "no such note" })
+        );
+        let denied = AzimuthError::PermissionDenied("read-only".to_string());
+        assert_eq!(serde_json::to_value(&denied).unwrap()["kind"], "permission_denied");
+        assert_eq!(missing.to_string(), "no such note");
+
+        use std::io::ErrorKind;
+        let kind = |e: ErrorKind| serde_json::to_value(AzimuthError::from(std::io::Error::from(e))).unwrap()["kind"].clone();
+        assert_eq!(kind(ErrorKind::NotFound), "not_found");
+        assert_eq!(kind(ErrorKind::AlreadyExists), "already_exists");
+        assert_eq!(kind(ErrorKind::PermissionDenied), "permission_denied");
+        assert_eq!(kind(ErrorKind::Interrupted), "io");
+        assert_eq!(serde_json::to_value(AzimuthError::from("plain")).unwrap()["kind"], "other");
+
+        // Commands report a missing note as not_found
+        let (_dir, notebook) = temp_vault();
+        assert!(matches!(read_note(notebook, "absent.md".to_string()), Err(AzimuthError::NotFound(_))));
+    }
+
+    #[test]
+    fn helper_errors_keep_their_kind() {
+        let (dir, base_path) = temp_vault();
+        let base = dir.path();
+
+        // A note removed before it reaches the trash
+        let err = move_to_trash(base, &base.join(TRASH_DIR).join("entry"), &base.join("gone.md")).unwrap_err();
+        assert!(matches!(err, AzimuthError::NotFound(_)), "{:?}", err);
+
+        let err = AzimuthError::io_context(std::io::Error::from(std::io::ErrorKind::PermissionDenied), "Failed to upload a.md");
+        assert!(matches!(&err, AzimuthError::PermissionDenied(m) if m.starts_with("Failed to upload a.md: ")), "{:?}", err);
+
+        let options = SearchOptions { scope_path: Some("/".to_string()), ..Default::default() };
+        let err = SearchPlan::new(base_path.clone(), "x".to_string(), options).err().unwrap();
+        assert!(matches!(err, AzimuthError::InvalidPath(_)), "{:?}", err);
+
+        fs::write(base.join("notes.zip"), "not a zip").unwrap();
+        let err = import_zip(base_path.clone(), base.join("notes.zip").to_string_lossy().to_string()).unwrap_err();
+        assert!(matches!(err, AzimuthError::InvalidInput(_)), "{:?}", err);
+        let err = import_zip(base_path, base.join("absent.zip").to_string_lossy().to_string()).unwrap_err();
+        assert!(matches!(err, AzimuthError::NotFound(_)), "{:?}", err);
+    }
+
+    #[test]
+    fn watcher_ignores_hidden_and_outside_paths() {
+        let root = Path::new("/vault");
+        assert!(is_watched_path(root, Path::new("/vault/nb/a.md")));
+        assert!(is_watched_path(root, Path::new("/vault/nb/sub/b.md")));
+        // write_atomic's temp file, the app's own folders and another tool's
+        assert!(!is_watched_path(root, Path::new("/vault/nb/.a.md.azimuth-tmp")));
+        assert!(!is_watched_path(root, Path::new("/vault/.azimuth_history/a.md")));
+        assert!(!is_watched_path(root, Path::new("/vault/.azimuth_settings.json")));
+        assert!(!is_watched_path(root, Path::new("/vault/.git/HEAD")));
+        assert!(!is_watched_path(root, Path::new("/elsewhere/a.md")));
+    }
+
+    #[test]
+    fn theme_defaults_to_system_and_persists() {
+        let (dir, base_path) = temp_vault();
+        assert_eq!(load_settings(base_path.clone()).unwrap().theme, "system");
+
+        // Settings saved before the option existed
+        let mut settings = serde_json::to_value(AppSettings::default()).unwrap();
+        settings.as_object_mut().unwrap().remove("theme");
+        fs::write(dir.path().join(".azimuth_settings.json"), settings.to_string()).unwrap();
+        assert_eq!(load_settings(base_path.clone()).unwrap().theme, "system");
+
+        let mut settings = load_settings(base_path.clone()).unwrap();
+        settings.theme = "dark".to_string();
+        write_settings(&base_path, &settings).unwrap();
+        assert_eq!(load_settings(base_path.clone()).unwrap().theme, "dark");
+        // Other settings changes keep the saved theme
+        update_settings(&base_path, |s| s.auto_save = false).unwrap();
+        assert_eq!(load_settings(base_path).unwrap().theme, "dark");
+
+        assert!(window_theme("system").is_none());
+        assert!(matches!(window_theme("dark"), Some(tauri::Theme::Dark)));
+    }
+
+    #[test]
+    fn credential_flags_accept_strings_and_booleans() {
+        let credentials = serde_json::json!({ "a": "true", "b": true, "c": "false", "d": false, "e": "yes" });
+        let flags: Vec<bool> = ["a", "b", "c", "d", "e", "missing"].iter().map(|key| credential_flag(&credentials, key)).collect();
+        assert_eq!(flags, vec![true, true, false, false, false, false]);
+    }
+}
@@ -2,17 +2,36 @@ import React, { useState, useEffect, useCallback, useRef } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
 import { getCurrentWindow } from '@tauri-apps/api/window';
-import { watch } from '@tauri-apps/plugin-fs';
 import MDEditor, { commands } from '@uiw/react-md-editor';
 import { renderAsync } from 'docx-preview';
 import * as XLSX from 'xlsx';
-import { Note, Notebook, SyncConfig, AppSettings, SearchResult, SyncStatus, OpenTab, NotebookStyle } from './types';
+import { Note, Notebook, SyncConfig, AppSettings, SearchPage, SearchResult, SyncStatus, SyncPreview, PlannedChange, SnapshotInfo, TrashEntry, SavedAttachment, OrphanedAttachment, NoteStats, NotebookStats, ObsidianImportResult, NoteVersion, OpenTab, NotebookStyle, AzimuthError } from './types';
 import './App.css';
 
 interface LoadComplete {
   notebooks: Notebook[];
 }
 
+// Pasted screenshots are scaled down to this width before they are saved
+const PASTED_IMAGE_MAX_WIDTH = 1920;
+const PASTED_IMAGE_QUALITY = 85;
+
+const isAzimuthError = (e: unknown): e is AzimuthError =>
+  typeof e === 'object' && e !== null && 'kind' in e && 'message' in e;
+
+// Text for a failed command, with a hint where the kind of error suggests one
+const errorMessage = (e: unknown): string => {
+  if (!isAzimuthError(e)) return String(e);
+  switch (e.kind) {
+    case 'not_found':
+      return `${e.message} (it may have been moved or deleted)`;
+    case 'permission_denied':
+      return `${e.message} (check that Azimuth is allowed to access this folder)`;
+    default:
+      return e.message;
+  }
+};
+
 function App() {
   const [notesDir, setNotesDir] = useState<string>('');
   const [notebooks, setNotebooks] = useState<Notebook[]>([]);
@@ -30,6 +49,15 @@ function App() {
   const [showNewNote, setShowNewNote] = useState(false);
   const [newNoteName, setNewNoteName] = useState('');
   const [syncConfig, setSyncConfig] = useState<SyncConfig | null>(null);
+  // Kept in memory only; never written to the sync config
+  const [syncPassphrase, setSyncPassphrase] = useState('');
+  const [snapshots, setSnapshots] = useState<SnapshotInfo[] | null>(null);
+  const [trash, setTrash] = useState<TrashEntry[] | null>(null);
+  const [orphans, setOrphans] = useState<OrphanedAttachment[] | null>(null);
+  const [noteStats, setNoteStats] = useState<NoteStats | null>(null);
+  const [templates, setTemplates] = useState<string[]>([]);
+  const [noteVersions, setNoteVersions] = useState<NoteVersion[] | null>(null);
+  const [newNoteTemplate, setNewNoteTemplate] = useState('');
   const [isSaving, setIsSaving] = useState(false);
   const [saveIndicator, setSaveIndicator] = useState<'idle' | 'saving' | 'saved'>('idle');
   const [isSyncing, setIsSyncing] = useState(false);
@@ -207,14 +235,14 @@ function App() {
     }
   }, [content, activeTabId]);
 
-  // Status bar calculations
-  const getWordCount = (text: string): number => {
-    return text.trim().split(/\s+/).filter(w => w.length > 0).length;
-  };
-
-  const getCharCount = (text: string): number => {
-    return text.length;
-  };
+  // Status bar stats follow the saved file, so refresh on every save
+  useEffect(() => {
+    const notebookPath = selectedNotebook?.path || notesDir;
+    if (!selectedNote || !notebookPath || saveIndicator === 'saving') return;
+    invoke<NoteStats>('note_stats', { notebookPath, noteId: selectedNote.id })
+      .then(setNoteStats)
+      .catch(() => setNoteStats(null));
+  }, [selectedNote, selectedNotebook, notesDir, saveIndicator]);
 
   const formatFileSize = (bytes: number): string => {
     if (bytes < 1024) return `${bytes} B`;
@@ -248,8 +276,11 @@ function App() {
       .replace(/:time\b/g, timeOnly);
   };
 
-  // Sort notes
+  // Sort notes, pinned ones first
+  const isNotePinned = (note: Note) => settings?.pinned_notes?.includes(`${note.folder}/${note.id}`) || false;
+
   const sortedNotes = [...notes].sort((a, b) => {
+    if (isNotePinned(a) !== isNotePinned(b)) return isNotePinned(a) ? -1 : 1;
     let comparison = 0;
     
     switch (sortBy) {
@@ -322,6 +353,26 @@ function App() {
     await invoke('save_settings', { basePath: notesDir, settings: newSettings });
   };
 
+  const togglePinnedNote = async (note: Note) => {
+    if (!settings || !notesDir) return;
+    try {
+      const result = await invoke<{ is_pinned: boolean; pinned_notes: string[] }>('toggle_pinned_note', {
+        basePath: notesDir, notePath: `${note.folder}/${note.id}`,
+      });
+      setSettings({ ...settings, pinned_notes: result.pinned_notes });
+    } catch (e) {
+      console.error('Failed to toggle pinned note:', e);
+    }
+  };
+
+  // Renames and moves rewrite settings keys on the backend, so pick those up
+  const reloadSettings = async () => {
+    if (!notesDir) return;
+    const appSettings = await invoke<AppSettings>('load_settings', { basePath: notesDir });
+    setSettings(appSettings);
+    setFavorites(appSettings.favorites);
+  };
+
   const toggleFavoriteByPath = async (notePath: string) => {
     if (!notesDir) return;
     try {
@@ -550,6 +601,8 @@ function App() {
     };
   }, []);
 
+  // The backend watches the notes folder and reports files changed on disk by sync
+  // or another editor, so re-scope it whenever the folder changes
   useEffect(() => {
     if (!notesDir) return;
     
@@ -558,38 +611,48 @@ function App() {
     const isLargeDir = notesDir === '/' || 
                        notesDir.split('/').filter(Boolean).length <= 2;
     
-    if (isLargeDir) {
-      return;
-    }
-    
-    let stopWatching: (() => void) | undefined;
-    const startWatching = async () => {
+    const request = isLargeDir
+      ? invoke('stop_watching')
+      : invoke('start_watching', { path: notesDir });
+    request.catch(err => console.error('Failed to watch directory:', err));
+  }, [notesDir]);
+
+  useEffect(() => {
+    const refresh = async (event: { event: string; payload: string }) => {
+      const path = event.payload;
+      if (event.event !== 'file-changed') {
+        invoke('list_notebooks_async', { basePath: notesDirRef.current });
+      }
+      const notebook = selectedNotebookRef.current;
+      if (!notebook || path.substring(0, path.lastIndexOf('/')) !== notebook.path) return;
       try {
-        stopWatching = await watch(notesDir, async () => {
-          invoke('list_notebooks_async', { basePath: notesDirRef.current });
-          
-          if (selectedNotebook) {
-            try {
-              const notesList = await invoke<Note[]>('list_notes', { notebookPath: selectedNotebook.path });
-              setNotes(notesList);
-              
-              if (selectedNote && !notesList.some(n => n.id === selectedNote.id)) {
-                setSelectedNote(null);
-                setContent('');
-              }
-            } catch (err) {
-              console.error('Failed to refresh notes:', err);
-            }
-          }
-        }, { recursive: false }); // Don't watch recursively
+        const notesList = await invoke<Note[]>('list_notes', { notebookPath: notebook.path, basePath: notesDirRef.current });
+        setNotes(notesList);
+        
+        const note = selectedNoteRef.current;
+        if (!note || `${note.folder}/${note.id}` !== path) return;
+        const fresh = notesList.find(n => n.id === note.id);
+        if (!fresh) {
+          setSelectedNote(null);
+          setContent('');
+        } else if (fresh.content !== note.content && contentRef.current === note.content) {
+          // Only replace what's in the editor when it has no unsaved edits
+          setSelectedNote(fresh);
+          setOpenTabs(prev => prev.map(t => 
+            t.note.id === note.id ? { ...t, note: fresh, content: fresh.content, isDirty: false } : t
+          ));
+          setContent(fresh.content);
+        }
       } catch (err) {
-        console.error('Failed to watch directory:', err);
+        console.error('Failed to refresh notes:', err);
       }
     };
     
-    startWatching();
-    return () => { if (stopWatching) stopWatching(); };
-  }, [notesDir, selectedNotebook, selectedNote]);
+    const unlisten = Promise.all(
+      ['file-created', 'file-changed', 'file-deleted'].map(name => listen<string>(name, refresh))
+    );
+    return () => { unlisten.then(fns => fns.forEach(fn => fn())); };
+  }, []);
 
   // Memoize saveNote to use in keyboard shortcuts
   const saveNoteCallback = useCallback(async () => {
@@ -607,7 +670,7 @@ function App() {
       setSelectedNote(updatedNote);
       
       // Refresh the notes list to pick up any new files (e.g., pasted images)
-      const notesList = await invoke<Note[]>('list_notes', { notebookPath });
+      const notesList = await invoke<Note[]>('list_notes', { notebookPath, basePath: notesDir });
       setNotes(notesList);
       
       // Update tab to mark as not dirty
@@ -825,7 +888,7 @@ function App() {
 
   const loadNotes = useCallback(async (notebook: Notebook) => {
     try {
-      const notesList = await invoke<Note[]>('list_notes', { notebookPath: notebook.path });
+      const notesList = await invoke<Note[]>('list_notes', { notebookPath: notebook.path, basePath: notesDir });
       setNotes(notesList);
     } catch (e) {
       console.error('Failed to load notes:', e);
@@ -840,7 +903,7 @@ function App() {
       // Load root directory files when no notebook is selected
       const loadRootNotes = async () => {
         try {
-          const notesList = await invoke<Note[]>('list_notes', { notebookPath: notesDir });
+          const notesList = await invoke<Note[]>('list_notes', { notebookPath: notesDir, basePath: notesDir });
           setNotes(notesList);
         } catch (e) {
           console.error('Failed to load root notes:', e);
@@ -863,6 +926,26 @@ function App() {
     }
   }, [selectedNote, notesDir]);
 
+  // Templates are offered whenever the new note form opens
+  useEffect(() => {
+    if (!showNewNote || !notesDir) return;
+    invoke<string[]>('list_templates', { basePath: notesDir })
+      .then(setTemplates)
+      .catch(() => setTemplates([]));
+  }, [showNewNote, notesDir]);
+
+  // Pick up inline #tags each time the note is saved
+  useEffect(() => {
+    if (!selectedNote || !notesDir || saveIndicator !== 'saved') return;
+    const notePath = `${selectedNote.folder}/${selectedNote.id}`;
+    invoke<string[]>('sync_inline_tags', { basePath: notesDir, notePath })
+      .then(tags => {
+        setNoteTags(tags);
+        setAllTags(prev => [...new Set([...prev, ...tags])].sort());
+      })
+      .catch(console.error);
+  }, [selectedNote, notesDir, saveIndicator]);
+
   // Search
   const handleSearch = async (query: string) => {
     setSearchQuery(query);
@@ -871,8 +954,8 @@ function App() {
       return;
     }
     try {
-      const results = await invoke<SearchResult[]>('search_notes', { basePath: notesDir, query });
-      setSearchResults(results);
+      const page = await invoke<SearchPage>('search_notes', { basePath: notesDir, query });
+      setSearchResults(page.results);
     } catch (e) {
       console.error('Search failed:', e);
     }
@@ -882,7 +965,7 @@ function App() {
     const notebook = findNotebookByPath(notebooks, result.notebook_path);
     if (notebook) {
       setSelectedNotebook(notebook);
-      const notesList = await invoke<Note[]>('list_notes', { notebookPath: result.notebook_path });
+      const notesList = await invoke<Note[]>('list_notes', { notebookPath: result.notebook_path, basePath: notesDir });
       setNotes(notesList);
       const note = notesList.find(n => n.id === result.note_id);
       if (note) {
@@ -1128,12 +1211,13 @@ function App() {
         sourcePath: sourceNotebook.path, 
         targetPath: targetPath 
       });
+      await reloadSettings();
       // Refresh notebooks list
       const nbs = await invoke<Notebook[]>('list_notebooks', { basePath: notesDir });
       setNotebooks(nbs);
     } catch (e) {
       console.error('Failed to move notebook:', e);
-      alert(`Failed to move notebook: ${e}`);
+      alert(`Failed to move notebook: ${errorMessage(e)}`);
     }
   };
 
@@ -1150,6 +1234,7 @@ function App() {
         targetFolder: targetPath,
         noteId: note.id
       });
+      await reloadSettings();
       
       // Remove from current notes list
       setNotes(prev => prev.filter(n => n.id !== note.id));
@@ -1162,12 +1247,12 @@ function App() {
       
       // If we moved to the currently selected notebook, refresh its notes
       if (selectedNotebook?.path === targetPath) {
-        const notesList = await invoke<Note[]>('list_notes', { notebookPath: targetPath });
+        const notesList = await invoke<Note[]>('list_notes', { notebookPath: targetPath, basePath: notesDir });
         setNotes(notesList);
       }
     } catch (e) {
       console.error('Failed to move note:', e);
-      alert(`Failed to move note: ${e}`);
+      alert(`Failed to move note: ${errorMessage(e)}`);
     }
   };
 
@@ -1270,6 +1355,91 @@ function App() {
     }
   };
 
+  const importZip = async () => {
+    try {
+      const { open } = await import('@tauri-apps/plugin-dialog');
+      const selected = await open({ title: 'Select ZIP archive to import as notebook', filters: [{ name: 'ZIP', extensions: ['zip'] }] });
+      if (selected && typeof selected === 'string') {
+        const nb = await invoke<Notebook>('import_zip', { basePath: notesDir, zipPath: selected });
+        setNotebooks(prev => prev.some(n => n.path === nb.path) ? prev : [...prev, nb]);
+        setSelectedNotebook(nb);
+      }
+    } catch (err) {
+      console.error('Failed to import ZIP:', err);
+      alert(`Failed to import: ${errorMessage(err)}`);
+    }
+  };
+
+  const importEnex = async () => {
+    try {
+      const { open } = await import('@tauri-apps/plugin-dialog');
+      const selected = await open({ title: 'Select Evernote export to import', filters: [{ name: 'Evernote', extensions: ['enex'] }] });
+      if (!selected || typeof selected !== 'string') return;
+      const notebookName = selected.split(/[\\/]/).pop()?.replace(/\.enex$/i, '') || 'Evernote';
+      const result = await invoke<{ notebook: Notebook; notes_imported: number }>('import_enex', { basePath: notesDir, enexPath: selected, notebookName });
+      setNotebooks(prev => prev.some(n => n.path === result.notebook.path) ? prev : [...prev, result.notebook]);
+      setSelectedNotebook(result.notebook);
+      setSyncStatus(`Imported ${result.notes_imported} notes from Evernote`);
+    } catch (err) {
+      console.error('Failed to import Evernote export:', err);
+      alert(`Failed to import: ${errorMessage(err)}`);
+    }
+  };
+
+  const importObsidianVault = async () => {
+    try {
+      const { open } = await import('@tauri-apps/plugin-dialog');
+      const selected = await open({ directory: true, title: 'Select Obsidian vault to import' });
+      if (!selected || typeof selected !== 'string') return;
+      const result = await invoke<ObsidianImportResult>('import_obsidian_vault', { basePath: notesDir, vaultPath: selected });
+      setNotebooks(prev => [...prev, result.notebook]);
+      setSelectedNotebook(result.notebook);
+      setSyncStatus(`Imported ${result.notes_imported} notes and ${result.attachments_copied} attachments from Obsidian`);
+      if (result.unresolved_links.length > 0) {
+        const links = result.unresolved_links.slice(0, 20).map(link => `  ${link.note}: ${link.target}`).join('\n');
+        const more = result.unresolved_links.length > 20 ? `\n  …and ${result.unresolved_links.length - 20} more` : '';
+        alert(`${result.unresolved_links.length} links could not be resolved:\n${links}${more}`);
+      }
+    } catch (err) {
+      console.error('Failed to import Obsidian vault:', err);
+      alert(`Failed to import: ${errorMessage(err)}`);
+    }
+  };
+
+  const exportNote = async (format: 'html' | 'pdf') => {
+    const notebookPath = selectedNotebook?.path || notesDir;
+    if (!selectedNote || !notebookPath) return;
+    try {
+      const { save } = await import('@tauri-apps/plugin-dialog');
+      const outputPath = await save({
+        defaultPath: `${selectedNote.id.replace(/\.[^.]+$/, '')}.${format}`,
+        filters: [{ name: format.toUpperCase(), extensions: [format] }],
+      });
+      if (!outputPath) return;
+      const written = await invoke<string>('export_note', { notebookPath, noteId: selectedNote.id, format, outputPath });
+      setSyncStatus(`Exported to ${written}`);
+    } catch (err) {
+      console.error('Failed to export note:', err);
+      alert(`Failed to export: ${errorMessage(err)}`);
+    }
+  };
+
+  const exportNotebookZip = async (notebook: Notebook) => {
+    try {
+      const { save } = await import('@tauri-apps/plugin-dialog');
+      const outputPath = await save({
+        defaultPath: `${notebook.name}.zip`,
+        filters: [{ name: 'ZIP', extensions: ['zip'] }],
+      });
+      if (!outputPath) return;
+      const written = await invoke<string>('export_notebook_zip', { notebookPath: notebook.path, outputPath });
+      setSyncStatus(`Exported to ${written}`);
+    } catch (err) {
+      console.error('Failed to export notebook:', err);
+      alert(`Failed to export: ${errorMessage(err)}`);
+    }
+  };
+
   const createNotebook = async () => {
     if (!newNotebookName.trim()) return;
     try {
@@ -1285,6 +1455,22 @@ function App() {
   const createNote = async () => {
     const notebookPath = selectedNotebook?.path || notesDir;
     if (!notebookPath || !newNoteName.trim()) return;
+
+    if (newNoteTemplate) {
+      try {
+        const note = await invoke<Note>('create_note_from_template', {
+          notebookPath, templateName: newNoteTemplate, noteName: newNoteName.trim(),
+        });
+        setNotes([...notes, note]);
+        openNoteInTab(note);
+        setNewNoteName('');
+        setShowNewNote(false);
+      } catch (e) {
+        console.error('Failed to create note from template:', e);
+        alert(`Failed to create note: ${errorMessage(e)}`);
+      }
+      return;
+    }
     
     // Sanitize filename: remove invalid characters
     const safeName = newNoteName.trim().replace(/[<>:"/\\|?*]/g, '-');
@@ -1444,7 +1630,7 @@ function App() {
       } catch (e) {
         console.error('Failed to load office preview:', e);
         if (officeContainerRef.current) {
-          officeContainerRef.current.innerHTML = `<div class="office-error">Failed to load preview: ${e}</div>`;
+          officeContainerRef.current.innerHTML = `<div class="office-error">Failed to load preview: ${errorMessage(e)}</div>`;
         }
       } finally {
         setOfficeLoading(false);
@@ -1476,7 +1662,7 @@ function App() {
       setSelectedNote(updatedNote);
       
       // Refresh the notes list to pick up any new files (e.g., pasted images)
-      const notesList = await invoke<Note[]>('list_notes', { notebookPath });
+      const notesList = await invoke<Note[]>('list_notes', { notebookPath, basePath: notesDir });
       setNotes(notesList);
       
       // Update tab to mark as not dirty
@@ -1498,7 +1684,7 @@ function App() {
     if (!selectedNote || !notebookPath) return;
     if (!confirm('Delete this note?')) return;
     try {
-      await invoke('delete_note', { notebookPath, noteId: selectedNote.id });
+      await invoke('delete_note', { notebookPath, noteId: selectedNote.id, basePath: notesDir });
       setNotes(notes.filter(n => n.id !== selectedNote.id));
       // Close the tab for deleted note
       closeTab(selectedNote.id);
@@ -1520,12 +1706,15 @@ function App() {
           const base64 = (reader.result as string).split(',')[1];
           const fileName = `image_${Date.now()}.png`;
           try {
-            const path = await invoke<string>('save_attachment', {
+            const saved = await invoke<SavedAttachment>('save_attachment', {
               notebookPath: selectedNotebook.path, noteId: selectedNote.id, fileName, data: base64,
+              maxWidth: PASTED_IMAGE_MAX_WIDTH, quality: PASTED_IMAGE_QUALITY,
             });
-            setContent(prev => prev + `\n![${fileName}](${path})\n`);
+            setContent(prev => prev + `\n![${fileName}](${saved.url})\n`);
+            setSyncStatus(`Saved ${fileName} (${formatFileSize(saved.size)})`);
           } catch (err) {
             console.error('Failed to save image:', err);
+            alert(`Failed to save image: ${errorMessage(err)}`);
           }
         };
         reader.readAsDataURL(file);
@@ -1542,14 +1731,15 @@ function App() {
       reader.onload = async () => {
         const base64 = (reader.result as string).split(',')[1];
         try {
-          const path = await invoke<string>('save_attachment', {
+          const saved = await invoke<SavedAttachment>('save_attachment', {
             notebookPath: selectedNotebook.path, noteId: selectedNote.id, fileName: file.name, data: base64,
           });
           const isImage = file.type.startsWith('image/');
-          const markdown = isImage ? `\n![${file.name}](${path})\n` : `\n[${file.name}](${path})\n`;
+          const markdown = isImage ? `\n![${file.name}](${saved.url})\n` : `\n[${file.name}](${saved.url})\n`;
           setContent(prev => prev + markdown);
         } catch (err) {
           console.error('Failed to save attachment:', err);
+          alert(`Failed to save attachment: ${errorMessage(err)}`);
         }
       };
       reader.readAsDataURL(file);
@@ -1586,14 +1776,15 @@ function App() {
         
         const fileName = selected.split('/').pop() || `image_${Date.now()}.png`;
         console.log('Saving attachment:', { notebookPath: selectedNotebook.path, noteId: selectedNote.id, fileName });
-        const path = await invoke<string>('save_attachment', {
+        const saved = await invoke<SavedAttachment>('save_attachment', {
           notebookPath: selectedNotebook.path, noteId: selectedNote.id, fileName, data: base64,
         });
-        console.log('Saved attachment path:', path);
-        setContent(prev => prev + `\n![${fileName}](${path})\n`);
+        console.log('Saved attachment path:', saved.url);
+        setContent(prev => prev + `\n![${fileName}](${saved.url})\n`);
       }
     } catch (err) {
       console.error('Failed to insert image:', err);
+      alert(`Failed to insert image: ${errorMessage(err)}`);
     }
   };
 
@@ -1611,45 +1802,69 @@ function App() {
     setIsSyncing(true);
     setSyncStatus('Syncing...');
     try {
-      let result: SyncStatus;
-      const creds = syncConfig.credentials;
+      // The backend syncs with the saved configuration, so save any edits first
+      await invoke('save_sync_config', { basePath: notesDir, config: syncConfig });
+      const result = await invoke<SyncStatus>('run_sync', { basePath: notesDir, passphrase: syncPassphrase || null });
       
-      switch (syncConfig.provider) {
-        case 's3':
-          result = await invoke<SyncStatus>('sync_to_s3', {
-            bucket: creds.bucket, region: creds.region,
-            accessKey: creds.accessKey, secretKey: creds.secretKey, notesPath: notesDir,
-          });
-          break;
-        case 'dropbox':
-          result = await invoke<SyncStatus>('sync_to_dropbox', { accessToken: creds.accessToken, notesPath: notesDir });
-          break;
-        case 'onedrive':
-          result = await invoke<SyncStatus>('sync_to_onedrive', { accessToken: creds.accessToken, notesPath: notesDir });
-          break;
-        case 'googledrive':
-          result = await invoke<SyncStatus>('sync_to_google_drive', { accessToken: creds.accessToken, notesPath: notesDir });
-          break;
-        default:
-          throw new Error('Unknown provider');
+      if (!result.success && result.message === 'cancelled') {
+        setSyncStatus(`Sync cancelled: ${result.files_uploaded} uploaded, ${result.files_downloaded} downloaded`);
+        return;
       }
-      
       setSyncStatus(result.message);
       if (result.conflicts.length > 0) {
         alert(`Sync completed with ${result.conflicts.length} conflicts. Please resolve them.`);
       }
       
-      // Update last sync time
-      const updatedConfig = { ...syncConfig, last_sync: new Date().toISOString() };
-      await invoke('save_sync_config', { basePath: notesDir, config: updatedConfig });
-      setSyncConfig(updatedConfig);
+      // A successful sync records its time
+      const updatedConfig = await invoke<SyncConfig | null>('load_sync_config', { basePath: notesDir });
+      if (updatedConfig) setSyncConfig(updatedConfig);
     } catch (e) {
-      setSyncStatus(`Sync failed: ${e}`);
+      setSyncStatus(`Sync failed: ${errorMessage(e)}`);
     } finally {
       setIsSyncing(false);
     }
   };
 
+  const previewSync = async () => {
+    if (!syncConfig || !notesDir) return;
+    try {
+      await invoke('save_sync_config', { basePath: notesDir, config: syncConfig });
+      const preview = await invoke<SyncPreview>('preview_sync', { basePath: notesDir });
+      const count = (changes: PlannedChange[], change: string) => changes.filter(c => c.change === change).length;
+      setSyncStatus(
+        `Sync would upload ${count(preview.remote, 'new')} new and ${count(preview.remote, 'modified')} modified, ` +
+        `download ${count(preview.local, 'new')} new and ${count(preview.local, 'modified')} modified, ` +
+        `delete ${count(preview.remote, 'deleted')} remote and ${count(preview.local, 'deleted')} local` +
+        (preview.conflicts.length > 0 ? `, with ${preview.conflicts.length} conflicts` : '')
+      );
+    } catch (e) {
+      setSyncStatus(`Sync preview failed: ${errorMessage(e)}`);
+    }
+  };
+
+  const showNoteHistory = async () => {
+    const notebookPath = selectedNotebook?.path || notesDir;
+    if (!selectedNote || !notebookPath) return;
+    try {
+      setNoteVersions(await invoke<NoteVersion[]>('list_note_versions', { notebookPath, noteId: selectedNote.id }));
+    } catch (e) {
+      console.error('Failed to list note versions:', e);
+    }
+  };
+
+  const restoreNoteVersion = async (version: NoteVersion) => {
+    const notebookPath = selectedNotebook?.path || notesDir;
+    if (!selectedNote || !notebookPath) return;
+    if (!confirm(`Restore the version from ${new Date(version.created_at).toLocaleString()}? The current text is kept in the history.`)) return;
+    try {
+      const restored = await invoke<string>('restore_note_version', { notebookPath, noteId: selectedNote.id, timestamp: version.timestamp });
+      setContent(restored);
+      setNoteVersions(null);
+    } catch (e) {
+      alert(`Failed to restore: ${errorMessage(e)}`);
+    }
+  };
+
   const SearchModal = () => (
     <div className="modal-overlay" onClick={() => setShowSearch(false)}>
       <div className="modal search-modal" onClick={e => e.stopPropagation()}>
@@ -1693,6 +1908,13 @@ function App() {
     { id: 'new-notebook', label: 'New Notebook', shortcut: '', icon: '📁', category: 'File', action: () => { setShowCommandPalette(false); setShowNewNotebook(true); } },
     { id: 'save', label: 'Save Note', shortcut: '⌘S', icon: '💾', category: 'File', action: () => { setShowCommandPalette(false); if (selectedNote && isEditableFile(selectedNote.id)) saveNote(); } },
     { id: 'import-folder', label: 'Import Folder as Notebook', shortcut: '', icon: '📂', category: 'File', action: () => { setShowCommandPalette(false); importFolder(); } },
+    { id: 'import-zip', label: 'Import ZIP as Notebook', shortcut: '', icon: '📦', category: 'File', action: () => { setShowCommandPalette(false); importZip(); } },
+    { id: 'import-obsidian', label: 'Import Obsidian Vault', shortcut: '', icon: '💎', category: 'File', action: () => { setShowCommandPalette(false); importObsidianVault(); } },
+    { id: 'import-enex', label: 'Import Evernote Export (.enex)', shortcut: '', icon: '🐘', category: 'File', action: () => { setShowCommandPalette(false); importEnex(); } },
+    ...(selectedNote ? [
+      { id: 'export-html', label: 'Export Note as HTML', shortcut: '', icon: '🌐', category: 'File', action: () => { setShowCommandPalette(false); exportNote('html'); } },
+      { id: 'export-pdf', label: 'Export Note as PDF', shortcut: '', icon: '📄', category: 'File', action: () => { setShowCommandPalette(false); exportNote('pdf'); } },
+    ] : []),
     
     // Navigation commands
     { id: 'search', label: 'Search Notes', shortcut: '⌘K', icon: '🔍', category: 'Navigation', action: () => { setShowCommandPalette(false); setShowSearch(true); } },
@@ -1701,6 +1923,7 @@ function App() {
     // Edit commands
     { id: 'toggle-favorite', label: isFavorite ? 'Remove from Favorites' : 'Add to Favorites', shortcut: '', icon: isFavorite ? '★' : '☆', category: 'Edit', action: () => { setShowCommandPalette(false); toggleFavorite(); } },
     { id: 'add-tag', label: 'Add Tag to Note', shortcut: '', icon: '🏷️', category: 'Edit', action: () => { setShowCommandPalette(false); setShowTagInput(true); } },
+    ...(selectedNote ? [{ id: 'note-history', label: 'Show Note History', shortcut: '', icon: '🕘', category: 'Edit', action: () => { setShowCommandPalette(false); showNoteHistory(); } }] : []),
     { id: 'delete-note', label: 'Delete Note', shortcut: '', icon: '🗑️', category: 'Edit', action: () => { setShowCommandPalette(false); deleteNote(); } },
     
     // View commands
@@ -1711,6 +1934,7 @@ function App() {
     
     // Sync commands
     ...(syncConfig ? [{ id: 'sync', label: 'Sync Now', shortcut: '', icon: '☁️', category: 'Sync', action: () => { setShowCommandPalette(false); performSync(); } }] : []),
+    ...(syncConfig ? [{ id: 'sync-preview', label: 'Preview Sync', shortcut: '', icon: '🔍', category: 'Sync', action: () => { setShowCommandPalette(false); previewSync(); } }] : []),
     
     // Settings commands
     { id: 'settings', label: 'Open Settings', shortcut: '⌘,', icon: '⚙️', category: 'Settings', action: () => { setShowCommandPalette(false); setShowSettings(true); } },
@@ -1920,9 +2144,9 @@ function App() {
       }
       
       try {
-        await invoke('delete_note', { notebookPath, noteId: noteToDelete.id });
+        await invoke('delete_note', { notebookPath, noteId: noteToDelete.id, basePath: notesDir });
         // Refresh notes list
-        const notesList = await invoke<Note[]>('list_notes', { notebookPath });
+        const notesList = await invoke<Note[]>('list_notes', { notebookPath, basePath: notesDir });
         setNotes(notesList);
         // Close tab if open
         if (openTabs.some(t => t.note.id === noteToDelete.id)) {
@@ -1930,10 +2154,64 @@ function App() {
         }
       } catch (e) {
         console.error('Failed to delete note:', e);
-        alert(`Failed to delete: ${e}`);
+        alert(`Failed to delete: ${errorMessage(e)}`);
       }
     };
     
+    const handleShowNotebookInfo = async () => {
+      const notebook = contextMenu.notebook;
+      setContextMenu(null);
+      if (!notebook) return;
+      try {
+        const stats = await invoke<NotebookStats>('notebook_stats', { notebookPath: notebook.path, recursive: true });
+        const extensions = Object.entries(stats.by_extension)
+          .sort((a, b) => b[1] - a[1])
+          .map(([ext, count]) => `  ${ext ? `.${ext}` : '(no extension)'}: ${count}`)
+          .join('\n');
+        alert([
+          notebook.name,
+          `Notes: ${stats.notes}`,
+          `Attachments: ${stats.attachments}`,
+          `Total size: ${formatFileSize(stats.total_size)}`,
+          `Last modified: ${stats.last_modified ? new Date(stats.last_modified).toLocaleString() : '—'}`,
+          extensions && `By extension:\n${extensions}`,
+        ].filter(Boolean).join('\n'));
+      } catch (e) {
+        console.error('Failed to load notebook info:', e);
+      }
+    };
+
+    const handleDeleteNotebook = async () => {
+      const notebookToDelete = contextMenu.notebook;
+      setContextMenu(null);
+      if (!notebookToDelete) return;
+
+      const { ask } = await import('@tauri-apps/plugin-dialog');
+      const confirmed = await ask(`Move the notebook "${notebookToDelete.name}" and everything in it to the trash?`, {
+        title: 'Confirm Delete',
+        kind: 'warning',
+      });
+      if (!confirmed) return;
+
+      try {
+        await invoke<number>('delete_notebook', { basePath: notesDir, notebookPath: notebookToDelete.path });
+        const inDeleted = (path: string) => path === notebookToDelete.path || path.startsWith(`${notebookToDelete.path}/`);
+        if (selectedNotebook && inDeleted(selectedNotebook.path)) {
+          setSelectedNotebook(null);
+          setNotes([]);
+        }
+        openTabs.filter(t => inDeleted(t.note.folder)).forEach(t => closeTab(t.note.id));
+        const nbs = await invoke<Notebook[]>('list_notebooks', { basePath: notesDir });
+        setNotebooks(nbs);
+        const appSettings = await invoke<AppSettings>('load_settings', { basePath: notesDir });
+        setSettings(appSettings);
+        setFavorites(appSettings.favorites);
+      } catch (e) {
+        console.error('Failed to delete notebook:', e);
+        alert(`Failed to delete notebook: ${errorMessage(e)}`);
+      }
+    };
+
     // Context menu for notes in the notes list
     if (contextMenu.note) {
       return (
@@ -1946,6 +2224,9 @@ function App() {
             <button onClick={handleRenameNote}>
               ✏️ Rename
             </button>
+            <button onClick={() => { togglePinnedNote(contextMenu.note!); setContextMenu(null); }}>
+              📌 {isNotePinned(contextMenu.note) ? 'Unpin from Top' : 'Pin to Top'}
+            </button>
             <button onClick={handleDeleteNote}>
               🗑️ Delete
             </button>
@@ -1991,6 +2272,15 @@ function App() {
           <button onClick={handleCustomize}>
             🎨 Customize
           </button>
+          <button onClick={() => { if (contextMenu.notebook) exportNotebookZip(contextMenu.notebook); setContextMenu(null); }}>
+            📦 Export as ZIP
+          </button>
+          <button onClick={handleShowNotebookInfo}>
+            ℹ️ Folder Info
+          </button>
+          <button onClick={handleDeleteNotebook}>
+            🗑️ Delete Notebook
+          </button>
           <div className="context-menu-separator" />
           <button onClick={handleRevealInFinder}>
             📂 Reveal in Finder
@@ -2095,7 +2385,113 @@ function App() {
           />
           <span>{settings?.ui_font_size || 13}px</span>
         </div>
+        <div className="settings-section">
+          <label>Theme</label>
+          <select
+            value={settings?.theme || 'system'}
+            onChange={async e => {
+              if (!settings || !notesDir) return;
+              const newSettings = { ...settings, theme: e.target.value as AppSettings['theme'] };
+              setSettings(newSettings);
+              await invoke('save_settings', { basePath: notesDir, settings: newSettings });
+            }}
+          >
+            <option value="system">System</option>
+            <option value="light">Light</option>
+            <option value="dark">Dark</option>
+          </select>
+        </div>
         
+        <div className="settings-section">
+          <label>Trash</label>
+          <input
+            type="number"
+            min="0"
+            value={settings?.trash_retention_days ?? 30}
+            onChange={async e => {
+              if (!settings || !notesDir) return;
+              const newSettings = { ...settings, trash_retention_days: parseInt(e.target.value) || 0 };
+              setSettings(newSettings);
+              await invoke('save_settings', { basePath: notesDir, settings: newSettings });
+            }}
+          />
+          <span>days before deleted notes are removed</span>
+          <button onClick={async () => {
+            setTrash(await invoke<TrashEntry[]>('list_trash', { basePath: notesDir }));
+          }}>Show Trash</button>
+          <button onClick={async () => {
+            if (!confirm(`Permanently remove notes deleted more than ${settings?.trash_retention_days ?? 30} days ago?`)) return;
+            await invoke<number>('empty_trash', { basePath: notesDir });
+            setTrash(await invoke<TrashEntry[]>('list_trash', { basePath: notesDir }));
+          }}>Empty Trash</button>
+          {trash && (trash.length === 0 ? <p className="last-sync">Trash is empty</p> : trash.map(entry => (
+            <div key={entry.id} className="last-sync">
+              {entry.note_path} (deleted {new Date(entry.deleted_at).toLocaleString()}){' '}
+              <button onClick={async () => {
+                try {
+                  await invoke<TrashEntry>('restore_from_trash', { basePath: notesDir, trashEntry: entry.id });
+                  setTrash(await invoke<TrashEntry[]>('list_trash', { basePath: notesDir }));
+                  if (selectedNotebook) loadNotes(selectedNotebook);
+                } catch (e) {
+                  alert(`Failed to restore: ${errorMessage(e)}`);
+                }
+              }}>Restore</button>
+            </div>
+          )))}
+        </div>
+
+        <div className="settings-section">
+          <label>Attachment Size Limit</label>
+          <input
+            type="number"
+            min="0"
+            step="0.5"
+            placeholder="No limit"
+            value={settings?.max_attachment_bytes ? settings.max_attachment_bytes / 1_000_000 : ''}
+            onChange={async e => {
+              if (!settings || !notesDir) return;
+              const megabytes = parseFloat(e.target.value);
+              const newSettings = { ...settings, max_attachment_bytes: megabytes > 0 ? Math.round(megabytes * 1_000_000) : null };
+              setSettings(newSettings);
+              await invoke('save_settings', { basePath: notesDir, settings: newSettings });
+            }}
+          />
+          <span>MB per attachment (empty for no limit)</span>
+        </div>
+
+        <div className="settings-section">
+          <label>Orphaned Attachments</label>
+          <span>Files in attachment folders that no note links to</span>
+          <button onClick={async () => {
+            setOrphans(await invoke<OrphanedAttachment[]>('find_orphaned_attachments', { basePath: notesDir }));
+          }}>Find</button>
+          {orphans && orphans.length > 0 && (
+            <button onClick={async () => {
+              const total = orphans.reduce((sum, orphan) => sum + orphan.size, 0);
+              if (!confirm(`Move ${orphans.length} orphaned attachments (${formatFileSize(total)}) to the trash?`)) return;
+              try {
+                await invoke<number>('delete_orphaned_attachments', { basePath: notesDir, paths: orphans.map(orphan => orphan.path) });
+                setOrphans(await invoke<OrphanedAttachment[]>('find_orphaned_attachments', { basePath: notesDir }));
+              } catch (e) {
+                alert(`Failed to delete: ${errorMessage(e)}`);
+              }
+            }}>Move All to Trash</button>
+          )}
+          {orphans && (orphans.length === 0 ? <p className="last-sync">No orphaned attachments</p> : orphans.map(orphan => (
+            <div key={orphan.path} className="last-sync">
+              {orphan.path.startsWith(notesDir) ? orphan.path.slice(notesDir.length + 1) : orphan.path} ({formatFileSize(orphan.size)}){' '}
+              <button onClick={async () => {
+                try {
+                  await invoke<number>('delete_orphaned_attachments', { basePath: notesDir, paths: [orphan.path] });
+                  setOrphans(prev => prev?.filter(o => o.path !== orphan.path) ?? null);
+                } catch (e) {
+                  alert(`Failed to delete: ${errorMessage(e)}`);
+                }
+              }}>Move to Trash</button>
+            </div>
+          )))}
+        </div>
+
         {/* Cloud Sync Section */}
         <div className="settings-section">
           <label>Cloud Sync</label>
@@ -2116,6 +2512,22 @@ function App() {
               className={syncConfig?.provider === 'googledrive' ? 'active' : ''}
               onClick={() => setSyncConfig({ provider: 'googledrive', enabled: true, credentials: syncConfig?.provider === 'googledrive' ? syncConfig.credentials : {} })}
             >Google Drive</button>
+            <button 
+              className={syncConfig?.provider === 'webdav' ? 'active' : ''}
+              onClick={() => setSyncConfig({ provider: 'webdav', enabled: true, credentials: syncConfig?.provider === 'webdav' ? syncConfig.credentials : {} })}
+            >WebDAV</button>
+            <button 
+              className={syncConfig?.provider === 'git' ? 'active' : ''}
+              onClick={() => setSyncConfig({ provider: 'git', enabled: true, credentials: syncConfig?.provider === 'git' ? syncConfig.credentials : {} })}
+            >Git</button>
+            <button 
+              className={syncConfig?.provider === 'sftp' ? 'active' : ''}
+              onClick={() => setSyncConfig({ provider: 'sftp', enabled: true, credentials: syncConfig?.provider === 'sftp' ? syncConfig.credentials : {} })}
+            >SFTP</button>
+            <button 
+              className={syncConfig?.provider === 'icloud' ? 'active' : ''}
+              onClick={() => setSyncConfig({ provider: 'icloud', enabled: true, credentials: {} })}
+            >iCloud Drive</button>
           </div>
           {syncConfig && (
             <div className="sync-config">
@@ -2133,6 +2545,79 @@ function App() {
                   <input placeholder="Secret Key" type="password" value={syncConfig.credentials.secretKey || ''} onChange={e => setSyncConfig({
                     ...syncConfig, credentials: { ...syncConfig.credentials, secretKey: e.target.value }
                   })} />
+                  <input placeholder="Endpoint URL (optional, for B2, MinIO, Wasabi or R2)" value={syncConfig.credentials.endpointUrl || ''} onChange={e => setSyncConfig({
+                    ...syncConfig, credentials: { ...syncConfig.credentials, endpointUrl: e.target.value }
+                  })} />
+                  <label>
+                    <input
+                      type="checkbox"
+                      checked={syncConfig.credentials.forcePathStyle === 'true'}
+                      onChange={e => setSyncConfig({
+                        ...syncConfig, credentials: { ...syncConfig.credentials, forcePathStyle: String(e.target.checked) }
+                      })}
+                    />
+                    Path-style addressing (MinIO)
+                  </label>
+                </>
+              )}
+              {syncConfig.provider === 'webdav' && (
+                <>
+                  <input placeholder="Collection URL" value={syncConfig.credentials.url || ''} onChange={e => setSyncConfig({
+                    ...syncConfig, credentials: { ...syncConfig.credentials, url: e.target.value }
+                  })} />
+                  <input placeholder="Username" value={syncConfig.credentials.username || ''} onChange={e => setSyncConfig({
+                    ...syncConfig, credentials: { ...syncConfig.credentials, username: e.target.value }
+                  })} />
+                  <input placeholder="Password" type="password" value={syncConfig.credentials.password || ''} onChange={e => setSyncConfig({
+                    ...syncConfig, credentials: { ...syncConfig.credentials, password: e.target.value }
+                  })} />
+                </>
+              )}
+              {syncConfig.provider === 'git' && (
+                <>
+                  <input placeholder="Remote URL" value={syncConfig.credentials.remoteUrl || ''} onChange={e => setSyncConfig({
+                    ...syncConfig, credentials: { ...syncConfig.credentials, remoteUrl: e.target.value }
+                  })} />
+                  <input placeholder="Branch (main)" value={syncConfig.credentials.branch || ''} onChange={e => setSyncConfig({
+                    ...syncConfig, credentials: { ...syncConfig.credentials, branch: e.target.value }
+                  })} />
+                  <input placeholder="Username" value={syncConfig.credentials.username || ''} onChange={e => setSyncConfig({
+                    ...syncConfig, credentials: { ...syncConfig.credentials, username: e.target.value }
+                  })} />
+                  <input placeholder="Password or Token" type="password" value={syncConfig.credentials.password || ''} onChange={e => setSyncConfig({
+                    ...syncConfig, credentials: { ...syncConfig.credentials, password: e.target.value }
+                  })} />
+                  <input placeholder="SSH Key Path (optional)" value={syncConfig.credentials.sshKeyPath || ''} onChange={e => setSyncConfig({
+                    ...syncConfig, credentials: { ...syncConfig.credentials, sshKeyPath: e.target.value }
+                  })} />
+                </>
+              )}
+              {syncConfig.provider === 'sftp' && (
+                <>
+                  <input placeholder="Host" value={syncConfig.credentials.host || ''} onChange={e => setSyncConfig({
+                    ...syncConfig, credentials: { ...syncConfig.credentials, host: e.target.value }
+                  })} />
+                  <input placeholder="Port (22)" value={syncConfig.credentials.port || ''} onChange={e => setSyncConfig({
+                    ...syncConfig, credentials: { ...syncConfig.credentials, port: e.target.value }
+                  })} />
+                  <input placeholder="Username" value={syncConfig.credentials.username || ''} onChange={e => setSyncConfig({
+                    ...syncConfig, credentials: { ...syncConfig.credentials, username: e.target.value }
+                  })} />
+                  <input placeholder="Private Key Path (optional)" value={syncConfig.credentials.keyPath || ''} onChange={e => setSyncConfig({
+                    ...syncConfig, credentials: { ...syncConfig.credentials, keyPath: e.target.value }
+                  })} />
+                  {syncConfig.credentials.keyPath ? (
+                    <input placeholder="Key Passphrase (optional)" type="password" value={syncConfig.credentials.keyPassphrase || ''} onChange={e => setSyncConfig({
+                      ...syncConfig, credentials: { ...syncConfig.credentials, keyPassphrase: e.target.value }
+                    })} />
+                  ) : (
+                    <input placeholder="Password" type="password" value={syncConfig.credentials.password || ''} onChange={e => setSyncConfig({
+                      ...syncConfig, credentials: { ...syncConfig.credentials, password: e.target.value }
+                    })} />
+                  )}
+                  <input placeholder="Remote Folder" value={syncConfig.credentials.remoteDir || ''} onChange={e => setSyncConfig({
+                    ...syncConfig, credentials: { ...syncConfig.credentials, remoteDir: e.target.value }
+                  })} />
                 </>
               )}
               {(syncConfig.provider === 'dropbox' || syncConfig.provider === 'onedrive' || syncConfig.provider === 'googledrive') && (
@@ -2140,10 +2625,49 @@ function App() {
                   ...syncConfig, credentials: { ...syncConfig.credentials, accessToken: e.target.value }
                 })} />
               )}
+              {syncConfig.provider !== 'git' && (
+                <label>
+                  <input
+                    type="checkbox"
+                    checked={syncConfig.propagate_deletions ?? false}
+                    onChange={e => setSyncConfig({ ...syncConfig, propagate_deletions: e.target.checked })}
+                  />
+                  Sync deletions
+                </label>
+              )}
+              {syncConfig.provider !== 'icloud' && (
+                <input
+                  type="number"
+                  min="1"
+                  placeholder="Max speed in KB/s (optional)"
+                  value={syncConfig.max_bytes_per_sec ? Math.round(syncConfig.max_bytes_per_sec / 1024) : ''}
+                  onChange={e => setSyncConfig({
+                    ...syncConfig, max_bytes_per_sec: e.target.value ? parseInt(e.target.value) * 1024 : null
+                  })}
+                />
+              )}
+              {!['git', 'icloud'].includes(syncConfig.provider) && (
+                <input placeholder="Encryption Passphrase (optional, not saved)" type="password" value={syncPassphrase} onChange={e => setSyncPassphrase(e.target.value)} />
+              )}
               <button className="save-sync-btn" onClick={async () => {
                 await invoke('save_sync_config', { basePath: notesDir, config: syncConfig });
               }}>Save Sync Configuration</button>
               {syncConfig.last_sync && <p className="last-sync">Last sync: {new Date(syncConfig.last_sync).toLocaleString()}</p>}
+              <button onClick={async () => {
+                setSnapshots(await invoke<SnapshotInfo[]>('list_snapshots', { basePath: notesDir }));
+              }}>Show Sync Backups</button>
+              {snapshots && (snapshots.length === 0 ? <p className="last-sync">No backups yet</p> : snapshots.map(snapshot => (
+                <div key={snapshot.id} className="last-sync">
+                  {new Date(snapshot.created_at).toLocaleString()} ({snapshot.file_count} files){' '}
+                  <button onClick={async () => {
+                    if (!confirm(`Restore ${snapshot.file_count} files from this backup? Current versions are backed up first.`)) return;
+                    const restored = await invoke<number>('restore_snapshot', { basePath: notesDir, snapshotId: snapshot.id });
+                    setSyncStatus(`Restored ${restored} files`);
+                    setSnapshots(await invoke<SnapshotInfo[]>('list_snapshots', { basePath: notesDir }));
+                    if (selectedNotebook) loadNotes(selectedNotebook);
+                  }}>Restore</button>
+                </div>
+              )))}
             </div>
           )}
         </div>
@@ -2194,7 +2718,8 @@ function App() {
             <h3>Working with Notes</h3>
             <ul>
               <li><strong>Create a note:</strong> Click the + button in the Notes panel</li>
-              <li><strong>Rename/Delete:</strong> Right-click a note in the list</li>
+              <li><strong>Templates:</strong> Put notes in <code>.azimuth_templates</code> in your notes folder to pick them when creating a note. <code>{"{{title}}"}</code>, <code>{"{{date}}"}</code> and <code>{"{{time}}"}</code> are filled in</li>
+              <li><strong>Rename/Delete:</strong> Right-click a note in the list. Deleted notes go to the trash, restorable from Settings</li>
               <li><strong>Add images:</strong> Click the image icon in the toolbar, or paste/drag images</li>
               <li><strong>Favorite a note:</strong> Click the ☆ button in the toolbar</li>
               <li><strong>Tag notes:</strong> Click "+ Tag" below the toolbar</li>
@@ -2236,9 +2761,18 @@ function App() {
       // Also set directly on body to ensure it applies
       document.body.style.fontFamily = uiFont;
       document.body.style.fontSize = uiFontSize;
+      document.documentElement.dataset.theme = settings.theme || 'system';
     }
   }, [settings]);
 
+  // save_settings reports theme changes, including ones made from another window
+  useEffect(() => {
+    const unlisten = listen<AppSettings['theme']>('theme-changed', event => {
+      setSettings(prev => prev ? { ...prev, theme: event.payload } : prev);
+    });
+    return () => { unlisten.then(fn => fn()); };
+  }, []);
+
   return (
     <div className="app">
       {isChangingDirectory && (
@@ -2279,6 +2813,21 @@ function App() {
               <option value="">All notes</option>
               {allTags.map(tag => <option key={tag} value={tag}>#{tag}</option>)}
             </select>
+            {filterTag && (
+              <button
+                title={`Remove #${filterTag} from every note`}
+                onClick={async () => {
+                  if (!confirm(`Remove #${filterTag} from every note?`)) return;
+                  try {
+                    await invoke<number>('delete_tag', { basePath: notesDir, tag: filterTag });
+                    setAllTags(await invoke<string[]>('get_all_tags', { basePath: notesDir }));
+                    setFilterTag(null);
+                  } catch (e) {
+                    alert(`Failed to delete tag: ${errorMessage(e)}`);
+                  }
+                }}
+              >🗑️</button>
+            )}
           </div>
         )}
         
@@ -2344,14 +2893,14 @@ function App() {
                       const notebook = findNotebookByPath(notebooks, notebookPath);
                       if (notebook) {
                         setSelectedNotebook(notebook);
-                        const notesList = await invoke<Note[]>('list_notes', { notebookPath });
+                        const notesList = await invoke<Note[]>('list_notes', { notebookPath, basePath: notesDir });
                         setNotes(notesList);
                         const note = notesList.find(n => n.id === noteId);
                         if (note) { openNoteInTab(note); }
                       } else {
                         // Notebook not found in tree - try loading notes directly
                         try {
-                          const notesList = await invoke<Note[]>('list_notes', { notebookPath });
+                          const notesList = await invoke<Note[]>('list_notes', { notebookPath, basePath: notesDir });
                           setNotes(notesList);
                           const note = notesList.find(n => n.id === noteId);
                           if (note) { 
@@ -2458,6 +3007,12 @@ function App() {
                   }}
                   autoFocus
                 />
+                {templates.length > 0 && (
+                  <select value={newNoteTemplate} onChange={e => setNewNoteTemplate(e.target.value)} title="Template">
+                    <option value="">Blank note</option>
+                    {templates.map(template => <option key={template} value={template}>{template}</option>)}
+                  </select>
+                )}
                 <button onClick={createNote}>Create</button>
               </div>
             )}
@@ -2497,8 +3052,9 @@ function App() {
                               oldId: note.id, 
                               newId: renameValue.trim() 
                             });
+                            await reloadSettings();
                             // Refresh notes list
-                            const notesList = await invoke<Note[]>('list_notes', { notebookPath });
+                            const notesList = await invoke<Note[]>('list_notes', { notebookPath, basePath: notesDir });
                             setNotes(notesList);
                             // Update selected note if it was renamed
                             if (selectedNote?.id === note.id) {
@@ -2514,7 +3070,7 @@ function App() {
                             }
                           } catch (err) {
                             console.error('Failed to rename:', err);
-                            alert(`Failed to rename: ${err}`);
+                            alert(`Failed to rename: ${errorMessage(err)}`);
                           }
                           setRenamingNote(null);
                           setRenameValue('');
@@ -2549,6 +3105,7 @@ function App() {
                       setDropTarget(null);
                     }}
                   >
+                    {isNotePinned(note) && '📌 '}
                     {favorites.includes(`${note.folder}/${note.id}`) && '⭐ '}
                     {getFileIcon(note.id)} {note.id}
                   </li>
@@ -2592,6 +3149,9 @@ function App() {
               {syncConfig?.enabled && (
                 <button onClick={performSync} disabled={isSyncing}>{isSyncing ? '🔄 Syncing...' : '☁️ Sync'}</button>
               )}
+              {isSyncing && (
+                <button onClick={() => invoke('cancel_sync')}>✕ Cancel Sync</button>
+              )}
               <div className="toolbar-spacer" />
               {syncStatus && <span className="sync-status">{syncStatus}</span>}
             </div>
@@ -2689,8 +3249,14 @@ function App() {
                 {abbreviatePath(`${selectedNote.folder}/${selectedNote.id}`)}
               </span>
               <span className="status-spacer" />
-              <span className="status-item">Words: {getWordCount(content)}</span>
-              <span className="status-item">Characters: {getCharCount(content)}</span>
+              {noteStats && (
+                <>
+                  <span className="status-item">Words: {noteStats.words}</span>
+                  <span className="status-item">Characters: {noteStats.characters}</span>
+                  <span className="status-item">Lines: {noteStats.lines}</span>
+                  <span className="status-item">{noteStats.reading_minutes} min read</span>
+                </>
+              )}
               <span className="status-item">Size: {formatFileSize(new Blob([content]).size)}</span>
               <span className="status-spacer" />
               <span className="status-item hint">:date :today :time</span>
@@ -2706,6 +3272,20 @@ function App() {
       </main>
       
       {showSearch && <SearchModal />}
+      {noteVersions && (
+        <div className="modal-overlay" onClick={() => setNoteVersions(null)}>
+          <div className="modal" onClick={e => e.stopPropagation()}>
+            <h2>Note History</h2>
+            {noteVersions.length === 0 ? <p className="last-sync">No earlier versions yet</p> : noteVersions.map(version => (
+              <div key={version.timestamp} className="last-sync">
+                {new Date(version.created_at).toLocaleString()} ({formatFileSize(version.size)}){' '}
+                <button onClick={() => restoreNoteVersion(version)}>Restore</button>
+              </div>
+            ))}
+            <button className="close-btn" onClick={() => setNoteVersions(null)}>Close</button>
+          </div>
+        </div>
+      )}
       {showCommandPalette && <CommandPalette />}
       {settingsModalContent}
       {showHelp && <HelpModal />}
@@ -5,6 +5,13 @@ export interface Note {
   folder: string;
   created_at: string;
   updated_at: string;
+  frontmatter?: Record<string, unknown>;
+}
+
+// How commands reject; `message` is meant for the user
+export interface AzimuthError {
+  kind: 'io' | 'not_found' | 'already_exists' | 'permission_denied' | 'invalid_path' | 'invalid_input' | 'serde' | 'network' | 'other';
+  message: string;
 }
 
 export interface Notebook {
@@ -19,6 +26,8 @@ export interface SyncConfig {
   enabled: boolean;
   credentials: Record<string, string>;
   last_sync?: string;
+  propagate_deletions?: boolean;
+  max_bytes_per_sec?: number | null;
 }
 
 export interface AppSettings {
@@ -33,7 +42,25 @@ export interface AppSettings {
   tags: Record<string, string[]>;
   notebook_styles: Record<string, NotebookStyle>;
   pinned_folders: string[];
+  pinned_notes?: string[];
   auto_save: boolean;
+  note_reminders?: Record<string, string>;
+  note_metadata?: Record<string, Record<string, unknown>>;
+  archived?: string[];
+  exclude_archived?: boolean;
+  ignored_dirs?: string[];
+  max_notebooks?: number;
+  max_entries_to_scan?: number;
+  trash_retention_days?: number;
+  max_attachment_bytes?: number | null;
+  theme?: 'light' | 'dark' | 'system';
+}
+
+export interface TagNode {
+  name: string;
+  tag: string;
+  count: number;
+  children: TagNode[];
 }
 
 export interface NotebookStyle {
@@ -41,13 +68,47 @@ export interface NotebookStyle {
   color: string;
 }
 
+export interface NoteMetadata {
+  id: string;
+  title: string;
+  folder: string;
+  created_at: string;
+  updated_at: string;
+  size: number;
+}
+
+export interface NotePage {
+  notes: Note[];
+  total: number;
+}
+
+export interface UnresolvedLink {
+  note: string;
+  target: string;
+}
+
+export interface ObsidianImportResult {
+  notebook: Notebook;
+  notes_imported: number;
+  attachments_copied: number;
+  unresolved_links: UnresolvedLink[];
+}
+
+export interface SearchPage {
+  results: SearchResult[];
+  total_matches: number;
+}
+
 export interface SearchResult {
   note_id: string;
   note_title: string;
   notebook_path: string;
   notebook_name: string;
   snippet: string;
+  snippets: string[];
   match_count: number;
+  line_number: number;
+  fuzzy_score?: number | null;
 }
 
 export interface SyncStatus {
@@ -58,6 +119,46 @@ export interface SyncStatus {
   conflicts: SyncConflict[];
 }
 
+export interface PlannedChange {
+  path: string;
+  change: 'new' | 'modified' | 'deleted';
+}
+
+export interface SyncPreview {
+  remote: PlannedChange[];
+  local: PlannedChange[];
+  conflicts: string[];
+}
+
+export interface SnapshotInfo {
+  id: string;
+  created_at: string;
+  file_count: number;
+}
+
+export interface TrashEntry {
+  id: string;
+  deleted_at: string;
+  note_path: string;
+  file_count: number;
+}
+
+export interface OrphanedAttachment {
+  path: string;
+  note: string;
+  size: number;
+}
+
+export interface SavedAttachment {
+  url: string;
+  size: number;
+}
+
+export interface Thumbnail {
+  mime: string;
+  data: string;
+}
+
 export interface SyncConflict {
   file_path: string;
   local_modified: string;
@@ -71,6 +172,27 @@ export interface ConflictResolution {
   resolution: 'keep_local' | 'keep_remote' | 'keep_both';
 }
 
+export interface NotebookStats {
+  notes: number;
+  attachments: number;
+  total_size: number;
+  last_modified?: string | null;
+  by_extension: Record<string, number>;
+}
+
+export interface NoteStats {
+  words: number;
+  characters: number;
+  lines: number;
+  reading_minutes: number;
+}
+
+export interface NoteVersion {
+  timestamp: string;
+  created_at: string;
+  size: number;
+}
+
 export interface OpenTab {
   note: Note;
   content: string;